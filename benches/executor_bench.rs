@@ -0,0 +1,275 @@
+use chrono::{Duration, Utc};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use flux::client::HttpClient;
+use flux::executor::extract_json_path;
+use flux::metrics::{MetricsCollector, MetricsSummary, RequestResult};
+use flux::reporter::Report;
+use hdrhistogram::Histogram;
+use jsonpath_rust::{JsonPathFinder, JsonPathInst};
+use regex::Regex;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Barrier, Mutex};
+use std::thread;
+
+const RECORDS_PER_CALLER: usize = 1000;
+const DISTRIBUTION_RESULTS: usize = 1_000_000;
+
+/// Upper bounds of the latency distribution buckets, as in the HTML report
+const BUCKET_BOUNDS: [u64; 5] = [50, 100, 200, 500, 1000];
+
+fn sample_result(latency_ms: u64) -> RequestResult {
+    RequestResult {
+        scenario_name: Some("bench".to_string()),
+        latency_ms,
+        status_code: 200,
+        error: None,
+        request_start_timestamp: Utc::now(),
+        request_end_timestamp: Utc::now(),
+        sse_total_ms: None,
+        wall_latency_ms: None,
+        timing: None,
+        request_id: None,
+        content_encoding: None,
+        is_hook: false,
+    }
+}
+
+/// Proposed collector: each caller buffers results and stores them a batch
+/// at a time under one lock
+struct BatchedCollector {
+    results: Mutex<Vec<RequestResult>>,
+    histogram: Mutex<Histogram<u64>>,
+}
+
+impl BatchedCollector {
+    const BATCH_SIZE: usize = 64;
+
+    fn new() -> Self {
+        Self {
+            results: Mutex::new(Vec::new()),
+            histogram: Mutex::new(Histogram::new_with_bounds(1, 60_000, 3).unwrap()),
+        }
+    }
+
+    fn store(&self, batch: &mut Vec<RequestResult>) {
+        let mut histogram = self.histogram.lock().unwrap();
+        for result in batch.iter() {
+            let _ = histogram.record(result.latency_ms);
+        }
+        self.results.lock().unwrap().append(batch);
+    }
+}
+
+/// Record from `callers` threads released at the same time
+fn run_callers<F>(callers: usize, caller: F)
+where
+    F: Fn(usize) + Sync,
+{
+    let barrier = Barrier::new(callers);
+
+    thread::scope(|scope| {
+        for id in 0..callers {
+            let barrier = &barrier;
+            let caller = &caller;
+            scope.spawn(move || {
+                barrier.wait();
+                caller(id);
+            });
+        }
+    });
+}
+
+fn bench_record(c: &mut Criterion) {
+    let mut group = c.benchmark_group("record");
+    group.sample_size(10);
+
+    for callers in [1, 16, 256] {
+        group.throughput(Throughput::Elements((callers * RECORDS_PER_CALLER) as u64));
+
+        group.bench_with_input(BenchmarkId::new("current", callers), &callers, |b, &n| {
+            b.iter(|| {
+                let collector = MetricsCollector::new();
+                run_callers(n, |id| {
+                    for i in 0..RECORDS_PER_CALLER {
+                        collector.record(sample_result((id + i) as u64 % 1000 + 1));
+                    }
+                });
+                collector.generate_summary()
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("batched", callers), &callers, |b, &n| {
+            b.iter(|| {
+                let collector = BatchedCollector::new();
+                run_callers(n, |id| {
+                    let mut batch = Vec::with_capacity(BatchedCollector::BATCH_SIZE);
+                    for i in 0..RECORDS_PER_CALLER {
+                        batch.push(sample_result((id + i) as u64 % 1000 + 1));
+                        if batch.len() == BatchedCollector::BATCH_SIZE {
+                            collector.store(&mut batch);
+                        }
+                    }
+                    collector.store(&mut batch);
+                });
+            })
+        });
+    }
+
+    group.finish();
+}
+
+/// 1 KB JSON template cycling through `count` placeholders, with the
+/// variables they refer to
+fn template(count: usize) -> (String, HashMap<String, String>) {
+    let variables: HashMap<String, String> = (0..count)
+        .map(|i| (format!("var_{}", i), format!("value-{}", i)))
+        .collect();
+
+    let mut template = String::from("{");
+    let mut i = 0;
+    while template.len() < 1024 {
+        template.push_str(&format!(
+            r#""field_{}": "{{{{ var_{} }}}}", "#,
+            i,
+            i % count
+        ));
+        i += 1;
+    }
+    template.push('}');
+
+    (template, variables)
+}
+
+/// Proposed substitution: one pre-compiled regex pass
+fn substitute_with_regex(
+    pattern: &Regex,
+    template: &str,
+    variables: &HashMap<String, String>,
+) -> String {
+    pattern
+        .replace_all(template, |caps: &regex::Captures| {
+            variables
+                .get(&caps[1])
+                .cloned()
+                .unwrap_or_else(|| caps[0].to_string())
+        })
+        .into_owned()
+}
+
+fn bench_substitute(c: &mut Criterion) {
+    let mut group = c.benchmark_group("substitute_variables_1kb");
+    let client = HttpClient::new().unwrap();
+    let pattern = Regex::new(r"\{\{\s*([A-Za-z0-9_$.]+)\s*\}\}").unwrap();
+
+    for count in [10, 100] {
+        let (template, variables) = template(count);
+
+        group.bench_with_input(BenchmarkId::new("current", count), &template, |b, t| {
+            b.iter(|| client.substitute_variables(t, &variables).unwrap())
+        });
+
+        group.bench_with_input(BenchmarkId::new("regex", count), &template, |b, t| {
+            b.iter(|| substitute_with_regex(&pattern, t, &variables))
+        });
+    }
+
+    group.finish();
+}
+
+/// Proposed distribution: sort the latencies once, then find each bucket
+/// boundary by binary search
+fn distribution_from_sorted(sorted: &[u64]) -> Vec<usize> {
+    let mut counts = Vec::with_capacity(BUCKET_BOUNDS.len() + 1);
+    let mut previous = 0;
+    for bound in BUCKET_BOUNDS {
+        let end = sorted.partition_point(|&latency| latency < bound);
+        counts.push(end - previous);
+        previous = end;
+    }
+    counts.push(sorted.len() - previous);
+    counts
+}
+
+fn bench_distribution(c: &mut Criterion) {
+    let mut group = c.benchmark_group("latency_distribution_1m");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(DISTRIBUTION_RESULTS as u64));
+
+    let start = Utc::now();
+    let results: Vec<RequestResult> = (0..DISTRIBUTION_RESULTS)
+        .map(|i| sample_result((i as u64 * 7919) % 2000))
+        .collect();
+    let report = Report {
+        summary: MetricsSummary::from_results(&results, start, start + Duration::seconds(60)),
+        results,
+        phase_summaries: Vec::new(),
+        request_summaries: Vec::new(),
+    };
+
+    group.bench_function("current", |b| {
+        b.iter(|| report.calculate_latency_distribution())
+    });
+
+    // The sort would happen once when results are collected, so it is not
+    // part of the measurement
+    let mut sorted: Vec<u64> = report.results.iter().map(|r| r.latency_ms).collect();
+    sorted.sort_unstable();
+    group.bench_function("pre_sorted", |b| {
+        b.iter(|| distribution_from_sorted(&sorted))
+    });
+
+    group.finish();
+}
+
+/// JSON body of about 10 KB with the value to extract near the end
+fn json_body() -> String {
+    let items: Vec<serde_json::Value> = (0..150)
+        .map(|i| serde_json::json!({ "id": i, "name": format!("item-{}", i), "tags": ["a", "b"] }))
+        .collect();
+    serde_json::json!({ "items": items, "session": { "token": "abc123" } }).to_string()
+}
+
+/// Proposed extraction: parse the body and the path once each
+fn extract_parsed_once(body: &str, json_path: &str) -> Option<String> {
+    let json = serde_json::from_str(body).ok()?;
+    let path = JsonPathInst::from_str(json_path).ok()?;
+    let finder = JsonPathFinder::new(Box::new(json), Box::new(path));
+
+    match finder.find() {
+        serde_json::Value::Array(values) => values.into_iter().next().map(|v| match v {
+            serde_json::Value::String(s) => s,
+            other => other.to_string(),
+        }),
+        _ => None,
+    }
+}
+
+fn bench_extract(c: &mut Criterion) {
+    let mut group = c.benchmark_group("extract_json_path_10kb");
+    let body = json_body();
+    let json_path = "$.session.token";
+    assert_eq!(
+        extract_json_path(&body, json_path).unwrap().as_deref(),
+        Some("abc123")
+    );
+
+    group.throughput(Throughput::Bytes(body.len() as u64));
+    group.bench_function("current", |b| {
+        b.iter(|| extract_json_path(&body, json_path).unwrap())
+    });
+    group.bench_function("parse_once", |b| {
+        b.iter(|| extract_parsed_once(&body, json_path))
+    });
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_record,
+    bench_substitute,
+    bench_distribution,
+    bench_extract
+);
+criterion_main!(benches);
@@ -0,0 +1,94 @@
+use chrono::Utc;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use flux::metrics::{MetricsCollector, RequestResult};
+use hdrhistogram::Histogram;
+use std::sync::{Barrier, Mutex};
+use std::thread;
+
+const SENDERS: usize = 1000;
+const RECORDS_PER_SENDER: usize = 100;
+
+/// The previous collector design: every record takes both storage locks
+struct MutexCollector {
+    results: Mutex<Vec<RequestResult>>,
+    histogram: Mutex<Histogram<u64>>,
+}
+
+impl MutexCollector {
+    fn new() -> Self {
+        Self {
+            results: Mutex::new(Vec::new()),
+            histogram: Mutex::new(Histogram::new_with_bounds(1, 60_000, 3).unwrap()),
+        }
+    }
+
+    fn record(&self, result: RequestResult) {
+        let latency = result.latency_ms;
+        self.results.lock().unwrap().push(result);
+        let _ = self.histogram.lock().unwrap().record(latency);
+    }
+}
+
+fn sample_result(latency_ms: u64) -> RequestResult {
+    RequestResult {
+        scenario_name: Some("bench".to_string()),
+        latency_ms,
+        status_code: 200,
+        error: None,
+        request_start_timestamp: Utc::now(),
+        request_end_timestamp: Utc::now(),
+        sse_total_ms: None,
+        wall_latency_ms: None,
+        timing: None,
+        request_id: None,
+        content_encoding: None,
+        is_hook: false,
+    }
+}
+
+/// Record from `SENDERS` threads released at the same time
+fn run_senders<F>(record: F)
+where
+    F: Fn(RequestResult) + Sync,
+{
+    let barrier = Barrier::new(SENDERS);
+
+    thread::scope(|scope| {
+        for sender in 0..SENDERS {
+            let barrier = &barrier;
+            let record = &record;
+            scope.spawn(move || {
+                barrier.wait();
+                for i in 0..RECORDS_PER_SENDER {
+                    record(sample_result((sender + i) as u64 % 1000 + 1));
+                }
+            });
+        }
+    });
+}
+
+fn bench_record(c: &mut Criterion) {
+    let mut group = c.benchmark_group("record_1000_senders");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements((SENDERS * RECORDS_PER_SENDER) as u64));
+
+    group.bench_function(BenchmarkId::new("mutex", SENDERS), |b| {
+        b.iter(|| {
+            let collector = MutexCollector::new();
+            run_senders(|r| collector.record(r));
+        })
+    });
+
+    group.bench_function(BenchmarkId::new("channel", SENDERS), |b| {
+        b.iter(|| {
+            let collector = MetricsCollector::new();
+            run_senders(|r| collector.record(r));
+            collector.generate_summary()
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_record);
+criterion_main!(benches);
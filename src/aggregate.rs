@@ -0,0 +1,152 @@
+use crate::reporter::Report;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Spread of one summary metric across several runs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldStats {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    /// Sample standard deviation (0 for a single run)
+    pub stddev: f64,
+}
+
+/// Run-to-run statistics for every numeric summary field
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AggregatedReport {
+    pub run_count: usize,
+    pub first_start_time: DateTime<Utc>,
+    pub last_end_time: DateTime<Utc>,
+    /// Stats keyed by `MetricsSummary` field name. Optional fields are only
+    /// included when every run has them.
+    pub metrics: BTreeMap<String, FieldStats>,
+}
+
+impl AggregatedReport {
+    /// Aggregate the summaries of several runs of the same test
+    pub fn from_reports(reports: &[Report]) -> Result<Self> {
+        let first_start_time = reports
+            .iter()
+            .map(|r| r.summary.start_time)
+            .min()
+            .ok_or_else(|| anyhow::anyhow!("No reports to aggregate"))?;
+        let last_end_time = reports
+            .iter()
+            .map(|r| r.summary.end_time)
+            .max()
+            .unwrap_or(first_start_time);
+
+        // Walk the serialized summaries so new numeric fields are picked up
+        // without listing them here
+        let mut values: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+        for report in reports {
+            if let serde_json::Value::Object(fields) = serde_json::to_value(&report.summary)? {
+                for (name, value) in fields {
+                    if let Some(number) = value.as_f64() {
+                        values.entry(name).or_default().push(number);
+                    }
+                }
+            }
+        }
+
+        let metrics = values
+            .into_iter()
+            .filter(|(_, samples)| samples.len() == reports.len())
+            .map(|(name, samples)| (name, field_stats(&samples)))
+            .collect();
+
+        Ok(Self {
+            run_count: reports.len(),
+            first_start_time,
+            last_end_time,
+            metrics,
+        })
+    }
+
+    /// Write the aggregated report as pretty-printed JSON
+    pub fn generate_json(&self, output_path: &str) -> Result<()> {
+        // Ensure parent directory exists
+        if let Some(parent) = Path::new(output_path).parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(output_path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Min, max, mean and sample standard deviation of a non-empty sample
+fn field_stats(samples: &[f64]) -> FieldStats {
+    let n = samples.len() as f64;
+    let mean = samples.iter().sum::<f64>() / n;
+    let variance = if samples.len() > 1 {
+        samples.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0)
+    } else {
+        0.0
+    };
+
+    FieldStats {
+        min: samples.iter().copied().fold(f64::INFINITY, f64::min),
+        max: samples.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        mean,
+        stddev: variance.sqrt(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::{MetricsSummary, RequestResult};
+
+    fn report(latency_ms: u64, apdex: Option<f64>) -> Report {
+        let now = Utc::now();
+        let results = vec![RequestResult {
+            scenario_name: None,
+            latency_ms,
+            status_code: 200,
+            error: None,
+            request_start_timestamp: now,
+            request_end_timestamp: now,
+            sse_total_ms: None,
+            wall_latency_ms: None,
+            timing: None,
+            request_id: None,
+            content_encoding: None,
+            is_hook: false,
+        }];
+        let mut summary = MetricsSummary::from_results(&results, now, now);
+        summary.apdex = apdex;
+
+        Report {
+            summary,
+            results,
+            phase_summaries: Vec::new(),
+            request_summaries: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_aggregate_reports() {
+        let reports = vec![
+            report(100, Some(1.0)),
+            report(200, None),
+            report(300, Some(0.5)),
+        ];
+
+        let aggregated = AggregatedReport::from_reports(&reports).unwrap();
+        let p95 = &aggregated.metrics["p95_latency_ms"];
+
+        assert_eq!(aggregated.run_count, 3);
+        assert_eq!(p95.min, 100.0);
+        assert_eq!(p95.max, 300.0);
+        assert_eq!(p95.mean, 200.0);
+        assert_eq!(p95.stddev, 100.0);
+        assert!(!aggregated.metrics.contains_key("apdex"));
+        assert!(!aggregated.metrics.contains_key("start_time"));
+    }
+}
@@ -0,0 +1,107 @@
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+
+/// Capacity of the first chunk as a power of two; each later chunk is
+/// twice the size of the one before
+const FIRST_CHUNK_BITS: u32 = 10;
+
+/// Chunks needed to address every `usize` index
+const MAX_CHUNKS: usize = (usize::BITS - FIRST_CHUNK_BITS) as usize;
+
+type Chunk<T> = Box<[OnceLock<T>]>;
+
+/// Append-only vector pushed to through `&self` without a lock. Items live
+/// in chunks allocated once when the write cursor first reaches them, so
+/// appends never move earlier items and readers never block writers.
+pub struct AppendVec<T> {
+    chunks: Box<[OnceLock<Chunk<T>>]>,
+    /// Index of the next item to be written
+    cursor: AtomicUsize,
+}
+
+impl<T> AppendVec<T> {
+    pub fn new() -> Self {
+        Self {
+            chunks: (0..MAX_CHUNKS).map(|_| OnceLock::new()).collect(),
+            cursor: AtomicUsize::new(0),
+        }
+    }
+
+    /// Append an item in O(1), allocating the next chunk when the current
+    /// one is full
+    pub fn push(&self, item: T) {
+        let index = self.cursor.fetch_add(1, Ordering::Relaxed);
+        let (chunk, offset) = locate(index);
+        let chunk = self.chunks[chunk].get_or_init(|| {
+            (0..1usize << (chunk as u32 + FIRST_CHUNK_BITS))
+                .map(|_| OnceLock::new())
+                .collect()
+        });
+        let _ = chunk[offset].set(item);
+    }
+
+    /// Items in the order they were appended, up to the first whose push
+    /// is still in progress
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        let written = self.cursor.load(Ordering::Acquire);
+        (0..written).map_while(move |index| {
+            let (chunk, offset) = locate(index);
+            self.chunks[chunk].get()?[offset].get()
+        })
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for AppendVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T> Default for AppendVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Chunk holding `index` and its offset within the chunk
+fn locate(index: usize) -> (usize, usize) {
+    let position = index + (1 << FIRST_CHUNK_BITS);
+    let bits = usize::BITS - 1 - position.leading_zeros();
+    let chunk = (bits - FIRST_CHUNK_BITS) as usize;
+    (chunk, position - (1 << bits))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_locate_across_chunks() {
+        assert_eq!(locate(0), (0, 0));
+        assert_eq!(locate(1023), (0, 1023));
+        assert_eq!(locate(1024), (1, 0));
+        assert_eq!(locate(3071), (1, 2047));
+        assert_eq!(locate(3072), (2, 0));
+    }
+
+    #[test]
+    fn test_concurrent_pushes() {
+        let items = AppendVec::new();
+        thread::scope(|scope| {
+            for thread_id in 0..8 {
+                let items = &items;
+                scope.spawn(move || {
+                    for i in 0..1000 {
+                        items.push(thread_id * 1000 + i);
+                    }
+                });
+            }
+        });
+
+        let mut pushed: Vec<usize> = items.iter().copied().collect();
+        pushed.sort_unstable();
+        assert_eq!(pushed, (0..8000).collect::<Vec<_>>());
+    }
+}
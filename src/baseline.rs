@@ -0,0 +1,302 @@
+use crate::metrics::MetricsSummary;
+use serde::Serialize;
+
+/// Change of a single metric between a baseline and the current run
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricDelta {
+    pub name: &'static str,
+    pub baseline: f64,
+    pub current: f64,
+    pub change_pct: f64,
+    /// Moved in the unfavourable direction by any amount
+    pub worsened: bool,
+    /// Moved in the favourable direction
+    pub improved: bool,
+    /// Worsened by more than the tolerance
+    pub regressed: bool,
+}
+
+/// Which direction of change is an improvement
+#[derive(Debug, Clone, Copy)]
+enum Better {
+    Higher,
+    Lower,
+    Neither,
+}
+
+/// Compare the key metrics of the current run against a baseline
+pub fn compare(
+    baseline: &MetricsSummary,
+    current: &MetricsSummary,
+    tolerance_pct: f64,
+) -> Vec<MetricDelta> {
+    vec![
+        delta(
+            "P95 Latency (ms)",
+            baseline.p95_latency_ms as f64,
+            current.p95_latency_ms as f64,
+            Better::Lower,
+            tolerance_pct,
+        ),
+        delta(
+            "Throughput (req/s)",
+            baseline.throughput_rps,
+            current.throughput_rps,
+            Better::Higher,
+            tolerance_pct,
+        ),
+        delta(
+            "Error Rate (%)",
+            baseline.error_rate,
+            current.error_rate,
+            Better::Lower,
+            tolerance_pct,
+        ),
+    ]
+}
+
+/// Compare every numeric summary metric of two reports. Optional metrics
+/// are only compared when both reports have them.
+pub fn compare_all(
+    baseline: &MetricsSummary,
+    current: &MetricsSummary,
+    tolerance_pct: f64,
+) -> Vec<MetricDelta> {
+    let mut metrics: Vec<(&'static str, f64, f64, Better)> = vec![
+        (
+            "Total Requests",
+            baseline.total_requests as f64,
+            current.total_requests as f64,
+            Better::Higher,
+        ),
+        (
+            "Successful Requests",
+            baseline.successful_requests as f64,
+            current.successful_requests as f64,
+            Better::Higher,
+        ),
+        (
+            "Failed Requests",
+            baseline.failed_requests as f64,
+            current.failed_requests as f64,
+            Better::Lower,
+        ),
+        (
+            "Duration (s)",
+            baseline.total_duration_secs,
+            current.total_duration_secs,
+            Better::Neither,
+        ),
+        (
+            "Throughput (req/s)",
+            baseline.throughput_rps,
+            current.throughput_rps,
+            Better::Higher,
+        ),
+        (
+            "Error Rate (%)",
+            baseline.error_rate,
+            current.error_rate,
+            Better::Lower,
+        ),
+        (
+            "Min Latency (ms)",
+            baseline.min_latency_ms as f64,
+            current.min_latency_ms as f64,
+            Better::Lower,
+        ),
+        (
+            "Mean Latency (ms)",
+            baseline.mean_latency_ms,
+            current.mean_latency_ms,
+            Better::Lower,
+        ),
+        (
+            "Median Latency (ms)",
+            baseline.median_latency_ms,
+            current.median_latency_ms,
+            Better::Lower,
+        ),
+        (
+            "Std Dev (ms)",
+            baseline.stddev_latency_ms,
+            current.stddev_latency_ms,
+            Better::Lower,
+        ),
+        (
+            "P50 Latency (ms)",
+            baseline.p50_latency_ms as f64,
+            current.p50_latency_ms as f64,
+            Better::Lower,
+        ),
+        (
+            "P90 Latency (ms)",
+            baseline.p90_latency_ms as f64,
+            current.p90_latency_ms as f64,
+            Better::Lower,
+        ),
+        (
+            "P95 Latency (ms)",
+            baseline.p95_latency_ms as f64,
+            current.p95_latency_ms as f64,
+            Better::Lower,
+        ),
+        (
+            "P99 Latency (ms)",
+            baseline.p99_latency_ms as f64,
+            current.p99_latency_ms as f64,
+            Better::Lower,
+        ),
+        (
+            "Max Latency (ms)",
+            baseline.max_latency_ms as f64,
+            current.max_latency_ms as f64,
+            Better::Lower,
+        ),
+    ];
+
+    if let (Some(a), Some(b)) = (baseline.apdex, current.apdex) {
+        metrics.push(("Apdex", a, b, Better::Higher));
+    }
+    if let (Some(a), Some(b)) = (baseline.over_sla_percent, current.over_sla_percent) {
+        metrics.push(("Over SLA (%)", a, b, Better::Lower));
+    }
+
+    metrics
+        .into_iter()
+        .map(|(name, a, b, better)| delta(name, a, b, better, tolerance_pct))
+        .collect()
+}
+
+/// Build a delta, flagging it when the metric worsened beyond the tolerance
+fn delta(
+    name: &'static str,
+    baseline: f64,
+    current: f64,
+    better: Better,
+    tolerance_pct: f64,
+) -> MetricDelta {
+    let change_pct = percent_change(baseline, current);
+    let worsened_pct = match better {
+        Better::Higher => -change_pct,
+        Better::Lower => change_pct,
+        Better::Neither => 0.0,
+    };
+
+    MetricDelta {
+        name,
+        baseline,
+        current,
+        change_pct,
+        worsened: worsened_pct > 0.0,
+        improved: worsened_pct < 0.0,
+        regressed: worsened_pct > tolerance_pct,
+    }
+}
+
+/// Percentage change from `from` to `to`
+pub fn percent_change(from: f64, to: f64) -> f64 {
+    if from == 0.0 {
+        if to == 0.0 {
+            0.0
+        } else {
+            100.0_f64.copysign(to)
+        }
+    } else {
+        (to - from) / from * 100.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use std::collections::HashMap;
+
+    fn summary(p95: u64, throughput: f64, error_rate: f64) -> MetricsSummary {
+        MetricsSummary {
+            total_requests: 100,
+            successful_requests: 100,
+            failed_requests: 0,
+            total_duration_secs: 10.0,
+            throughput_rps: throughput,
+            min_latency_ms: 1,
+            max_latency_ms: p95,
+            mean_latency_ms: 50.0,
+            median_latency_ms: 50.0,
+            stddev_latency_ms: 0.0,
+            p50_latency_ms: 50,
+            p90_latency_ms: p95,
+            p95_latency_ms: p95,
+            p99_latency_ms: p95,
+            error_rate,
+            start_time: Utc::now(),
+            end_time: Utc::now(),
+            sampled_requests: None,
+            sample_rate: None,
+            error_breakdown: HashMap::new(),
+            timeout_count: 0,
+            skipped_requests: 0,
+            rate_limited_count: 0,
+            aborted_chains: 0,
+            chain_timeouts: 0,
+            throttled_requests: 0,
+            dns_cache_hits: 0,
+            clamped_histogram_values: 0,
+            hook_requests: 0,
+            failed_hook_requests: 0,
+            timing_percentiles: Vec::new(),
+            connection_reuse_rate: None,
+            first_error_time: None,
+            last_error_time: None,
+            sample_errors: Vec::new(),
+            gzip_responses: false,
+            latency_histogram: None,
+            max_outstanding: None,
+            dropped_iterations: None,
+            peak_concurrency: None,
+            apdex: None,
+            over_sla_count: None,
+            over_sla_percent: None,
+        }
+    }
+
+    #[test]
+    fn test_compare_flags_regressions() {
+        let baseline = summary(100, 500.0, 1.0);
+        let current = summary(120, 480.0, 1.0);
+
+        let deltas = compare(&baseline, &current, 10.0);
+
+        assert_eq!(deltas[0].change_pct, 20.0);
+        assert!(deltas[0].regressed);
+        assert!(!deltas[1].regressed);
+        assert!(!deltas[2].regressed);
+    }
+
+    #[test]
+    fn test_compare_all_directions() {
+        let baseline = summary(100, 500.0, 2.0);
+        let current = summary(150, 600.0, 2.0);
+
+        let deltas = compare_all(&baseline, &current, 25.0);
+        let find = |name: &str| deltas.iter().find(|d| d.name == name).unwrap();
+
+        assert!(find("P95 Latency (ms)").worsened);
+        assert!(find("P95 Latency (ms)").regressed);
+        assert!(!find("Throughput (req/s)").worsened);
+        assert!(!find("Error Rate (%)").worsened);
+        assert!(!find("Duration (s)").worsened);
+        assert!(deltas.iter().all(|d| d.name != "Apdex"));
+    }
+
+    #[test]
+    fn test_compare_ignores_improvements() {
+        let baseline = summary(100, 500.0, 2.0);
+        let current = summary(50, 900.0, 0.0);
+
+        let deltas = compare(&baseline, &current, 0.0);
+
+        assert!(deltas.iter().all(|d| !d.regressed));
+    }
+}
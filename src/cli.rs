@@ -0,0 +1,225 @@
+use clap::{Parser, Subcommand};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+/// Command line arguments
+#[derive(Debug, Parser)]
+#[command(
+    name = "flux",
+    version,
+    about = "High-performance container-native load testing"
+)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Path to the YAML configuration file
+    #[arg(default_value = "/app/config.yaml")]
+    pub config: PathBuf,
+
+    /// Previous JSON report to compare this run against
+    #[arg(long, value_name = "PATH")]
+    pub baseline: Option<PathBuf>,
+
+    /// Allowed regression (in percent) before the baseline check fails
+    #[arg(long, value_name = "PCT", default_value_t = 10.0)]
+    pub baseline_tolerance: f64,
+
+    /// Write all reports into this directory, overriding the configured paths
+    #[arg(long, value_name = "DIR", conflicts_with_all = ["output_json", "output_html"])]
+    pub output_dir: Option<PathBuf>,
+
+    /// Override the JSON report path
+    #[arg(long, value_name = "PATH")]
+    pub output_json: Option<String>,
+
+    /// Override the HTML report path
+    #[arg(long, value_name = "PATH")]
+    pub output_html: Option<String>,
+
+    /// Plain line-based output without colors or progress bars
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    /// Stream each request result, then the summary, to stdout as NDJSON
+    /// while the test runs, instead of showing the terminal UI
+    #[arg(long)]
+    pub events: bool,
+
+    /// Send each request once and print the outcome instead of running the
+    /// test; exits with code 1 if any request fails
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+/// Report tooling and distributed run subcommands
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run a test on several agents at once and merge their reports
+    Coordinate {
+        /// Path to the YAML configuration file
+        config: PathBuf,
+
+        /// Agent addresses as `host[:port]`, comma-separated
+        #[arg(long, required = true, value_delimiter = ',')]
+        workers: Vec<String>,
+
+        /// Shared token the agents were started with
+        #[arg(long, env = "FLUX_AGENT_TOKEN", hide_env_values = true)]
+        token: String,
+    },
+
+    /// Wait for a coordinator and run the tests it sends
+    Agent {
+        /// Address to listen on; use 0.0.0.0 to accept remote coordinators
+        #[arg(long, value_name = "ADDR", default_value = "127.0.0.1:7070")]
+        listen: SocketAddr,
+
+        /// Shared token every request must present as a bearer token
+        #[arg(long, env = "FLUX_AGENT_TOKEN", hide_env_values = true)]
+        token: String,
+    },
+
+    /// Merge JSON reports from multiple runners into a single report
+    Merge {
+        /// JSON reports to merge
+        #[arg(required = true)]
+        reports: Vec<PathBuf>,
+
+        /// Merged JSON report path
+        #[arg(short, long, value_name = "PATH")]
+        output: String,
+
+        /// Also render the merged report as HTML
+        #[arg(long, value_name = "PATH")]
+        html: Option<String>,
+    },
+
+    /// Summarize run-to-run variance across several JSON reports
+    Aggregate {
+        /// JSON reports from repeated runs of the same test
+        #[arg(required = true)]
+        reports: Vec<PathBuf>,
+
+        /// Aggregated JSON report path
+        #[arg(short, long, value_name = "PATH")]
+        output: String,
+
+        /// Also render the aggregated report as HTML
+        #[arg(long, value_name = "PATH")]
+        html: Option<String>,
+    },
+
+    /// Compare two JSON reports metric by metric
+    Compare {
+        /// Report to compare against (e.g. before a deployment)
+        baseline: PathBuf,
+
+        /// Report being compared (e.g. after a deployment)
+        current: PathBuf,
+
+        /// Exit with code 1 if any metric worsens by more than this percentage
+        #[arg(long, value_name = "PCT")]
+        assert_max_regression_pct: Option<f64>,
+
+        /// Write a side-by-side HTML comparison report
+        #[arg(short, long, value_name = "PATH")]
+        output: Option<String>,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_arguments() {
+        let cli = Cli::parse_from(["flux"]);
+
+        assert_eq!(cli.config, PathBuf::from("/app/config.yaml"));
+        assert!(cli.baseline.is_none());
+        assert_eq!(cli.baseline_tolerance, 10.0);
+        assert!(!cli.events);
+    }
+
+    #[test]
+    fn test_baseline_arguments() {
+        let cli = Cli::parse_from([
+            "flux",
+            "config.yaml",
+            "--baseline",
+            "baseline.json",
+            "--baseline-tolerance",
+            "5",
+        ]);
+
+        assert_eq!(cli.config, PathBuf::from("config.yaml"));
+        assert_eq!(cli.baseline, Some(PathBuf::from("baseline.json")));
+        assert_eq!(cli.baseline_tolerance, 5.0);
+    }
+
+    #[test]
+    fn test_output_dir_conflicts_with_output_paths() {
+        let cli = Cli::parse_from(["flux", "--output-dir", "results"]);
+        assert_eq!(cli.output_dir, Some(PathBuf::from("results")));
+
+        let err = Cli::try_parse_from([
+            "flux",
+            "--output-dir",
+            "results",
+            "--output-json",
+            "report.json",
+        ])
+        .unwrap_err();
+        assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    fn test_compare_subcommand() {
+        let cli = Cli::parse_from([
+            "flux",
+            "compare",
+            "before.json",
+            "after.json",
+            "--assert-max-regression-pct",
+            "5",
+        ]);
+
+        match cli.command {
+            Some(Command::Compare {
+                baseline,
+                current,
+                assert_max_regression_pct,
+                output,
+            }) => {
+                assert_eq!(baseline, PathBuf::from("before.json"));
+                assert_eq!(current, PathBuf::from("after.json"));
+                assert_eq!(assert_max_regression_pct, Some(5.0));
+                assert!(output.is_none());
+            }
+            _ => panic!("Expected compare subcommand"),
+        }
+    }
+
+    #[test]
+    fn test_merge_subcommand() {
+        let cli = Cli::parse_from(["flux", "merge", "a.json", "b.json", "-o", "combined.json"]);
+
+        match cli.command {
+            Some(Command::Merge {
+                reports,
+                output,
+                html,
+            }) => {
+                assert_eq!(
+                    reports,
+                    vec![PathBuf::from("a.json"), PathBuf::from("b.json")]
+                );
+                assert_eq!(output, "combined.json");
+                assert!(html.is_none());
+            }
+            _ => panic!("Expected merge subcommand"),
+        }
+    }
+}
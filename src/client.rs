@@ -1,25 +1,223 @@
-use crate::config::{MultipartPart, Scenario};
-use anyhow::Result;
-use reqwest::{Client, Method, Response};
+use crate::config::{
+    parse_duration, parse_placeholder, Config, DefaultHeaders, GraphqlConfig, MultipartPart,
+    Scenario, REQUEST_ID_VARIABLE,
+};
+use crate::dns::DnsCache;
+use crate::metrics::{RequestError, TimeoutKind, TimingBreakdown};
+use crate::timing::TimedClient;
+use crate::unix::{self, UnixClient};
+use anyhow::{Context, Result};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use reqwest::header::{
+    HeaderMap, HeaderName, HeaderValue, ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_TYPE, HOST,
+};
+use reqwest::{Client, ClientBuilder, Method, Request, RequestBuilder, Response};
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::io::Write;
+use std::net::{IpAddr, SocketAddr};
 use std::path::Path;
 use std::str::FromStr;
-use std::time::Duration;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tera::Tera;
+use tracing::warn;
+use uuid::Uuid;
+
+/// W3C trace context header sent when `tracing_enabled` is set
+const TRACEPARENT: &str = "traceparent";
+
+/// Variables propagated into `traceparent` instead of fresh ids
+pub const TRACE_ID_VARIABLE: &str = "trace_id";
+pub const SPAN_ID_VARIABLE: &str = "span_id";
+
+/// Request timeout unless a step sets its own
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// reqwest client with the time its connection pool was started
+struct PooledClient {
+    client: Client,
+    built: Instant,
+}
 
 /// HTTP client wrapper for making requests
 pub struct HttpClient {
-    client: Client,
+    client: Mutex<PooledClient>,
+    /// `connection_ttl` and the config to rebuild the client from
+    recycle: Option<(Duration, Box<Config>)>,
+    auto_content_type: bool,
+    omit_empty_headers: bool,
+    compress_body: bool,
+    minify_json_body: bool,
+    pretty_print_json_body: bool,
+    strict_variables: bool,
+    trace_header: Option<String>,
+    tracing_enabled: bool,
+    host: Option<String>,
+    unix: Option<UnixClient>,
+    /// Client timing each request, with `detailed_timing`
+    timed: Option<TimedClient>,
+    /// Default headers overlaid with the global `headers`
+    base_headers: HashMap<String, String>,
+    /// Lookups shared by every client built from it
+    dns_cache: Option<DnsCache>,
+    /// Contents of each step's GraphQL `query_file`, by path
+    graphql_queries: HashMap<String, String>,
+    /// Each step's `body_template_file`, compiled and named by its path
+    body_templates: Tera,
 }
 
 impl HttpClient {
     /// Create a new HTTP client
     pub fn new() -> Result<Self> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .pool_max_idle_per_host(100)
-            .build()?;
+        Ok(Self {
+            client: Mutex::new(PooledClient {
+                client: Self::builder(true).build()?,
+                built: Instant::now(),
+            }),
+            recycle: None,
+            auto_content_type: true,
+            omit_empty_headers: true,
+            compress_body: false,
+            minify_json_body: true,
+            pretty_print_json_body: false,
+            strict_variables: false,
+            trace_header: None,
+            tracing_enabled: false,
+            host: None,
+            unix: None,
+            timed: None,
+            base_headers: DefaultHeaders::default().resolved(),
+            dns_cache: None,
+            graphql_queries: HashMap::new(),
+            body_templates: Tera::default(),
+        })
+    }
+
+    /// Create a new HTTP client using the request options from the config
+    pub fn from_config(config: &Config) -> Result<Self> {
+        Self::with_dns_cache(config, DnsCache::from_config(config))
+    }
+
+    /// Like `from_config`, resolving through `dns_cache`, e.g. one shared
+    /// with other workers' clients
+    pub fn with_dns_cache(config: &Config, dns_cache: Option<DnsCache>) -> Result<Self> {
+        let recycle = match config.connection_ttl {
+            Some(ref ttl) => Some((parse_duration(ttl)?, Box::new(config.clone()))),
+            None => None,
+        };
+
+        let unix = config
+            .target
+            .as_deref()
+            .and_then(unix::socket_path)
+            .map(|socket| UnixClient::new(&socket));
+        let timed = if config.detailed_timing && unix.is_none() {
+            let mut resolve = HashMap::new();
+            for (host, addr) in &config.resolve {
+                resolve.insert(host.clone(), addr.parse::<SocketAddr>()?.ip());
+            }
+            Some(TimedClient::new(resolve)?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            client: Mutex::new(PooledClient {
+                client: Self::build_client(config, dns_cache.as_ref())?,
+                built: Instant::now(),
+            }),
+            recycle,
+            auto_content_type: config.auto_content_type,
+            omit_empty_headers: config.omit_empty_headers,
+            compress_body: config.compress_body,
+            minify_json_body: config.minify_json_body,
+            pretty_print_json_body: config.pretty_print_json_body,
+            strict_variables: config.strict_variables,
+            trace_header: config.trace_header.clone(),
+            tracing_enabled: config.tracing_enabled,
+            host: config.host.clone(),
+            unix,
+            timed,
+            base_headers: merge_headers(&config.default_headers.resolved(), &config.headers),
+            dns_cache,
+            graphql_queries: load_graphql_queries(config)?,
+            body_templates: load_body_templates(config)?,
+        })
+    }
 
-        Ok(Self { client })
+    /// DNS cache the client resolves through, if enabled
+    pub fn dns_cache(&self) -> Option<&DnsCache> {
+        self.dns_cache.as_ref()
+    }
+
+    /// Current reqwest client. With `connection_ttl` a client older than the
+    /// TTL is replaced, so no pooled connection outlives it by more than a
+    /// request; in-flight requests finish on the old one.
+    fn client(&self) -> Result<Client> {
+        let mut pooled = self.client.lock().unwrap();
+        if let Some((ttl, ref config)) = self.recycle {
+            if pooled.built.elapsed() >= ttl {
+                *pooled = PooledClient {
+                    client: Self::build_client(config, self.dns_cache.as_ref())?,
+                    built: Instant::now(),
+                };
+            }
+        }
+        Ok(pooled.client.clone())
+    }
+
+    /// Build the reqwest client for the connection options in the config
+    fn build_client(config: &Config, dns_cache: Option<&DnsCache>) -> Result<Client> {
+        let mut builder = Self::builder(config.decompress);
+        if let Some(cache) = dns_cache {
+            builder = builder.dns_resolver(Arc::new(cache.clone()));
+        }
+        if let Some(ref accept_encoding) = config.accept_encoding {
+            let decode = |coding| config.decompress && accepts_encoding(accept_encoding, coding);
+            builder = builder
+                .gzip(decode("gzip"))
+                .brotli(decode("br"))
+                .deflate(decode("deflate"))
+                .default_headers(HeaderMap::from_iter([(
+                    ACCEPT_ENCODING,
+                    HeaderValue::from_str(accept_encoding)?,
+                )]));
+        }
+        for (host, addr) in &config.resolve {
+            builder = builder.resolve(host, addr.parse()?);
+        }
+        if let Some(ref addr) = config.local_address {
+            let addr: IpAddr = addr
+                .parse()
+                .map_err(|_| anyhow::anyhow!("Invalid local address '{}'", addr))?;
+            builder = builder.local_address(addr);
+        }
+
+        builder = builder.pool_max_idle_per_host(config.pool_max_idle_per_host);
+        if let Some(ref timeout) = config.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(parse_duration(timeout)?);
+        }
+        if let Some(ref idle) = config.tcp_keepalive {
+            builder = builder.tcp_keepalive(parse_duration(idle)?);
+        }
+        builder = builder.tcp_nodelay(config.tcp_nodelay);
+        if !config.follow_redirects {
+            builder = builder.redirect(reqwest::redirect::Policy::none());
+        }
+
+        Ok(builder.build()?)
+    }
+
+    /// Client builder with the shared defaults
+    fn builder(decompress: bool) -> ClientBuilder {
+        Client::builder()
+            .timeout(DEFAULT_TIMEOUT)
+            .pool_max_idle_per_host(100)
+            .gzip(decompress)
+            .brotli(decompress)
+            .deflate(decompress)
     }
 
     /// Execute a simple request
@@ -31,23 +229,52 @@ impl HttpClient {
         body: Option<&str>,
         multipart: Option<&Vec<MultipartPart>>,
     ) -> Result<Response> {
+        let request = self
+            .build_simple(url, method, headers, body, multipart)
+            .await?;
+        self.send(request).await
+    }
+
+    /// Build a simple request without sending it
+    pub async fn build_simple(
+        &self,
+        url: &str,
+        method: &str,
+        headers: &HashMap<String, String>,
+        body: Option<&str>,
+        multipart: Option<&Vec<MultipartPart>>,
+    ) -> Result<Request> {
         let method = Method::from_str(method)?;
-        let mut request = self.client.request(method, url);
+        let mut request = self.request(method, url)?;
 
         // Add headers
-        for (key, value) in headers {
+        let headers = merge_headers(&self.base_headers, headers);
+        for (key, value) in &headers {
             request = request.header(key, value);
         }
+        if let Some(ref host) = self.host {
+            request = request.header(HOST, host);
+        }
+        if self.tracing_enabled {
+            request = request.header(TRACEPARENT, traceparent(&HashMap::new()));
+        }
+        let request_id = Uuid::new_v4().to_string();
 
         // Handle multipart or body
         if let Some(parts) = multipart {
-            request = self.build_multipart_request(request, parts).await?;
+            request = self
+                .build_multipart_request(request, parts, &HashMap::new())
+                .await?;
         } else if let Some(body_content) = body {
-            request = request.body(body_content.to_string());
+            let body_content = substitute_one(body_content, REQUEST_ID_VARIABLE, &request_id);
+            let body_content = self.format_json_body(body_content);
+            if let Some(content_type) = self.content_type_for(&headers, &body_content) {
+                request = request.header(CONTENT_TYPE, content_type);
+            }
+            request = with_body(request, body_content, self.compress_body)?;
         }
 
-        let response = request.send().await?;
-        Ok(response)
+        self.with_request_id(request.build()?, &request_id)
     }
 
     /// Execute a scenario step
@@ -57,7 +284,17 @@ impl HttpClient {
         scenario: &Scenario,
         variables: &HashMap<String, String>,
     ) -> Result<Response> {
-        // Build full URL
+        let request = self.build_scenario(base_url, scenario, variables).await?;
+        self.send(request).await
+    }
+
+    /// Full URL of a scenario step, with variables substituted
+    pub fn scenario_url(
+        &self,
+        base_url: Option<&str>,
+        scenario: &Scenario,
+        variables: &HashMap<String, String>,
+    ) -> Result<String> {
         let url = if scenario.url.starts_with("http://") || scenario.url.starts_with("https://") {
             scenario.url.clone()
         } else if let Some(base) = base_url {
@@ -65,33 +302,213 @@ impl HttpClient {
         } else {
             scenario.url.clone()
         };
+        self.substitute_variables(&url, variables)
+    }
+
+    /// Build a scenario step's request, substituting variables, without
+    /// sending it
+    pub async fn build_scenario(
+        &self,
+        base_url: Option<&str>,
+        scenario: &Scenario,
+        variables: &HashMap<String, String>,
+    ) -> Result<Request> {
+        let url = self.scenario_url(base_url, scenario, variables)?;
 
         let method = Method::from_str(&scenario.method)?;
-        let mut request = self.client.request(method, &url);
+        let mut request = self.request(method, &url)?;
 
-        // Add headers with variable substitution
-        for (key, value) in &scenario.headers {
-            let substituted_value = self.substitute_variables(value, variables);
-            request = request.header(key, substituted_value);
+        if let Some(ref timeout) = scenario.timeout {
+            request = request.timeout(parse_duration(timeout)?);
         }
 
-        // Handle multipart or body
-        if let Some(parts) = &scenario.multipart {
-            request = self.build_multipart_request(request, parts).await?;
-        } else if let Some(body_content) = &scenario.body {
-            let substituted_body = self.substitute_variables(body_content, variables);
-            request = request.body(substituted_body);
+        // Add headers with variable substitution in names and values
+        let headers = merge_headers(&self.base_headers, &scenario.headers);
+        for (key, value) in &headers {
+            let substituted_value = self.substitute_variables(value, variables)?;
+            if substituted_value.is_empty() && self.omit_empty_headers {
+                continue;
+            }
+            let substituted_key = self.substitute_variables(key, variables)?;
+            request = request.header(substituted_key, substituted_value);
+        }
+        if let Some(ref host) = scenario.host {
+            request = request.header(HOST, self.substitute_variables(host, variables)?);
+        }
+        if self.tracing_enabled {
+            request = request.header(TRACEPARENT, traceparent(variables));
+        }
+        let request_id = Uuid::new_v4().to_string();
+
+        // Handle GraphQL, multipart, form or body
+        let compress = scenario.compress_body.unwrap_or(self.compress_body);
+        if let Some(graphql) = &scenario.graphql {
+            if !has_header(&headers, CONTENT_TYPE.as_str()) {
+                request = request.header(CONTENT_TYPE, "application/json");
+            }
+            let body = self.graphql_body(graphql, variables)?;
+            request = with_body(request, body, compress)?;
+        } else if let Some(parts) = &scenario.multipart {
+            request = self
+                .build_multipart_request(request, parts, variables)
+                .await?;
+        } else if let Some(form) = &scenario.form {
+            let mut fields = HashMap::new();
+            for (name, value) in form {
+                fields.insert(
+                    self.substitute_variables(name, variables)?,
+                    self.substitute_variables(value, variables)?,
+                );
+            }
+            request = request.form(&fields);
+        } else if let Some(body_content) = self.scenario_body(scenario, variables)? {
+            let body_content = substitute_one(&body_content, REQUEST_ID_VARIABLE, &request_id);
+            let substituted_body =
+                self.format_json_body(self.substitute_variables(&body_content, variables)?);
+            if let Some(content_type) = self.content_type_for(&headers, &substituted_body) {
+                request = request.header(CONTENT_TYPE, content_type);
+            }
+            request = with_body(request, substituted_body, compress)?;
         }
 
-        let response = request.send().await?;
-        Ok(response)
+        self.with_request_id(request.build()?, &request_id)
     }
 
-    /// Build multipart form request
+    /// Body of a step: `body`, or `body_template_file` rendered by Tera with
+    /// the variables as context
+    fn scenario_body<'a>(
+        &self,
+        scenario: &'a Scenario,
+        variables: &HashMap<String, String>,
+    ) -> Result<Option<Cow<'a, str>>> {
+        let Some(ref path) = scenario.body_template_file else {
+            return Ok(scenario.body.as_deref().map(Cow::Borrowed));
+        };
+        let body = self
+            .body_templates
+            .render(path, &tera::Context::from_serialize(variables)?)
+            .with_context(|| format!("Failed to render body template {}", path))?;
+        Ok(Some(Cow::Owned(body)))
+    }
+
+    /// Reformat a JSON body as configured; other bodies are sent unchanged
+    fn format_json_body(&self, body: String) -> String {
+        if !self.minify_json_body && !self.pretty_print_json_body {
+            return body;
+        }
+        if serde_json::from_str::<serde::de::IgnoredAny>(&body).is_err() {
+            return body;
+        }
+        reformat_json(&body, self.pretty_print_json_body)
+    }
+
+    /// Add the trace header carrying `request_id`, if configured and not
+    /// already set by the step's headers
+    fn with_request_id(&self, mut request: Request, request_id: &str) -> Result<Request> {
+        if let Some(ref name) = self.trace_header {
+            let name = HeaderName::from_str(name)?;
+            if !request.headers().contains_key(&name) {
+                request
+                    .headers_mut()
+                    .insert(name, HeaderValue::from_str(request_id)?);
+            }
+        }
+        Ok(request)
+    }
+
+    /// Trace id a built request carries, if configured
+    pub fn request_id(&self, request: &Request) -> Option<String> {
+        let name = self.trace_header.as_ref()?;
+        let value = request.headers().get(name)?;
+        value.to_str().ok().map(str::to_string)
+    }
+
+    /// Send a built request
+    pub async fn send(&self, request: Request) -> Result<Response> {
+        if let Some(ref unix) = self.unix {
+            if request.url().host_str() == Some(unix::HOST) {
+                return unix.send(request, DEFAULT_TIMEOUT).await;
+            }
+        }
+        Ok(self.client()?.execute(request).await?)
+    }
+
+    /// Send a built request, with its timing breakdown when
+    /// `detailed_timing` is set
+    pub async fn send_timed(
+        &self,
+        request: Request,
+    ) -> Result<(Response, Option<TimingBreakdown>)> {
+        match self.timed {
+            Some(ref timed) => {
+                let (response, timing) = timed.send(request, DEFAULT_TIMEOUT).await?;
+                Ok((response, Some(timing)))
+            }
+            None => Ok((self.send(request).await?, None)),
+        }
+    }
+
+    /// Start a request, mapping `unix:` URLs onto the target socket
+    fn request(&self, method: Method, url: &str) -> Result<RequestBuilder> {
+        if !url.starts_with("unix:") {
+            return Ok(self.client()?.request(method, url));
+        }
+        let Some(ref unix) = self.unix else {
+            anyhow::bail!("Unix socket URL '{}' needs a unix: target", url);
+        };
+        Ok(self.client()?.request(method, unix.http_url(url)?))
+    }
+
+    /// Build the `{"query": ..., "variables": ...}` body of a GraphQL request
+    fn graphql_body(
+        &self,
+        graphql: &GraphqlConfig,
+        variables: &HashMap<String, String>,
+    ) -> Result<String> {
+        let query = match (&graphql.query, &graphql.query_file) {
+            (Some(query), _) => query,
+            (None, Some(path)) => self.graphql_queries.get(path).ok_or_else(|| {
+                anyhow::anyhow!("Query file {} was not loaded with the config", path)
+            })?,
+            (None, None) => anyhow::bail!("GraphQL requires 'query' or 'query_file'"),
+        };
+
+        let mut body = serde_json::json!({ "query": query });
+        if let Some(ref graphql_variables) = graphql.variables {
+            body["variables"] = self.substitute_json(graphql_variables, variables)?;
+        }
+
+        Ok(body.to_string())
+    }
+
+    /// Substitute variables in every string of a JSON value
+    fn substitute_json(
+        &self,
+        value: &serde_json::Value,
+        variables: &HashMap<String, String>,
+    ) -> Result<serde_json::Value> {
+        Ok(match value {
+            serde_json::Value::String(s) => {
+                serde_json::Value::String(self.substitute_variables(s, variables)?)
+            }
+            serde_json::Value::Array(items) => items
+                .iter()
+                .map(|item| self.substitute_json(item, variables))
+                .collect::<Result<_>>()?,
+            serde_json::Value::Object(fields) => fields
+                .iter()
+                .map(|(k, v)| Ok((k.clone(), self.substitute_json(v, variables)?)))
+                .collect::<Result<_>>()?,
+            other => other.clone(),
+        })
+    }
+
+    /// Build multipart form request, substituting variables into field values
     async fn build_multipart_request(
         &self,
         request: reqwest::RequestBuilder,
         parts: &[MultipartPart],
+        variables: &HashMap<String, String>,
     ) -> Result<reqwest::RequestBuilder> {
         let mut form = reqwest::multipart::Form::new();
 
@@ -120,7 +537,10 @@ impl HttpClient {
                 }
                 "field" => {
                     if let Some(ref value) = part.value {
-                        form = form.text(part.name.clone(), value.clone());
+                        form = form.text(
+                            part.name.clone(),
+                            self.substitute_variables(value, variables)?,
+                        );
                     }
                 }
                 _ => {
@@ -132,25 +552,341 @@ impl HttpClient {
         Ok(request.multipart(form))
     }
 
-    /// Substitute variables in a string using {{ variable }} syntax
-    fn substitute_variables(&self, template: &str, variables: &HashMap<String, String>) -> String {
-        let mut result = template.to_string();
+    /// Content type to inject for a body when none was set explicitly
+    fn content_type_for(
+        &self,
+        headers: &HashMap<String, String>,
+        body: &str,
+    ) -> Option<&'static str> {
+        if !self.auto_content_type || has_header(headers, CONTENT_TYPE.as_str()) {
+            return None;
+        }
+
+        detect_content_type(body)
+    }
+
+    /// Substitute variables in a string using {{ variable }} syntax. An
+    /// unset variable takes its `{{ variable | default("value") }}`; without
+    /// one the placeholder is kept, or with `strict_variables` it's an error.
+    pub fn substitute_variables(
+        &self,
+        template: &str,
+        variables: &HashMap<String, String>,
+    ) -> Result<String> {
+        let mut result = String::with_capacity(template.len());
+        let mut rest = template;
+
+        while let Some(start) = rest.find("{{") {
+            let Some(len) = rest[start..].find("}}") else {
+                break;
+            };
+            let placeholder = &rest[start..start + len + 2];
+            result.push_str(&rest[..start]);
+            rest = &rest[start + len + 2..];
 
-        for (key, value) in variables {
-            let placeholder = format!("{{{{ {} }}}}", key);
-            result = result.replace(&placeholder, value);
+            let Some((name, default)) = parse_placeholder(&placeholder[2..len]) else {
+                result.push_str(placeholder);
+                continue;
+            };
+            match (variables.get(name), default) {
+                (Some(value), _) => result.push_str(value),
+                (None, Some(default)) => result.push_str(default),
+                (None, None) if self.strict_variables => {
+                    anyhow::bail!("Variable '{}' is not set", name)
+                }
+                (None, None) => {
+                    warn!("Variable '{}' is not set, leaving its placeholder", name);
+                    result.push_str(placeholder);
+                }
+            }
         }
 
-        result
+        result.push_str(rest);
+        Ok(result)
     }
 }
 
+/// W3C `traceparent` value, continuing the trace in the `trace_id` and
+/// `span_id` variables when set and starting a new one otherwise
+pub fn traceparent(variables: &HashMap<String, String>) -> String {
+    let trace_id = variables
+        .get(TRACE_ID_VARIABLE)
+        .cloned()
+        .unwrap_or_else(|| format!("{:032x}", rand::random::<u128>()));
+    let span_id = variables
+        .get(SPAN_ID_VARIABLE)
+        .cloned()
+        .unwrap_or_else(|| format!("{:016x}", rand::random::<u64>()));
+
+    format!("00-{}-{}-01", trace_id, span_id)
+}
+
+/// Classify a request failure into a structured error
+pub fn classify_error(error: &anyhow::Error) -> RequestError {
+    // Unix socket requests fail with hyper and tokio errors
+    if error
+        .downcast_ref::<tokio::time::error::Elapsed>()
+        .is_some()
+    {
+        return RequestError::Timeout {
+            kind: TimeoutKind::Request,
+        };
+    }
+    if let Some(hyper_error) = error.downcast_ref::<hyper::Error>() {
+        return classify_cause(hyper_error);
+    }
+    let Some(reqwest_error) = error.downcast_ref::<reqwest::Error>() else {
+        return RequestError::Other(error.to_string());
+    };
+
+    if reqwest_error.is_timeout() {
+        let kind = if reqwest_error.is_connect() {
+            TimeoutKind::Connect
+        } else {
+            TimeoutKind::Request
+        };
+        return RequestError::Timeout { kind };
+    }
+    classify_cause(reqwest_error)
+}
+
+/// Classify a connection failure by its innermost cause
+fn classify_cause(error: &dyn std::error::Error) -> RequestError {
+    // The underlying cause is only exposed through the error source chain
+    let mut detail = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        detail = cause.to_string();
+        source = cause.source();
+    }
+    let lower = detail.to_lowercase();
+
+    if lower.contains("connection refused") {
+        RequestError::ConnectionRefused
+    } else if lower.contains("dns") || lower.contains("lookup address") {
+        RequestError::DnsError(detail)
+    } else if lower.contains("tls") || lower.contains("ssl") || lower.contains("certificate") {
+        RequestError::TlsError(detail)
+    } else {
+        RequestError::Other(error.to_string())
+    }
+}
+
+/// Failure reported in the `errors` array of a GraphQL response body
+pub fn graphql_error(body: &str) -> Option<RequestError> {
+    let json: serde_json::Value = serde_json::from_str(body).ok()?;
+    let first = json.get("errors")?.as_array()?.first()?;
+
+    let message = first
+        .get("message")
+        .and_then(|m| m.as_str())
+        .map(str::to_string)
+        .unwrap_or_else(|| first.to_string());
+    Some(RequestError::GraphQLError(message))
+}
+
+/// Read the `query_file` of every GraphQL step once, so requests don't
+/// touch the disk
+fn load_graphql_queries(config: &Config) -> Result<HashMap<String, String>> {
+    let mut queries = HashMap::new();
+    for step in config.steps() {
+        let Some(path) = step.graphql.as_ref().and_then(|g| g.query_file.as_ref()) else {
+            continue;
+        };
+        if !queries.contains_key(path) {
+            let query = std::fs::read_to_string(path)
+                .map_err(|e| anyhow::anyhow!("Failed to read query file {}: {}", path, e))?;
+            queries.insert(path.clone(), query);
+        }
+    }
+    Ok(queries)
+}
+
+/// Read and compile every step's `body_template_file` once, so requests
+/// only render them
+fn load_body_templates(config: &Config) -> Result<Tera> {
+    let mut templates = Tera::default();
+    templates.autoescape_on(Vec::new());
+    for step in config.steps() {
+        let Some(ref path) = step.body_template_file else {
+            continue;
+        };
+        if templates.get_template_names().all(|name| name != path) {
+            let template = std::fs::read_to_string(path)
+                .map_err(|e| anyhow::anyhow!("Failed to read body template {}: {}", path, e))?;
+            templates
+                .add_raw_template(path, &template)
+                .with_context(|| format!("Failed to parse body template {}", path))?;
+        }
+    }
+    Ok(templates)
+}
+
+/// Whether an `Accept-Encoding` value accepts `coding`, directly or via
+/// `*`, with a non-zero quality
+fn accepts_encoding(accept_encoding: &str, coding: &str) -> bool {
+    accept_encoding.split(',').any(|entry| {
+        let mut params = entry.split(';');
+        let name = params.next().unwrap_or_default().trim();
+        let refused = params.any(|param| {
+            param
+                .trim()
+                .strip_prefix("q=")
+                .and_then(|q| q.parse::<f64>().ok())
+                == Some(0.0)
+        });
+        (name.eq_ignore_ascii_case(coding) || name == "*") && !refused
+    })
+}
+
+/// Replace only the `{{ name }}` placeholders of one variable, leaving any
+/// others for `substitute_variables`
+fn substitute_one(template: &str, name: &str, value: &str) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start..].find("}}") else {
+            break;
+        };
+        let placeholder = &rest[start..start + len + 2];
+        result.push_str(&rest[..start]);
+        rest = &rest[start + len + 2..];
+
+        match parse_placeholder(&placeholder[2..len]) {
+            Some((found, _)) if found == name => result.push_str(value),
+            _ => result.push_str(placeholder),
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// Gzip a request body
+pub fn gzip(body: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(body)?;
+    Ok(encoder.finish()?)
+}
+
+/// Attach a body, gzipped with `Content-Encoding: gzip` when `compress` is set
+fn with_body(
+    request: reqwest::RequestBuilder,
+    body: String,
+    compress: bool,
+) -> Result<reqwest::RequestBuilder> {
+    if compress {
+        Ok(request
+            .header(CONTENT_ENCODING, "gzip")
+            .body(gzip(body.as_bytes())?))
+    } else {
+        Ok(request.body(body))
+    }
+}
+
+/// Whether `name` is set in `headers`, ignoring case
+fn has_header(headers: &HashMap<String, String>, name: &str) -> bool {
+    headers.keys().any(|k| k.eq_ignore_ascii_case(name))
+}
+
+/// `overrides` layered over `base`, comparing names case-insensitively
+fn merge_headers(
+    base: &HashMap<String, String>,
+    overrides: &HashMap<String, String>,
+) -> HashMap<String, String> {
+    let mut merged: HashMap<String, String> = base
+        .iter()
+        .filter(|(name, _)| !has_header(overrides, name))
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+    merged.extend(overrides.iter().map(|(n, v)| (n.clone(), v.clone())));
+    merged
+}
+
+/// Detect the content type of a request body from its format
+fn detect_content_type(body: &str) -> Option<&'static str> {
+    let trimmed = body.trim();
+
+    if trimmed.is_empty() {
+        return None;
+    }
+
+    if serde_json::from_str::<serde_json::Value>(trimmed).is_ok() {
+        return Some("application/json; charset=utf-8");
+    }
+
+    let has_json_delimiters = trimmed.contains(['{', '}', '[', ']']);
+    let has_whitespace = trimmed.contains(char::is_whitespace);
+    if trimmed.contains('=') && !has_json_delimiters && !has_whitespace {
+        return Some("application/x-www-form-urlencoded");
+    }
+
+    None
+}
+
 impl Default for HttpClient {
     fn default() -> Self {
         Self::new().expect("Failed to create HTTP client")
     }
 }
 
+/// Re-lay out valid JSON compactly, or indented when `pretty`, by moving
+/// only the whitespace outside strings. Unlike a round trip through
+/// `serde_json::Value`, numbers and duplicate keys are kept as written.
+fn reformat_json(json: &str, pretty: bool) -> String {
+    fn newline(out: &mut String, depth: usize) {
+        out.push('\n');
+        for _ in 0..depth {
+            out.push_str("  ");
+        }
+    }
+
+    let mut out = String::with_capacity(json.len());
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in json.chars() {
+        if in_string {
+            out.push(c);
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+        if c.is_whitespace() {
+            continue;
+        }
+
+        let after_open = out.ends_with(['{', '[']);
+        if pretty {
+            match c {
+                '}' | ']' => {
+                    depth -= 1;
+                    if !after_open {
+                        newline(&mut out, depth);
+                    }
+                }
+                _ if after_open => newline(&mut out, depth),
+                _ => {}
+            }
+        }
+        out.push(c);
+        match c {
+            '"' => in_string = true,
+            '{' | '[' => depth += 1,
+            ',' if pretty => newline(&mut out, depth),
+            ':' if pretty => out.push(' '),
+            _ => {}
+        }
+    }
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -163,19 +899,580 @@ mod tests {
         vars.insert("user".to_string(), "john".to_string());
 
         let template = "Bearer {{ token }} for {{ user }}";
-        let result = client.substitute_variables(template, &vars);
+        let result = client.substitute_variables(template, &vars).unwrap();
 
         assert_eq!(result, "Bearer abc123 for john");
     }
 
+    #[test]
+    fn test_substitute_defaults() {
+        let mut client = HttpClient::new().unwrap();
+        let mut vars = HashMap::new();
+        vars.insert("user".to_string(), "john".to_string());
+
+        let template = r#"{{ user | default("guest") }} {{ token | default("anonymous") }}"#;
+        let result = client.substitute_variables(template, &vars).unwrap();
+        assert_eq!(result, "john anonymous");
+
+        let result = client.substitute_variables("id={{ id }}", &vars).unwrap();
+        assert_eq!(result, "id={{ id }}");
+
+        client.strict_variables = true;
+        let err = client
+            .substitute_variables("id={{ id }}", &vars)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Variable 'id' is not set");
+        assert!(client
+            .substitute_variables("id={{ id | default('0') }}", &vars)
+            .is_ok());
+    }
+
     #[test]
     fn test_substitute_no_variables() {
         let client = HttpClient::new().unwrap();
         let vars = HashMap::new();
 
         let template = "No variables here";
-        let result = client.substitute_variables(template, &vars);
+        let result = client.substitute_variables(template, &vars).unwrap();
 
         assert_eq!(result, "No variables here");
     }
+
+    #[tokio::test]
+    async fn test_json_body_formatting() {
+        async fn body(client: &HttpClient, body: &str) -> String {
+            let scenario = Scenario {
+                body: Some(body.to_string()),
+                ..serde_yaml::from_str("{ name: a, method: POST, url: /a }").unwrap()
+            };
+            let request = client
+                .build_scenario(Some("http://localhost"), &scenario, &HashMap::new())
+                .await
+                .unwrap();
+            String::from_utf8(request.body().unwrap().as_bytes().unwrap().to_vec()).unwrap()
+        }
+
+        let mut client = HttpClient::new().unwrap();
+        let json = "{\n  \"name\": \"flux\",\n  \"id\": 1\n}";
+        assert_eq!(body(&client, json).await, r#"{"name":"flux","id":1}"#);
+        assert_eq!(body(&client, "<a> b </a>").await, "<a> b </a>");
+
+        // Numbers and duplicate keys are kept as written
+        let exact = r#"{ "id": 12345678901234567890123, "a": "x y", "a": [ ] }"#;
+        assert_eq!(
+            body(&client, exact).await,
+            r#"{"id":12345678901234567890123,"a":"x y","a":[]}"#
+        );
+
+        client.pretty_print_json_body = true;
+        assert_eq!(body(&client, r#"{"id": 1}"#).await, "{\n  \"id\": 1\n}");
+        assert_eq!(
+            body(&client, r#"{"a":[1,{}],"b":"\" :"}"#).await,
+            "{\n  \"a\": [\n    1,\n    {}\n  ],\n  \"b\": \"\\\" :\"\n}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_form_body() {
+        let config: Config = r#"
+target: http://example.com
+scenarios:
+  - name: login
+    method: POST
+    url: /login
+    form: { "user_{{ field }}": "{{ user }}" }
+output: { json: out.json, html: out.html }
+"#
+        .parse()
+        .unwrap();
+        let client = HttpClient::from_config(&config).unwrap();
+        let variables = HashMap::from([
+            ("field".to_string(), "name".to_string()),
+            ("user".to_string(), "a b&c".to_string()),
+        ]);
+
+        let request = client
+            .build_scenario(config.target.as_deref(), &config.scenarios[0], &variables)
+            .await
+            .unwrap();
+        assert_eq!(
+            request.headers()[CONTENT_TYPE],
+            "application/x-www-form-urlencoded"
+        );
+        let body = String::from_utf8(request.body().unwrap().as_bytes().unwrap().to_vec()).unwrap();
+        assert_eq!(body, "user_name=a+b%26c");
+
+        let err = "target: http://example.com\nscenarios:\n  - { name: a, method: POST, url: /, body: x, form: { a: b } }\noutput: { json: a, html: b }\n"
+            .parse::<Config>()
+            .unwrap_err();
+        assert!(err.to_string().contains("'form' cannot be combined"));
+    }
+
+    #[tokio::test]
+    async fn test_body_template_file() {
+        let path = std::env::temp_dir().join(format!("flux-body-{}.j2", std::process::id()));
+        std::fs::write(
+            &path,
+            "{\n  \"items\": [{% for i in range(end=3) %}{{ i }}{% if not loop.last %}, {% endif %}{% endfor %}],\n  \"user\": \"{{ user }}\"{% if admin %}, \"role\": \"admin\"{% endif %}\n}\n",
+        )
+        .unwrap();
+        let config: Config = format!(
+            "target: http://localhost\nscenarios:\n  - {{ name: a, method: POST, url: /a, body_template_file: {} }}\noutput: {{ json: a, html: b }}\n",
+            path.display()
+        )
+        .parse()
+        .unwrap();
+        let client = HttpClient::from_config(&config).unwrap();
+        let scenario = &config.scenarios[0];
+        let variables = HashMap::from([("user".to_string(), "u1".to_string())]);
+
+        // Requests render the template compiled when the client was built
+        std::fs::remove_file(&path).unwrap();
+
+        let request = client
+            .build_scenario(Some("http://localhost"), scenario, &variables)
+            .await
+            .unwrap();
+        let body = String::from_utf8(request.body().unwrap().as_bytes().unwrap().to_vec()).unwrap();
+        assert_eq!(body, r#"{"items":[0,1,2],"user":"u1"}"#);
+
+        let err = client
+            .build_scenario(Some("http://localhost"), scenario, &HashMap::new())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Failed to render body template"));
+    }
+
+    #[tokio::test]
+    async fn test_graphql_query_file_read_once() {
+        let path = std::env::temp_dir().join(format!("flux-query-{}.graphql", std::process::id()));
+        std::fs::write(&path, "{ user { id } }").unwrap();
+        let config: Config = format!(
+            "target: http://localhost\nscenarios:\n  - {{ name: q, method: POST, url: /graphql, graphql: {{ query_file: {} }} }}\noutput: {{ json: a, html: b }}\n",
+            path.display()
+        )
+        .parse()
+        .unwrap();
+        let client = HttpClient::from_config(&config).unwrap();
+
+        // Requests use the query read when the client was built
+        std::fs::remove_file(&path).unwrap();
+        let request = client
+            .build_scenario(
+                Some("http://localhost"),
+                &config.scenarios[0],
+                &HashMap::new(),
+            )
+            .await
+            .unwrap();
+        let body = String::from_utf8(request.body().unwrap().as_bytes().unwrap().to_vec()).unwrap();
+        assert_eq!(body, r#"{"query":"{ user { id } }"}"#);
+
+        assert!(HttpClient::from_config(&config).is_err());
+    }
+
+    #[test]
+    fn test_detect_content_type() {
+        assert_eq!(
+            detect_content_type(r#"{"name": "flux"}"#),
+            Some("application/json; charset=utf-8")
+        );
+        assert_eq!(
+            detect_content_type("[1, 2, 3]"),
+            Some("application/json; charset=utf-8")
+        );
+        assert_eq!(
+            detect_content_type("user=john&age=25"),
+            Some("application/x-www-form-urlencoded")
+        );
+        assert_eq!(detect_content_type("<xml>hello</xml>"), None);
+        assert_eq!(detect_content_type(""), None);
+    }
+
+    #[test]
+    fn test_explicit_content_type_is_kept() {
+        let client = HttpClient::new().unwrap();
+        let mut headers = HashMap::new();
+        headers.insert("content-type".to_string(), "text/plain".to_string());
+
+        assert_eq!(client.content_type_for(&headers, r#"{"a": 1}"#), None);
+    }
+
+    #[test]
+    fn test_classify_non_http_error() {
+        let error = anyhow::anyhow!("File not found: /app/data/missing.txt");
+
+        assert_eq!(
+            classify_error(&error),
+            RequestError::Other("File not found: /app/data/missing.txt".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_classify_connection_refused() {
+        // Bind and drop a listener to get a local port with nothing behind it
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        let client = HttpClient::new().unwrap();
+        let error = client
+            .execute_simple(
+                &format!("http://127.0.0.1:{}/", port),
+                "GET",
+                &HashMap::new(),
+                None,
+                None,
+            )
+            .await
+            .unwrap_err();
+
+        assert_eq!(classify_error(&error), RequestError::ConnectionRefused);
+    }
+
+    #[test]
+    fn test_gzip_round_trip() {
+        use flate2::read::GzDecoder;
+        use std::io::Read;
+
+        let body = r#"{"user": "{{ user }}", "items": [1, 2, 3]}"#.repeat(10);
+        let compressed = gzip(body.as_bytes()).unwrap();
+
+        let mut decompressed = String::new();
+        GzDecoder::new(&compressed[..])
+            .read_to_string(&mut decompressed)
+            .unwrap();
+
+        assert!(compressed.len() < body.len());
+        assert_eq!(decompressed, body);
+    }
+
+    #[tokio::test]
+    async fn test_gzip_response_decompression() {
+        let gzipped = gzip(br#"{"token": "abc"}"#).unwrap();
+
+        let router = axum::Router::new().route(
+            "/token",
+            axum::routing::get(move || {
+                let body = gzipped.clone();
+                async move { ([("content-encoding", "gzip")], body) }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let target = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        for decompress in [true, false] {
+            let config: Config = format!(
+                r#"
+target: {}
+decompress: {}
+scenarios:
+  - {{ name: token, method: GET, url: /token }}
+output: {{ json: out.json, html: out.html }}
+"#,
+                target, decompress
+            )
+            .parse()
+            .unwrap();
+
+            let client = HttpClient::from_config(&config).unwrap();
+            let body = client
+                .execute_scenario(Some(&target), &config.scenarios[0], &HashMap::new())
+                .await
+                .unwrap()
+                .bytes()
+                .await
+                .unwrap();
+
+            if decompress {
+                assert_eq!(&body[..], br#"{"token": "abc"}"#);
+            } else {
+                // gzip magic bytes
+                assert_eq!(&body[..2], &[0x1f, 0x8b]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_accepts_encoding() {
+        assert!(accepts_encoding("gzip, br", "br"));
+        assert!(accepts_encoding("*", "deflate"));
+        assert!(!accepts_encoding("identity", "gzip"));
+        assert!(!accepts_encoding("GZIP;q=0, br", "gzip"));
+        assert!(accepts_encoding("gzip;q=0.5", "gzip"));
+    }
+
+    #[test]
+    fn test_connection_ttl_recycles_client() {
+        let config: Config =
+            "target: http://localhost\nconnection_ttl: 20ms\noutput: { json: a, html: b }\n"
+                .parse()
+                .unwrap();
+        let client = HttpClient::from_config(&config).unwrap();
+        let built = |client: &HttpClient| client.client.lock().unwrap().built;
+
+        let first = built(&client);
+        client.client().unwrap();
+        assert_eq!(built(&client), first);
+
+        std::thread::sleep(Duration::from_millis(25));
+        client.client().unwrap();
+        assert!(built(&client) > first);
+    }
+
+    #[tokio::test]
+    async fn test_patch_and_head_requests() {
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let body = r#"{"name":"updated"}"#;
+        let server = MockServer::start().await;
+        Mock::given(method("PATCH"))
+            .and(path("/resource"))
+            .and(header("content-length", body.len().to_string().as_str()))
+            .and(header("content-type", "application/json; charset=utf-8"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("HEAD"))
+            .and(path("/resource"))
+            .respond_with(ResponseTemplate::new(200).insert_header("etag", "\"v2\""))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = HttpClient::new().unwrap();
+        let url = format!("{}/resource", server.uri());
+
+        let response = client
+            .execute_simple(&url, "PATCH", &HashMap::new(), Some(body), None)
+            .await
+            .unwrap();
+        assert_eq!(response.status().as_u16(), 204);
+
+        let response = client
+            .execute_simple(&url, "HEAD", &HashMap::new(), None, None)
+            .await
+            .unwrap();
+        assert_eq!(response.headers()["etag"], "\"v2\"");
+        assert_eq!(response.text().await.unwrap(), "");
+    }
+
+    #[tokio::test]
+    async fn test_resolve_overrides_dns() {
+        let router = axum::Router::new().route(
+            "/health",
+            axum::routing::get(|headers: axum::http::HeaderMap| async move {
+                headers["host"].to_str().unwrap().to_string()
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let config: Config = format!(
+            r#"
+target: http://api.flux.invalid:{port}
+resolve: {{ api.flux.invalid: "127.0.0.1:{port}" }}
+output: {{ json: out.json, html: out.html }}
+"#
+        )
+        .parse()
+        .unwrap();
+
+        let client = HttpClient::from_config(&config).unwrap();
+        let host = client
+            .execute_simple(
+                &format!("http://api.flux.invalid:{}/health", port),
+                "GET",
+                &HashMap::new(),
+                None,
+                None,
+            )
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert_eq!(host, format!("api.flux.invalid:{}", port));
+
+        let err = "target: http://a\nresolve: { a: localhost }\noutput: { json: a, html: b }"
+            .parse::<Config>()
+            .unwrap_err();
+        assert!(err.to_string().contains("expected IP:port"));
+    }
+
+    #[tokio::test]
+    async fn test_default_headers() {
+        let build = |yaml: &str| {
+            let config: Config = format!(
+                "target: http://example.com\n{}\nscenarios:\n  - {{ name: a, method: GET, url: /, headers: {{ x-env: step }} }}\noutput: {{ json: a, html: b }}\n",
+                yaml
+            )
+            .parse()
+            .unwrap();
+            async move {
+                let client = HttpClient::from_config(&config).unwrap();
+                client
+                    .build_scenario(
+                        config.target.as_deref(),
+                        &config.scenarios[0],
+                        &HashMap::new(),
+                    )
+                    .await
+                    .unwrap()
+            }
+        };
+        let header = |request: &Request, name: &str| {
+            let values: Vec<&str> = request
+                .headers()
+                .get_all(name)
+                .iter()
+                .map(|v| v.to_str().unwrap())
+                .collect();
+            values.join(",")
+        };
+
+        let request = build("").await;
+        assert_eq!(header(&request, "accept"), "*/*");
+        assert_eq!(header(&request, "x-env"), "step");
+
+        let request = build("headers: { accept: application/json, X-Env: global }").await;
+        assert_eq!(header(&request, "accept"), "application/json");
+        assert_eq!(header(&request, "x-env"), "step");
+
+        let request = build("default_headers: { Accept: text/html, X-Client: flux }").await;
+        assert_eq!(header(&request, "accept"), "text/html");
+        assert_eq!(header(&request, "x-client"), "flux");
+
+        let request = build("default_headers: false").await;
+        assert!(request.headers().get("accept").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_request_id_header() {
+        let config: Config = r#"
+target: http://example.com
+request_id_header: X-Request-Id
+scenarios:
+  - { name: order, method: POST, url: /orders, body: '{"ref": "{{ $request_id }}", "user": "{{ user }}"}' }
+  - { name: fixed, method: GET, url: /, headers: { x-request-id: abc } }
+output: { json: out.json, html: out.html }
+"#
+        .parse()
+        .unwrap();
+        let client = HttpClient::from_config(&config).unwrap();
+        let variables = HashMap::from([("user".to_string(), "u1".to_string())]);
+
+        let request = client
+            .build_scenario(config.target.as_deref(), &config.scenarios[0], &variables)
+            .await
+            .unwrap();
+        let id = client.request_id(&request).unwrap();
+        assert_eq!(id.len(), 36);
+        let body = String::from_utf8(request.body().unwrap().as_bytes().unwrap().to_vec()).unwrap();
+        assert_eq!(body, format!(r#"{{"ref":"{}","user":"u1"}}"#, id));
+
+        let request = client
+            .build_scenario(config.target.as_deref(), &config.scenarios[1], &variables)
+            .await
+            .unwrap();
+        assert_eq!(client.request_id(&request).as_deref(), Some("abc"));
+    }
+
+    #[tokio::test]
+    async fn test_header_name_substitution_and_empty_values() {
+        let yaml = |omit: bool| {
+            format!(
+                r#"
+target: http://example.com
+omit_empty_headers: {omit}
+scenarios:
+  - name: signed
+    method: GET
+    url: /
+    headers:
+      "X-Signature-{{{{ version }}}}": "sig"
+      X-Tenant: '{{{{ tenant | default("") }}}}'
+output: {{ json: out.json, html: out.html }}
+"#
+            )
+        };
+        let variables = HashMap::from([("version".to_string(), "2".to_string())]);
+
+        let config: Config = yaml(true).parse().unwrap();
+        let client = HttpClient::from_config(&config).unwrap();
+        let request = client
+            .build_scenario(config.target.as_deref(), &config.scenarios[0], &variables)
+            .await
+            .unwrap();
+        assert_eq!(request.headers()["x-signature-2"], "sig");
+        assert!(!request.headers().contains_key("x-tenant"));
+
+        let config: Config = yaml(false).parse().unwrap();
+        let client = HttpClient::from_config(&config).unwrap();
+        let request = client
+            .build_scenario(config.target.as_deref(), &config.scenarios[0], &variables)
+            .await
+            .unwrap();
+        assert_eq!(request.headers()["x-tenant"], "");
+    }
+
+    #[tokio::test]
+    async fn test_host_override() {
+        let router = axum::Router::new().route(
+            "/host",
+            axum::routing::get(|headers: axum::http::HeaderMap| async move {
+                let hosts: Vec<&str> = headers
+                    .get_all("host")
+                    .iter()
+                    .map(|v| v.to_str().unwrap())
+                    .collect();
+                hosts.join(",")
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let target = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let config: Config = format!(
+            r#"
+target: {target}/host
+host: api.example.com
+scenarios:
+  - {{ name: tenant, method: GET, url: /host, host: "{{{{ tenant }}}}.example.com" }}
+output: {{ json: out.json, html: out.html }}
+"#
+        )
+        .parse()
+        .unwrap();
+        let client = HttpClient::from_config(&config).unwrap();
+
+        let request = client
+            .build_simple(
+                config.target.as_ref().unwrap(),
+                "GET",
+                &HashMap::new(),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        let host = client.send(request).await.unwrap().text().await.unwrap();
+        assert_eq!(host, "api.example.com");
+
+        let variables = HashMap::from([("tenant".to_string(), "acme".to_string())]);
+        let host = client
+            .execute_scenario(Some(&target), &config.scenarios[0], &variables)
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert_eq!(host, "acme.example.com");
+    }
 }
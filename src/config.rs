@@ -1,6 +1,18 @@
+use crate::client::{SPAN_ID_VARIABLE, TRACE_ID_VARIABLE};
+use crate::metrics::HistogramBounds;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::warn;
+
+/// Variable holding the 0-based iteration of a repeated scenario
+pub const REPEAT_INDEX_VARIABLE: &str = "repeat_index";
+
+/// Built-in body variable holding the id sent in `trace_header`
+pub const REQUEST_ID_VARIABLE: &str = "$request_id";
 
 /// Main configuration structure for Flux load testing
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -13,10 +25,29 @@ pub struct Config {
     #[serde(default)]
     pub method: Option<String>,
 
-    /// Headers for simple mode
+    /// Accept methods outside `STANDARD_METHODS`
+    #[serde(default)]
+    pub allow_custom_method: bool,
+
+    /// Extension methods accepted besides `STANDARD_METHODS`, e.g.
+    /// `[PROPFIND, MKCOL]`; unlike `allow_custom_method`, a typo of a
+    /// standard method is still rejected
+    #[serde(default)]
+    pub custom_methods: Vec<String>,
+
+    /// Headers sent with every request; scenario step headers override them
     #[serde(default)]
     pub headers: HashMap<String, String>,
 
+    /// Headers sent unless `headers` override them: `true` for the
+    /// built-in defaults, `false` for none, or a map replacing them
+    #[serde(default)]
+    pub default_headers: DefaultHeaders,
+
+    /// `Host` header for simple mode, independent of the target URL
+    #[serde(default)]
+    pub host: Option<String>,
+
     /// Request body for simple mode
     #[serde(default)]
     pub body: Option<String>,
@@ -25,10 +56,50 @@ pub struct Config {
     #[serde(default)]
     pub multipart: Option<Vec<MultipartPart>>,
 
+    /// Weighted mix of simple requests; one is picked per iteration
+    #[serde(default)]
+    pub requests: Vec<WeightedRequest>,
+
     /// Multi-step scenarios
     #[serde(default)]
     pub scenarios: Vec<Scenario>,
 
+    /// Unary gRPC call made instead of HTTP requests
+    #[serde(default)]
+    pub grpc: Option<GrpcConfig>,
+
+    /// Variables set before any request and available to every step and
+    /// to simple mode. Values may use `${ENV_VAR}` and the `{{ $timestamp }}`,
+    /// `{{ $timestamp_ms }}`, `{{ $datetime }}` and `{{ $uuid }}` functions,
+    /// evaluated once at start.
+    #[serde(default)]
+    pub variables: HashMap<String, String>,
+
+    /// Scenarios run once before the workers start; extracted variables are
+    /// shared with every worker. Also accepted as `before_all`.
+    #[serde(default, alias = "before_all")]
+    pub setup: Vec<Scenario>,
+
+    /// Scenarios run once after the workers finish. Also accepted as
+    /// `after_all`.
+    #[serde(default, alias = "after_all")]
+    pub teardown: Vec<Scenario>,
+
+    /// Scenario order per iteration: "sequential" (default) or "random"
+    #[serde(default)]
+    pub scenario_order: Option<String>,
+
+    /// What a failed step does to the rest of its chain iteration:
+    /// "continue" (default), "abort" or "skip-remaining"
+    #[serde(default)]
+    pub on_scenario_failure: Option<String>,
+
+    /// Wall-clock limit, in seconds, on one pass through the scenario list;
+    /// requests cut short and steps not yet started when it expires are
+    /// recorded as chain timeouts. `after_each` hooks run outside it.
+    #[serde(default)]
+    pub scenario_chain_timeout_secs: Option<u64>,
+
     /// Number of concurrent workers
     #[serde(default = "default_concurrency")]
     pub concurrency: usize,
@@ -37,12 +108,248 @@ pub struct Config {
     #[serde(default = "default_duration")]
     pub duration: String,
 
+    /// Phases run in order, each with its own concurrency and duration;
+    /// replaces `concurrency` and `duration` when set
+    #[serde(default)]
+    pub phases: Option<Vec<Phase>>,
+
     /// Execution mode: "async" or "sync"
     #[serde(default = "default_mode")]
     pub mode: String,
 
+    /// Spread the start of the async workers evenly over this long (e.g.
+    /// "10s") instead of starting them all at once
+    #[serde(default)]
+    pub ramp_up: Option<String>,
+
+    /// Random offset added to each worker's ramp-up start, as a fraction
+    /// of the gap between starts (e.g. 0.2 for ±20%)
+    #[serde(default)]
+    pub ramp_up_jitter_pct: Option<f64>,
+
+    /// Delay between starting each worker in sync mode, in milliseconds
+    #[serde(default = "default_sync_delay_ms")]
+    pub worker_stagger_ms: u64,
+
+    /// Think time after each iteration in sync mode, in milliseconds
+    #[serde(default = "default_sync_delay_ms")]
+    pub think_time_ms: u64,
+
+    /// Workload model: "closed" (default, workers wait for each response)
+    /// or "open" (iterations launched at `arrival_rate` regardless)
+    #[serde(default)]
+    pub workload_model: Option<String>,
+
+    /// Iterations launched per second in the open workload model
+    #[serde(default)]
+    pub arrival_rate: Option<f64>,
+
+    /// Cap on in-flight iterations in the open workload model; launches
+    /// beyond it are dropped and counted
+    #[serde(default)]
+    pub max_outstanding: Option<usize>,
+
+    /// Live progress update interval in milliseconds (0 disables updates)
+    #[serde(default = "default_ui_refresh_ms")]
+    pub ui_refresh_ms: u64,
+
+    /// Inject a Content-Type header based on the body format when none is set
+    #[serde(default = "default_true")]
+    pub auto_content_type: bool,
+
+    /// Leave out scenario headers whose value is empty after variable
+    /// substitution instead of sending them empty
+    #[serde(default = "default_true")]
+    pub omit_empty_headers: bool,
+
+    /// Gzip request bodies and send `Content-Encoding: gzip`
+    #[serde(default)]
+    pub compress_body: bool,
+
+    /// Send JSON bodies compactly, dropping the indentation of the YAML
+    #[serde(default = "default_true")]
+    pub minify_json_body: bool,
+
+    /// Send JSON bodies indented for debugging; takes precedence over
+    /// `minify_json_body`
+    #[serde(default)]
+    pub pretty_print_json_body: bool,
+
+    /// Fail a request instead of sending it when a `{{ variable }}` without
+    /// a default is not set
+    #[serde(default)]
+    pub strict_variables: bool,
+
+    /// Reject a config with extracted variables that are never used or used
+    /// variables that are never extracted, instead of warning about them
+    #[serde(default)]
+    pub strict_variable_usage: bool,
+
+    /// Reject a body on a GET or HEAD request instead of warning about it
+    #[serde(default)]
+    pub strict_bodies: bool,
+
+    /// Send a W3C `traceparent` header on every HTTP request, reusing the
+    /// `trace_id` and `span_id` variables when set
+    #[serde(default)]
+    pub tracing_enabled: bool,
+
+    /// Header carrying a fresh UUID on every request, e.g. "X-Request-Id",
+    /// to find requests in server-side logs and traces. Headers that already
+    /// set it are left alone.
+    #[serde(default, alias = "request_id_header")]
+    pub trace_header: Option<String>,
+
+    /// Seed for all randomness; worker N uses `seed + N`. Generated and
+    /// logged when unset so a run can be replayed.
+    #[serde(default)]
+    pub seed: Option<u64>,
+
+    /// Transparently decompress gzip, brotli and deflate responses; disable
+    /// to measure wire bytes
+    #[serde(default = "default_true")]
+    pub decompress: bool,
+
+    /// On a 429, pause the worker for the `Retry-After` delay and count the
+    /// response as rate limited rather than failed
+    #[serde(default)]
+    pub respect_rate_limit: bool,
+
+    /// `Accept-Encoding` sent with every request, e.g. "gzip, br" or
+    /// "identity"; only the listed encodings are decompressed
+    #[serde(default)]
+    pub accept_encoding: Option<String>,
+
+    /// Hostname to "IP:port" overrides that bypass DNS. The port in the
+    /// request URL still decides which port is used.
+    #[serde(default)]
+    pub resolve: HashMap<String, String>,
+
+    /// Seconds a host's DNS lookup is reused by new connections (default
+    /// 300); 0 disables the cache
+    #[serde(default)]
+    pub dns_cache_ttl_secs: Option<u64>,
+
+    /// Local IP outgoing connections are bound to, to pick the egress
+    /// interface on multi-homed hosts
+    #[serde(default)]
+    pub local_address: Option<String>,
+
+    /// Most idle connections kept open per host for reuse
+    #[serde(default = "default_pool_max_idle_per_host")]
+    pub pool_max_idle_per_host: usize,
+
+    /// How long an idle pooled connection is kept, e.g. "90s" (the default)
+    #[serde(default)]
+    pub pool_idle_timeout: Option<String>,
+
+    /// Longest a worker's connection pool is reused, e.g. "5m"; after that
+    /// the pool is replaced and new connections are opened
+    #[serde(default)]
+    pub connection_ttl: Option<String>,
+
+    /// Idle time before TCP keep-alive probes start on open connections;
+    /// off when unset. reqwest only exposes this idle time, so the probe
+    /// interval and count are the OS defaults (`tcp_keepalive_intvl` and
+    /// `tcp_keepalive_probes` on Linux) and have no option of their own.
+    #[serde(default)]
+    pub tcp_keepalive: Option<String>,
+
+    /// Disable Nagle's algorithm so small requests are sent immediately
+    #[serde(default = "default_true")]
+    pub tcp_nodelay: bool,
+
+    /// Follow 3xx redirects; when off, the 3xx response itself is recorded
+    /// with its single-hop latency and its `Location` header can be extracted
+    #[serde(default = "default_true")]
+    pub follow_redirects: bool,
+
+    /// Record DNS, connect, TLS and time-to-first-byte per request, sending
+    /// over HTTP/1.1 through a timing connection instead of the pooled
+    /// client. Adds overhead; responses are not decompressed.
+    #[serde(default)]
+    pub detailed_timing: bool,
+
+    /// Rolling P99 latency (ms) above which a live warning is shown
+    #[serde(default)]
+    pub alert_p99_ms: Option<u64>,
+
+    /// Decimal places of the latencies shown in the terminal; whole
+    /// milliseconds by default
+    #[serde(default)]
+    pub latency_precision: usize,
+
+    /// Apdex satisfied threshold T (ms); tolerating up to 4T
+    #[serde(default)]
+    pub apdex_threshold_ms: Option<u64>,
+
+    /// SLA latency (ms); requests slower than this are counted as over SLA
+    #[serde(default)]
+    pub sla_latency_ms: Option<u64>,
+
+    /// Lowest latency the HDR histogram tells apart, in ms (default 1);
+    /// faster requests share its lowest bucket
+    #[serde(default)]
+    pub histogram_min_ms: Option<u64>,
+
+    /// Highest latency the HDR histogram tracks, in ms (default 60000);
+    /// slower requests are clamped to it
+    #[serde(default)]
+    pub histogram_max_ms: Option<u64>,
+
+    /// Significant figures the histogram keeps, 1-5 (default 3)
+    #[serde(default)]
+    pub histogram_significant_figures: Option<u8>,
+
+    /// Fraction of requests written to the request log (0.0-1.0)
+    #[serde(default)]
+    pub sample_rate: Option<f64>,
+
+    /// Fraction of requests captured for the HAR file (0.0-1.0)
+    #[serde(default)]
+    pub capture_sample_rate: Option<f64>,
+
+    /// Port for the live web dashboard; disabled when unset
+    #[serde(default)]
+    pub dashboard_port: Option<u16>,
+
     /// Output configuration
     pub output: OutputConfig,
+
+    /// Notification sent when the test completes
+    #[serde(default)]
+    pub notify: Option<NotifyConfig>,
+}
+
+/// Built-in default headers, the same a plain `curl` sends
+pub const BUILTIN_DEFAULT_HEADERS: [(&str, &str); 1] = [("Accept", "*/*")];
+
+/// `default_headers` setting
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum DefaultHeaders {
+    Enabled(bool),
+    Custom(HashMap<String, String>),
+}
+
+impl Default for DefaultHeaders {
+    fn default() -> Self {
+        DefaultHeaders::Enabled(true)
+    }
+}
+
+impl DefaultHeaders {
+    /// Default headers to send
+    pub fn resolved(&self) -> HashMap<String, String> {
+        match self {
+            DefaultHeaders::Enabled(true) => BUILTIN_DEFAULT_HEADERS
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            DefaultHeaders::Enabled(false) => HashMap::new(),
+            DefaultHeaders::Custom(headers) => headers.clone(),
+        }
+    }
 }
 
 /// Multipart form data part
@@ -64,6 +371,45 @@ pub struct MultipartPart {
     pub value: Option<String>,
 }
 
+/// Named simple request in a weighted traffic mix
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WeightedRequest {
+    /// Name results are grouped under
+    pub name: String,
+
+    /// HTTP method (default: GET)
+    #[serde(default)]
+    pub method: Option<String>,
+
+    /// Path appended to `target`, or a full URL (default: `target`)
+    #[serde(default)]
+    pub url: Option<String>,
+
+    /// Headers added to the global `headers`
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+
+    /// Request body
+    #[serde(default)]
+    pub body: Option<String>,
+
+    /// Relative share of iterations
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+}
+
+impl WeightedRequest {
+    /// Full URL of the request against `target`
+    pub fn resolved_url(&self, target: Option<&str>) -> String {
+        let target = target.unwrap_or_default();
+        match self.url {
+            Some(ref url) if is_full_url(url) => url.clone(),
+            Some(ref url) => format!("{}{}", target.trim_end_matches('/'), url),
+            None => target.to_string(),
+        }
+    }
+}
+
 /// Scenario step definition
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Scenario {
@@ -80,86 +426,1268 @@ pub struct Scenario {
     #[serde(default)]
     pub headers: HashMap<String, String>,
 
+    /// `Host` header independent of the URL, e.g. when calling an ingress by
+    /// IP; `{{ variable }}` placeholders are substituted
+    #[serde(default)]
+    pub host: Option<String>,
+
     /// Request body
     #[serde(default)]
     pub body: Option<String>,
 
+    /// Tera template file rendered with the variables as context to make
+    /// the body, for loops and conditionals a plain `body` can't express
+    #[serde(default)]
+    pub body_template_file: Option<String>,
+
     /// Multipart form data
     #[serde(default)]
     pub multipart: Option<Vec<MultipartPart>>,
 
+    /// URL-encoded form fields sent as the body; `{{ variable }}`
+    /// placeholders are substituted in names and values
+    #[serde(default)]
+    pub form: Option<HashMap<String, String>>,
+
     /// Variable extraction rules
     #[serde(default)]
-    pub extract: HashMap<String, String>,
+    pub extract: HashMap<String, ExtractRule>,
+
+    /// Store the whole raw response body in this variable
+    #[serde(default)]
+    pub capture_body_as: Option<String>,
+
+    /// How `capture_body_as` stores the body: `text`, the default, which
+    /// fails the step on a body that is not valid UTF-8, or `base64`
+    #[serde(default)]
+    pub capture_body_encoding: Option<String>,
+
+    /// Expected SHA-256 of the response body as hex, or a `{{ variable }}`
+    /// holding one; a different body is recorded as an assertion failure
+    #[serde(default)]
+    pub assert_response_hash: Option<String>,
+
+    /// Store the SHA-256 of the response body, as hex, in this variable
+    #[serde(default)]
+    pub capture_response_hash_as: Option<String>,
 
     /// Dependency on previous step
     #[serde(default)]
     pub depends_on: Option<String>,
+
+    /// Run this step this many times in a row (0 or unset means once)
+    #[serde(default)]
+    pub repeat: Option<u32>,
+
+    /// Run this many concurrent copies of the step from the same variables
+    /// (default 1)
+    #[serde(default)]
+    pub concurrency: Option<usize>,
+
+    /// Only run when this condition on the variables holds:
+    /// `var == value`, `var != value` or `var exists`
+    #[serde(default)]
+    pub when: Option<String>,
+
+    /// Skip the step, recording it as skipped, when this condition holds
+    #[serde(default)]
+    pub skip_if: Option<SkipCondition>,
+
+    /// Response header holding the latency to record in milliseconds, e.g.
+    /// one set by a proxy for its upstream; the measured time is kept as
+    /// `wall_latency_ms`
+    #[serde(default)]
+    pub latency_from_header: Option<String>,
+
+    /// Criteria a response must meet to pass; failures are recorded as
+    /// assertion errors
+    #[serde(default)]
+    pub success_if: Option<SuccessCriteria>,
+
+    /// On a 429 with a `Retry-After` header, wait and resend the step
+    /// instead of recording the response
+    #[serde(default = "default_true")]
+    pub respect_retry_after: bool,
+
+    /// Longest `Retry-After` delay waited before a resend, in seconds
+    #[serde(default = "default_max_retry_after_secs")]
+    pub max_retry_after_secs: u64,
+
+    /// Abort the chain iteration when this step fails, whatever
+    /// `on_scenario_failure` says
+    #[serde(default)]
+    pub critical: bool,
+
+    /// Step protocol: "http" (default) or "websocket"
+    #[serde(default)]
+    pub protocol: Option<String>,
+
+    /// Timeout for a WebSocket exchange or SSE stream, in seconds
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+
+    /// Timeout for this step's HTTP request (e.g. "1s", "500ms", "1m"),
+    /// overriding the client default of 30s
+    #[serde(default)]
+    pub timeout: Option<String>,
+
+    /// Read this many Server-Sent Events from the response, then disconnect
+    #[serde(default)]
+    pub sse_event_count: Option<usize>,
+
+    /// GraphQL request sent as the JSON body
+    #[serde(default)]
+    pub graphql: Option<GraphqlConfig>,
+
+    /// Override the global `compress_body` for this step
+    #[serde(default)]
+    pub compress_body: Option<bool>,
+
+    /// Steps run before each execution of this one, sharing its variables.
+    /// If one fails the step is skipped.
+    #[serde(default)]
+    pub before_each: Vec<Scenario>,
+
+    /// Steps run after each execution of this one, even when it or a
+    /// `before_each` hook failed
+    #[serde(default)]
+    pub after_each: Vec<Scenario>,
 }
 
-/// Output configuration
-#[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct OutputConfig {
-    /// JSON output file path
-    pub json: String,
+/// Where a variable is extracted from in a step's response
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum ExtractRule {
+    /// JSONPath into the response body, e.g. "$.token"
+    JsonPath(String),
+    /// Value of a response header, e.g. `{ header: "X-Trace-Id" }`
+    Header { header: String },
+    /// JSONPath match passed through one of `EXTRACT_TRANSFORMS`, e.g.
+    /// `{ json_path: "$.items", transform: "json_encode" }`
+    Transformed {
+        json_path: String,
+        transform: String,
+    },
+}
 
-    /// HTML output file path
-    pub html: String,
+impl ExtractRule {
+    /// JSONPath into the response body, if the rule reads the body
+    pub fn json_path(&self) -> Option<&str> {
+        match self {
+            ExtractRule::JsonPath(json_path) | ExtractRule::Transformed { json_path, .. } => {
+                Some(json_path)
+            }
+            ExtractRule::Header { .. } => None,
+        }
+    }
 }
 
-fn default_concurrency() -> usize {
-    10
+/// GraphQL query or mutation for a scenario step
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GraphqlConfig {
+    /// Path to a file containing the query
+    #[serde(default)]
+    pub query_file: Option<String>,
+
+    /// Inline query
+    #[serde(default)]
+    pub query: Option<String>,
+
+    /// Query variables; `{{ variable }}` placeholders in string values are
+    /// substituted
+    #[serde(default)]
+    pub variables: Option<serde_json::Value>,
 }
 
-fn default_duration() -> String {
-    "30s".to_string()
+impl Scenario {
+    /// Whether this step is a WebSocket message exchange
+    pub fn is_websocket(&self) -> bool {
+        self.protocol.as_deref() == Some("websocket")
+    }
+
+    /// Whether variables should be extracted from the response body; HEAD
+    /// responses never have one
+    pub fn extracts_from_body(&self) -> bool {
+        self.extract.values().any(|rule| rule.json_path().is_some())
+            && !self.method.eq_ignore_ascii_case("HEAD")
+    }
+
+    /// Whether the response body is hashed, to check or capture it
+    pub fn hashes_response(&self) -> bool {
+        self.assert_response_hash.is_some() || self.capture_response_hash_as.is_some()
+    }
+
+    /// Whether the step has `before_each` or `after_each` hooks
+    pub fn has_hooks(&self) -> bool {
+        !self.before_each.is_empty() || !self.after_each.is_empty()
+    }
 }
 
-fn default_mode() -> String {
-    "async".to_string()
+/// Parsed `Scenario::when` condition
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Equals(String, String),
+    NotEquals(String, String),
+    Exists(String),
 }
 
-impl Config {
-    /// Load configuration from YAML file
-    pub fn from_file(path: &PathBuf) -> anyhow::Result<Self> {
-        let content = std::fs::read_to_string(path)?;
-        let config: Config = serde_yaml::from_str(&content)?;
-        config.validate()?;
-        Ok(config)
+impl Condition {
+    /// Evaluate against the current variables; a missing variable never
+    /// equals anything
+    pub fn evaluate(&self, variables: &HashMap<String, String>) -> bool {
+        match self {
+            Condition::Equals(name, value) => variables.get(name) == Some(value),
+            Condition::NotEquals(name, value) => variables.get(name) != Some(value),
+            Condition::Exists(name) => variables.contains_key(name),
+        }
     }
+}
 
-    /// Validate configuration
-    fn validate(&self) -> anyhow::Result<()> {
-        // Check if we have either simple mode or scenarios
-        if self.scenarios.is_empty() && self.target.is_none() {
-            anyhow::bail!("Either 'target' or 'scenarios' must be specified");
+impl Condition {
+    /// Variable the condition tests
+    pub fn variable(&self) -> &str {
+        match self {
+            Condition::Equals(name, _)
+            | Condition::NotEquals(name, _)
+            | Condition::Exists(name) => name,
         }
+    }
+}
 
-        // Validate mode
-        if self.mode != "async" && self.mode != "sync" {
-            anyhow::bail!("Mode must be either 'async' or 'sync'");
+impl FromStr for Condition {
+    type Err = anyhow::Error;
+
+    fn from_str(expression: &str) -> anyhow::Result<Self> {
+        let expression = expression.trim();
+
+        // Values may be quoted to keep surrounding whitespace
+        let operand = |s: &str| s.trim().trim_matches('"').to_string();
+
+        if let Some((name, value)) = expression.split_once("==") {
+            Ok(Condition::Equals(operand(name), operand(value)))
+        } else if let Some((name, value)) = expression.split_once("!=") {
+            Ok(Condition::NotEquals(operand(name), operand(value)))
+        } else if let Some(name) = expression.strip_suffix(" exists") {
+            Ok(Condition::Exists(operand(name)))
+        } else {
+            anyhow::bail!(
+                "Invalid condition '{}': expected 'var == value', 'var != value' or 'var exists'",
+                expression
+            )
         }
+    }
+}
 
-        // Validate concurrency
-        if self.concurrency == 0 {
-            anyhow::bail!("Concurrency must be greater than 0");
+/// `Scenario::skip_if` test of one variable
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SkipCondition {
+    pub variable: String,
+    /// "eq", "ne", "gt", "lt", "contains" or "empty"
+    pub operator: String,
+    /// Operand; not used by "empty"
+    #[serde(default)]
+    pub value: Option<String>,
+}
+
+impl SkipCondition {
+    /// Evaluate against the current variables. A missing variable is
+    /// "empty" and "ne" anything; `gt`/`lt` compare numerically.
+    pub fn evaluate(&self, variables: &HashMap<String, String>) -> bool {
+        let actual = variables.get(&self.variable);
+        let value = self.value.as_deref().unwrap_or_default();
+        let number = |s: &str| s.trim().parse::<f64>().ok();
+
+        match self.operator.as_str() {
+            "eq" => actual.is_some_and(|a| a == value),
+            "ne" => actual.is_none_or(|a| a != value),
+            "gt" | "lt" => match (actual.and_then(|a| number(a)), number(value)) {
+                (Some(a), Some(v)) if self.operator == "gt" => a > v,
+                (Some(a), Some(v)) => a < v,
+                _ => false,
+            },
+            "contains" => actual.is_some_and(|a| a.contains(value)),
+            "empty" => actual.is_none_or(|a| a.is_empty()),
+            _ => false,
         }
+    }
 
-        // Validate multipart parts
-        if let Some(ref parts) = self.multipart {
-            for part in parts {
-                if part.part_type == "file" && part.path.is_none() {
-                    anyhow::bail!("Multipart file type requires 'path' field");
-                }
-                if part.part_type == "field" && part.value.is_none() {
-                    anyhow::bail!("Multipart field type requires 'value' field");
-                }
+    fn validate(&self) -> anyhow::Result<()> {
+        match self.operator.as_str() {
+            "empty" => Ok(()),
+            "eq" | "ne" | "contains" if self.value.is_some() => Ok(()),
+            "gt" | "lt" => match self.value.as_deref().map(|v| v.trim().parse::<f64>()) {
+                Some(Ok(_)) => Ok(()),
+                _ => anyhow::bail!("operator '{}' needs a numeric 'value'", self.operator),
+            },
+            "eq" | "ne" | "contains" => {
+                anyhow::bail!("operator '{}' needs a 'value'", self.operator)
             }
+            other => anyhow::bail!(
+                "unknown operator '{}', expected eq, ne, gt, lt, contains or empty",
+                other
+            ),
         }
+    }
+}
 
-        // Validate scenarios
-        for scenario in &self.scenarios {
-            if let Some(ref parts) = scenario.multipart {
+/// `Scenario::success_if` criteria; every one that is set must hold
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SuccessCriteria {
+    /// Accepted status codes; when set, they replace the "4xx and 5xx
+    /// fail" rule
+    #[serde(default)]
+    pub status: Vec<u16>,
+    /// Slowest accepted latency
+    #[serde(default)]
+    pub max_latency_ms: Option<u64>,
+}
+
+impl SuccessCriteria {
+    /// Why a response with `status` taking `latency_ms` fails the
+    /// criteria, if it does
+    pub fn check(&self, status: u16, latency_ms: u64) -> Option<String> {
+        if !self.status.is_empty() && !self.status.contains(&status) {
+            let expected: Vec<String> = self.status.iter().map(u16::to_string).collect();
+            return Some(format!(
+                "bad status {}, expected {}",
+                status,
+                expected.join(" or ")
+            ));
+        }
+
+        match self.max_latency_ms {
+            Some(max) if latency_ms > max => Some(format!(
+                "too slow: {}ms, expected at most {}ms",
+                latency_ms, max
+            )),
+            _ => None,
+        }
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if let Some(status) = self.status.iter().find(|s| !(100..=599).contains(*s)) {
+            anyhow::bail!("status {} is not an HTTP status code", status);
+        }
+        if self.status.is_empty() && self.max_latency_ms.is_none() {
+            anyhow::bail!("expected 'status' and/or 'max_latency_ms'");
+        }
+        Ok(())
+    }
+}
+
+/// Output configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct OutputConfig {
+    /// JSON output file path
+    pub json: String,
+
+    /// HTML output file path
+    pub html: String,
+
+    /// JUnit XML output file path
+    #[serde(default)]
+    pub junit: Option<String>,
+
+    /// NDJSON per-request log file path
+    #[serde(default)]
+    pub request_log: Option<String>,
+
+    /// NDJSON file streaming every result as it completes, then the summary
+    #[serde(default)]
+    pub json_stream: Option<String>,
+
+    /// HAR 1.2 file of sampled request/response pairs
+    #[serde(default)]
+    pub har: Option<String>,
+
+    /// Grafana snapshot JSON (`POST /api/snapshots` body) with the run's
+    /// time series inlined
+    #[serde(default)]
+    pub grafana_dashboard: Option<String>,
+
+    /// Append the run timestamp to every output filename
+    #[serde(default)]
+    pub timestamp_outputs: bool,
+
+    /// Indent the JSON report; compact by default to keep large runs small
+    #[serde(default)]
+    pub pretty_json: bool,
+
+    /// Prometheus Pushgateway base URL the summary gauges are pushed to
+    #[serde(default)]
+    pub prometheus_push_gateway: Option<String>,
+
+    /// Job the gauges are pushed under (default "flux")
+    #[serde(default)]
+    pub prometheus_job: Option<String>,
+}
+
+/// One phase of a multi-phase test (e.g. smoke, load, stress)
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Phase {
+    pub name: String,
+
+    /// Number of concurrent workers during the phase
+    pub concurrency: usize,
+
+    /// Phase duration (e.g., "10s", "5m")
+    pub duration: String,
+
+    /// Cap on iterations per second across all workers
+    #[serde(default)]
+    pub rate_limit: Option<f64>,
+}
+
+/// Unary gRPC call configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GrpcConfig {
+    /// Path to the `.proto` file defining the service; imports are resolved
+    /// relative to its directory
+    pub proto_file: String,
+
+    /// Fully qualified service name (e.g. "helloworld.Greeter")
+    pub service: String,
+
+    /// Method name within the service
+    pub method: String,
+
+    /// Request message as JSON
+    pub request_json: String,
+
+    /// Server address (e.g. "http://localhost:50051")
+    pub endpoint: String,
+}
+
+/// Completion notification configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct NotifyConfig {
+    /// URL the summary is POSTed to
+    pub webhook_url: String,
+
+    /// Payload format: "json" (default) or "slack"
+    #[serde(default)]
+    pub format: Option<String>,
+}
+
+impl OutputConfig {
+    /// Point every enabled report at a file inside `dir`, creating it if needed
+    pub fn use_output_dir(&mut self, dir: &Path) -> anyhow::Result<()> {
+        std::fs::create_dir_all(dir)?;
+
+        let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+        self.json = path("flux_report.json");
+        self.html = path("flux_report.html");
+        if self.junit.is_some() {
+            self.junit = Some(path("flux_report.xml"));
+        }
+        if self.request_log.is_some() {
+            self.request_log = Some(path("flux_requests.ndjson"));
+        }
+        if self.json_stream.is_some() {
+            self.json_stream = Some(path("flux_stream.ndjson"));
+        }
+        if self.har.is_some() {
+            self.har = Some(path("flux_requests.har"));
+        }
+        if self.grafana_dashboard.is_some() {
+            self.grafana_dashboard = Some(path("flux_grafana.json"));
+        }
+
+        Ok(())
+    }
+
+    /// Expand `{timestamp}` and `{run_id}` placeholders in every output path.
+    /// With `timestamp_outputs`, paths without placeholders get `-<timestamp>`
+    /// appended to the file stem.
+    pub fn expand_placeholders(&mut self, timestamp: &str, run_id: &str) {
+        let timestamp_outputs = self.timestamp_outputs;
+        let expand = |path: &str| -> String {
+            if path.contains("{timestamp}") || path.contains("{run_id}") {
+                return path
+                    .replace("{timestamp}", timestamp)
+                    .replace("{run_id}", run_id);
+            }
+            if !timestamp_outputs {
+                return path.to_string();
+            }
+
+            let path = Path::new(path);
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            let file_name = match path.extension() {
+                Some(ext) => format!("{}-{}.{}", stem, timestamp, ext.to_string_lossy()),
+                None => format!("{}-{}", stem, timestamp),
+            };
+            path.with_file_name(file_name)
+                .to_string_lossy()
+                .into_owned()
+        };
+
+        self.json = expand(&self.json);
+        self.html = expand(&self.html);
+        for path in [
+            &mut self.junit,
+            &mut self.request_log,
+            &mut self.json_stream,
+            &mut self.har,
+            &mut self.grafana_dashboard,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            *path = expand(path);
+        }
+    }
+}
+
+impl FromStr for Config {
+    type Err = anyhow::Error;
+
+    /// Parse and validate configuration from a YAML string
+    fn from_str(yaml: &str) -> anyhow::Result<Self> {
+        let config: Config = serde_yaml::from_str(yaml)?;
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+fn default_concurrency() -> usize {
+    10
+}
+
+fn default_duration() -> String {
+    "30s".to_string()
+}
+
+fn default_mode() -> String {
+    "async".to_string()
+}
+
+fn default_max_retry_after_secs() -> u64 {
+    60
+}
+
+fn default_sync_delay_ms() -> u64 {
+    10
+}
+
+fn default_pool_max_idle_per_host() -> usize {
+    100
+}
+
+fn default_ui_refresh_ms() -> u64 {
+    1000
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+/// Whether a URL is absolute rather than a path relative to the target
+fn is_full_url(url: &str) -> bool {
+    ["http://", "https://", "unix:"]
+        .iter()
+        .any(|scheme| url.starts_with(scheme))
+}
+
+/// Split placeholder contents into the variable name and optional default,
+/// `None` if they aren't a placeholder
+pub fn parse_placeholder(inner: &str) -> Option<(&str, Option<&str>)> {
+    let (name, filter) = match inner.split_once('|') {
+        Some((name, filter)) => (name.trim(), Some(filter.trim())),
+        None => (inner.trim(), None),
+    };
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return None;
+    }
+
+    let default = match filter {
+        Some(filter) => {
+            let value = filter.strip_prefix("default(")?.strip_suffix(')')?.trim();
+            let quoted = |q| value.strip_prefix(q)?.strip_suffix(q);
+            Some(quoted('"').or_else(|| quoted('\''))?)
+        }
+        None => None,
+    };
+
+    Some((name, default))
+}
+
+/// Replace `${NAME}` with the environment variable `NAME`
+fn expand_env(value: &str) -> anyhow::Result<String> {
+    let mut result = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        let name = &rest[start + 2..start + len];
+        let env = std::env::var(name)
+            .map_err(|_| anyhow::anyhow!("environment variable '{}' is not set", name))?;
+        result.push_str(&rest[..start]);
+        result.push_str(&env);
+        rest = &rest[start + len + 1..];
+    }
+
+    result.push_str(rest);
+    Ok(result)
+}
+
+/// Replace `{{ $function }}` placeholders with their value, keeping any
+/// other placeholder for substitution at request time
+fn substitute_functions(template: &str, functions: &HashMap<String, String>) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start..].find("}}") else {
+            break;
+        };
+        let placeholder = &rest[start..start + len + 2];
+        result.push_str(&rest[..start]);
+        rest = &rest[start + len + 2..];
+
+        match parse_placeholder(&placeholder[2..len]).and_then(|(name, _)| functions.get(name)) {
+            Some(value) => result.push_str(value),
+            None => result.push_str(placeholder),
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// `{{ variable }}` placeholders in `template` as name and optional default
+fn placeholders(template: &str) -> Vec<(&str, Option<&str>)> {
+    let mut found = Vec::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start..].find("}}") else {
+            break;
+        };
+        found.extend(parse_placeholder(&rest[start + 2..start + len]));
+        rest = &rest[start + len + 2..];
+    }
+
+    found
+}
+
+/// Every string in a JSON value
+fn json_strings<'a>(value: &'a serde_json::Value, strings: &mut Vec<&'a str>) {
+    match value {
+        serde_json::Value::String(s) => strings.push(s),
+        serde_json::Value::Array(items) => items.iter().for_each(|v| json_strings(v, strings)),
+        serde_json::Value::Object(fields) => fields.values().for_each(|v| json_strings(v, strings)),
+        _ => {}
+    }
+}
+
+/// Parse a duration string such as "500ms", "30s", "5m" or "2h"; a bare
+/// number is seconds
+pub fn parse_duration(value: &str) -> anyhow::Result<Duration> {
+    let value = value.trim();
+
+    if let Some(stripped) = value.strip_suffix("ms") {
+        Ok(Duration::from_millis(stripped.parse()?))
+    } else if let Some(stripped) = value.strip_suffix('s') {
+        Ok(Duration::from_secs(stripped.parse()?))
+    } else if let Some(stripped) = value.strip_suffix('m') {
+        Ok(Duration::from_secs(stripped.parse::<u64>()? * 60))
+    } else if let Some(stripped) = value.strip_suffix('h') {
+        Ok(Duration::from_secs(stripped.parse::<u64>()? * 3600))
+    } else {
+        // Default to seconds if no suffix
+        Ok(Duration::from_secs(value.parse()?))
+    }
+}
+
+/// Accepted `on_scenario_failure` values
+pub const SCENARIO_FAILURE_POLICIES: [&str; 3] = ["continue", "abort", "skip-remaining"];
+
+/// Accepted `capture_body_encoding` values
+pub const CAPTURE_BODY_ENCODINGS: [&str; 2] = ["text", "base64"];
+
+/// Most decimal places of a millisecond latency, i.e. nanoseconds
+const MAX_LATENCY_PRECISION: usize = 6;
+
+/// In-flight iteration cap in the open workload model when unset
+const DEFAULT_MAX_OUTSTANDING: usize = 1000;
+
+/// Range of `arrival_rate` and phase `rate_limit` (per second). The tick
+/// period `1 / rate` must fit a `Duration` and stay above zero.
+const RATE_RANGE: std::ops::RangeInclusive<f64> = 0.001..=1_000_000.0;
+
+/// Transforms an `ExtractRule::Transformed` can apply to its match
+pub const EXTRACT_TRANSFORMS: [&str; 5] = [
+    "json_encode",
+    "url_encode",
+    "base64_encode",
+    "base64_decode",
+    "trim",
+];
+
+/// Methods accepted without `custom_methods` or `allow_custom_method`
+pub const STANDARD_METHODS: [&str; 9] = [
+    "GET", "POST", "PUT", "PATCH", "DELETE", "HEAD", "OPTIONS", "TRACE", "CONNECT",
+];
+
+/// Prefix of the environment variables read by `Config::from_env`
+pub const ENV_PREFIX: &str = "FLUX_";
+
+/// Report paths used by `Config::from_env` unless `FLUX_OUTPUT` is set
+const ENV_DEFAULT_OUTPUT: (&str, &str) = ("/app/results/output.json", "/app/results/report.html");
+
+impl Config {
+    /// Load configuration from YAML file
+    pub fn from_file(path: &PathBuf) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        content.parse()
+    }
+
+    /// Build configuration from `FLUX_<FIELD>` environment variables, e.g.
+    /// `FLUX_TARGET`, `FLUX_CONCURRENCY` or `FLUX_HEADERS` as a JSON object
+    pub fn from_env() -> anyhow::Result<Self> {
+        Self::from_vars(std::env::vars())
+    }
+
+    /// Build configuration from `FLUX_<FIELD>` name/value pairs. Values are
+    /// read as YAML, so JSON works for maps and lists, falling back to the
+    /// raw string for fields expecting one; unknown names are ignored.
+    fn from_vars(vars: impl IntoIterator<Item = (String, String)>) -> anyhow::Result<Self> {
+        use serde_yaml::{Mapping, Value};
+
+        let (json, html) = ENV_DEFAULT_OUTPUT;
+        let mut mapping = Mapping::new();
+        mapping.insert(
+            "output".into(),
+            Value::Mapping(Mapping::from_iter([
+                ("json".into(), json.into()),
+                ("html".into(), html.into()),
+            ])),
+        );
+
+        // Every field serializes, so a default config lists them all
+        let defaults = serde_yaml::to_value(serde_yaml::from_value::<Config>(Value::Mapping(
+            mapping.clone(),
+        ))?)?;
+        let fields = defaults
+            .as_mapping()
+            .ok_or_else(|| anyhow::anyhow!("Config did not serialize to a mapping"))?;
+
+        let mut vars: Vec<(String, String)> = vars
+            .into_iter()
+            .filter(|(name, _)| {
+                name.strip_prefix(ENV_PREFIX)
+                    .is_some_and(|field| fields.contains_key(field.to_lowercase().as_str()))
+            })
+            .collect();
+        vars.sort();
+
+        for (name, raw) in &vars {
+            let field = name[ENV_PREFIX.len()..].to_lowercase();
+            let parsed = serde_yaml::from_str::<Value>(raw).ok();
+
+            let mut error = None;
+            for value in parsed.into_iter().chain([Value::String(raw.clone())]) {
+                let mut candidate = mapping.clone();
+                candidate.insert(field.as_str().into(), value);
+                match serde_yaml::from_value::<Config>(Value::Mapping(candidate.clone())) {
+                    Ok(_) => {
+                        mapping = candidate;
+                        error = None;
+                        break;
+                    }
+                    Err(e) => error = Some(e),
+                }
+            }
+            if let Some(e) = error {
+                anyhow::bail!("Invalid {}: {}", name, e);
+            }
+        }
+
+        let required = [
+            "FLUX_TARGET",
+            "FLUX_SCENARIOS",
+            "FLUX_REQUESTS",
+            "FLUX_GRPC",
+        ];
+        if !vars
+            .iter()
+            .any(|(name, _)| required.contains(&name.as_str()))
+        {
+            anyhow::bail!(
+                "No configuration file found and FLUX_TARGET is not set (nor FLUX_SCENARIOS, FLUX_REQUESTS or FLUX_GRPC)"
+            );
+        }
+
+        let config: Config = serde_yaml::from_value(Value::Mapping(mapping))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// HTTP method used in simple mode
+    pub fn resolved_method(&self) -> &str {
+        self.method.as_deref().unwrap_or("GET")
+    }
+
+    /// Check a method is a valid token and, unless custom methods are
+    /// allowed, a standard one
+    fn validate_method(&self, method: &str) -> anyhow::Result<()> {
+        if reqwest::Method::from_str(method).is_err() {
+            anyhow::bail!("Invalid HTTP method '{}'", method);
+        }
+
+        let known = STANDARD_METHODS.contains(&method)
+            || self.custom_methods.iter().any(|custom| custom == method);
+        if !self.allow_custom_method && !known {
+            anyhow::bail!(
+                "Unsupported HTTP method '{}': expected one of {} (list it in 'custom_methods' to use it anyway)",
+                method,
+                STANDARD_METHODS.join(", ")
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Validate configuration
+    fn validate(&self) -> anyhow::Result<()> {
+        // Check if we have either simple mode or scenarios
+        if self.scenarios.is_empty()
+            && self.target.is_none()
+            && self.grpc.is_none()
+            && self.requests.is_empty()
+        {
+            anyhow::bail!("Either 'target' or 'scenarios' (or 'grpc') must be specified");
+        }
+
+        if let Some(ref method) = self.method {
+            self.validate_method(method)?;
+        }
+        for method in &self.custom_methods {
+            if reqwest::Method::from_str(method).is_err() {
+                anyhow::bail!("Invalid HTTP method '{}' in 'custom_methods'", method);
+            }
+        }
+
+        // Validate the request mix
+        if !self.requests.is_empty() {
+            if !self.scenarios.is_empty() || self.grpc.is_some() {
+                anyhow::bail!("'requests' cannot be combined with 'scenarios' or 'grpc'");
+            }
+            if self.requests.iter().all(|r| r.weight == 0) {
+                anyhow::bail!("At least one entry in 'requests' must have a weight above 0");
+            }
+        }
+        let mut request_names = HashSet::new();
+        for request in &self.requests {
+            if !request_names.insert(request.name.as_str()) {
+                anyhow::bail!("Duplicate request name '{}' in 'requests'", request.name);
+            }
+            if let Some(ref method) = request.method {
+                self.validate_method(method)
+                    .map_err(|e| anyhow::anyhow!("{} in request '{}'", e, request.name))?;
+            }
+            if self.target.is_none() && !is_full_url(request.url.as_deref().unwrap_or_default()) {
+                anyhow::bail!(
+                    "Request '{}' needs a full 'url' when no 'target' is set",
+                    request.name
+                );
+            }
+        }
+
+        // Validate mode
+        if self.mode != "async" && self.mode != "sync" {
+            anyhow::bail!("Mode must be either 'async' or 'sync'");
+        }
+
+        // Validate host mappings
+        for (host, addr) in &self.resolve {
+            addr.parse::<SocketAddr>().map_err(|_| {
+                anyhow::anyhow!(
+                    "Invalid address '{}' for host '{}' in 'resolve': expected IP:port",
+                    addr,
+                    host
+                )
+            })?;
+        }
+
+        if self.latency_precision > MAX_LATENCY_PRECISION {
+            anyhow::bail!(
+                "'latency_precision' must be at most {}",
+                MAX_LATENCY_PRECISION
+            );
+        }
+
+        let bounds = self.histogram_bounds();
+        if bounds.min_ms == 0 {
+            anyhow::bail!("'histogram_min_ms' must be at least 1");
+        }
+        if bounds.max_ms < bounds.min_ms.saturating_mul(2) {
+            anyhow::bail!("'histogram_max_ms' must be at least twice 'histogram_min_ms'");
+        }
+        if !(1..=5).contains(&bounds.significant_figures) {
+            anyhow::bail!("'histogram_significant_figures' must be between 1 and 5");
+        }
+
+        // Validate connection pool options
+        for (name, value) in [
+            ("pool_idle_timeout", &self.pool_idle_timeout),
+            ("connection_ttl", &self.connection_ttl),
+            ("tcp_keepalive", &self.tcp_keepalive),
+        ] {
+            if let Some(value) = value {
+                parse_duration(value).map_err(|e| anyhow::anyhow!("Invalid '{}': {}", name, e))?;
+            }
+        }
+
+        // Validate host override
+        if let Some(ref host) = self.host {
+            reqwest::header::HeaderValue::from_str(host)
+                .map_err(|_| anyhow::anyhow!("Invalid 'host' '{}'", host))?;
+        }
+
+        // Validate accepted encodings
+        if let Some(ref accept_encoding) = self.accept_encoding {
+            reqwest::header::HeaderValue::from_str(accept_encoding)
+                .map_err(|_| anyhow::anyhow!("Invalid 'accept_encoding' '{}'", accept_encoding))?;
+        }
+
+        // Validate trace header
+        if let Some(ref name) = self.trace_header {
+            reqwest::header::HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| anyhow::anyhow!("Invalid 'trace_header' name '{}'", name))?;
+        }
+
+        // Validate local address
+        if let Some(ref addr) = self.local_address {
+            addr.parse::<IpAddr>().map_err(|_| {
+                anyhow::anyhow!(
+                    "Invalid 'local_address' '{}': expected an IP address such as 10.0.0.5",
+                    addr
+                )
+            })?;
+        }
+
+        // Validate workload model
+        match self.workload_model.as_deref() {
+            None | Some("closed") => {}
+            Some("open") => {
+                let Some(rate) = self.arrival_rate else {
+                    anyhow::bail!("Open workload model requires an 'arrival_rate' greater than 0");
+                };
+                if !RATE_RANGE.contains(&rate) {
+                    anyhow::bail!(
+                        "'arrival_rate' must be between {} and {}",
+                        RATE_RANGE.start(),
+                        RATE_RANGE.end()
+                    );
+                }
+                if self.max_outstanding == Some(0) {
+                    anyhow::bail!("'max_outstanding' must be greater than 0");
+                }
+            }
+            Some(_) => anyhow::bail!("Workload model must be either 'closed' or 'open'"),
+        }
+
+        // Validate phases
+        if let Some(ref phases) = self.phases {
+            if phases.is_empty() {
+                anyhow::bail!("'phases' must contain at least one phase");
+            }
+            if self.is_open_model() {
+                anyhow::bail!("'phases' cannot be combined with the open workload model");
+            }
+            for phase in phases {
+                if phase.concurrency == 0 {
+                    anyhow::bail!(
+                        "Concurrency must be greater than 0 in phase '{}'",
+                        phase.name
+                    );
+                }
+                parse_duration(&phase.duration).map_err(|e| {
+                    anyhow::anyhow!("Invalid 'duration' in phase '{}': {}", phase.name, e)
+                })?;
+                if phase
+                    .rate_limit
+                    .is_some_and(|rate| !RATE_RANGE.contains(&rate))
+                {
+                    anyhow::bail!(
+                        "Rate limit must be between {} and {} in phase '{}'",
+                        RATE_RANGE.start(),
+                        RATE_RANGE.end(),
+                        phase.name
+                    );
+                }
+            }
+        }
+
+        // Validate concurrency
+        if self.concurrency == 0 {
+            anyhow::bail!("Concurrency must be greater than 0");
+        }
+
+        // Validate scenario order
+        if let Some(ref order) = self.scenario_order {
+            if order != "sequential" && order != "random" {
+                anyhow::bail!("Scenario order must be either 'sequential' or 'random'");
+            }
+        }
+        if let Some(ref policy) = self.on_scenario_failure {
+            if !SCENARIO_FAILURE_POLICIES.contains(&policy.as_str()) {
+                anyhow::bail!(
+                    "'on_scenario_failure' must be one of {}",
+                    SCENARIO_FAILURE_POLICIES.join(", ")
+                );
+            }
+        }
+        if self.scenario_chain_timeout_secs == Some(0) {
+            anyhow::bail!("'scenario_chain_timeout_secs' must be greater than 0");
+        }
+
+        // Validate ramp-up
+        if let Some(ref ramp_up) = self.ramp_up {
+            let ramp_up =
+                parse_duration(ramp_up).map_err(|e| anyhow::anyhow!("Invalid 'ramp_up': {}", e))?;
+            if ramp_up > parse_duration(&self.duration)? {
+                anyhow::bail!("'ramp_up' cannot be longer than 'duration'");
+            }
+        }
+        if let Some(jitter) = self.ramp_up_jitter_pct {
+            if self.ramp_up.is_none() {
+                anyhow::bail!("'ramp_up_jitter_pct' requires 'ramp_up'");
+            }
+            if !(0.0..=1.0).contains(&jitter) {
+                anyhow::bail!("'ramp_up_jitter_pct' must be between 0.0 and 1.0");
+            }
+        }
+
+        // Validate sample rate
+        if let Some(rate) = self.sample_rate {
+            if !(0.0..=1.0).contains(&rate) {
+                anyhow::bail!("Sample rate must be between 0.0 and 1.0");
+            }
+        }
+        if let Some(rate) = self.capture_sample_rate {
+            if !(0.0..=1.0).contains(&rate) {
+                anyhow::bail!("Capture sample rate must be between 0.0 and 1.0");
+            }
+        }
+
+        // Validate notification format
+        if let Some(ref notify) = self.notify {
+            if let Some(ref format) = notify.format {
+                if format != "json" && format != "slack" {
+                    anyhow::bail!("Notify format must be either 'json' or 'slack'");
+                }
+            }
+        }
+
+        // Validate multipart parts
+        if let Some(ref parts) = self.multipart {
+            for part in parts {
+                if part.part_type == "file" && part.path.is_none() {
+                    anyhow::bail!("Multipart file type requires 'path' field");
+                }
+                if part.part_type == "field" && part.value.is_none() {
+                    anyhow::bail!("Multipart field type requires 'value' field");
+                }
+            }
+        }
+
+        if self.compress_body && self.multipart.is_some() {
+            anyhow::bail!("'compress_body' cannot be combined with 'multipart'");
+        }
+
+        // Hooks only wrap steps of the load test, one level deep
+        if let Some(step) = self
+            .setup
+            .iter()
+            .chain(&self.teardown)
+            .find(|s| s.has_hooks())
+        {
+            anyhow::bail!(
+                "Setup and teardown scenario '{}' cannot have 'before_each' or 'after_each' hooks",
+                step.name
+            );
+        }
+        if let Some(hook) = self
+            .scenarios
+            .iter()
+            .flat_map(|s| s.before_each.iter().chain(&s.after_each))
+            .find(|h| h.has_hooks())
+        {
+            anyhow::bail!("Hook '{}' cannot have hooks of its own", hook.name);
+        }
+
+        // Validate scenarios
+        for scenario in self.steps() {
+            self.validate_method(&scenario.method)
+                .map_err(|e| anyhow::anyhow!("{} in scenario '{}'", e, scenario.name))?;
+
+            if let Some(ref protocol) = scenario.protocol {
+                if protocol != "http" && protocol != "websocket" {
+                    anyhow::bail!(
+                        "Protocol must be either 'http' or 'websocket' in scenario '{}'",
+                        scenario.name
+                    );
+                }
+            }
+
+            if scenario.multipart.is_some() && scenario.compress_body.unwrap_or(self.compress_body)
+            {
+                anyhow::bail!(
+                    "'compress_body' cannot be combined with 'multipart' in scenario '{}'",
+                    scenario.name
+                );
+            }
+
+            if let Some(ref graphql) = scenario.graphql {
+                if graphql.query.is_some() == graphql.query_file.is_some() {
+                    anyhow::bail!(
+                        "GraphQL requires exactly one of 'query' or 'query_file' in scenario '{}'",
+                        scenario.name
+                    );
+                }
+                if scenario.body.is_some()
+                    || scenario.body_template_file.is_some()
+                    || scenario.multipart.is_some()
+                    || scenario.form.is_some()
+                {
+                    anyhow::bail!(
+                        "GraphQL cannot be combined with 'body', 'body_template_file', 'multipart' or 'form' in scenario '{}'",
+                        scenario.name
+                    );
+                }
+            }
+
+            if let Some(ref hash) = scenario.assert_response_hash {
+                let is_digest = hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit());
+                if !is_digest && placeholders(hash).is_empty() {
+                    anyhow::bail!(
+                        "'assert_response_hash' must be a SHA-256 hex digest in scenario '{}'",
+                        scenario.name
+                    );
+                }
+            }
+
+            if scenario.form.is_some()
+                && (scenario.body.is_some()
+                    || scenario.body_template_file.is_some()
+                    || scenario.multipart.is_some())
+            {
+                anyhow::bail!(
+                    "'form' cannot be combined with 'body', 'body_template_file' or 'multipart' in scenario '{}'",
+                    scenario.name
+                );
+            }
+
+            if scenario.body_template_file.is_some()
+                && (scenario.body.is_some() || scenario.multipart.is_some())
+            {
+                anyhow::bail!(
+                    "'body_template_file' cannot be combined with 'body' or 'multipart' in scenario '{}'",
+                    scenario.name
+                );
+            }
+            if let Some(ref path) = scenario.body_template_file {
+                if !Path::new(path).is_file() {
+                    anyhow::bail!(
+                        "Body template file '{}' not found in scenario '{}'",
+                        path,
+                        scenario.name
+                    );
+                }
+            }
+
+            if let Some(ref timeout) = scenario.timeout {
+                parse_duration(timeout).map_err(|e| {
+                    anyhow::anyhow!("Invalid 'timeout' in scenario '{}': {}", scenario.name, e)
+                })?;
+            }
+
+            if scenario.concurrency == Some(0) {
+                anyhow::bail!(
+                    "Concurrency must be greater than 0 in scenario '{}'",
+                    scenario.name
+                );
+            }
+
+            if scenario.sse_event_count == Some(0) {
+                anyhow::bail!(
+                    "'sse_event_count' must be greater than 0 in scenario '{}'",
+                    scenario.name
+                );
+            }
+
+            if let Some(ref encoding) = scenario.capture_body_encoding {
+                if scenario.capture_body_as.is_none() {
+                    anyhow::bail!(
+                        "'capture_body_encoding' requires 'capture_body_as' in scenario '{}'",
+                        scenario.name
+                    );
+                }
+                if !CAPTURE_BODY_ENCODINGS.contains(&encoding.as_str()) {
+                    anyhow::bail!(
+                        "'capture_body_encoding' must be one of {} in scenario '{}'",
+                        CAPTURE_BODY_ENCODINGS.join(", "),
+                        scenario.name
+                    );
+                }
+            }
+
+            if let Some(ref when) = scenario.when {
+                when.parse::<Condition>().map_err(|e| {
+                    anyhow::anyhow!("Invalid 'when' in scenario '{}': {}", scenario.name, e)
+                })?;
+            }
+
+            if let Some(ref skip_if) = scenario.skip_if {
+                skip_if.validate().map_err(|e| {
+                    anyhow::anyhow!("Invalid 'skip_if' in scenario '{}': {}", scenario.name, e)
+                })?;
+            }
+
+            for (name, rule) in &scenario.extract {
+                if let ExtractRule::Transformed { transform, .. } = rule {
+                    if !EXTRACT_TRANSFORMS.contains(&transform.as_str()) {
+                        anyhow::bail!(
+                            "Unknown transform '{}' for variable '{}' in scenario '{}': expected one of {}",
+                            transform,
+                            name,
+                            scenario.name,
+                            EXTRACT_TRANSFORMS.join(", ")
+                        );
+                    }
+                }
+            }
+
+            if let Some(ref header) = scenario.latency_from_header {
+                if reqwest::header::HeaderName::from_str(header).is_err() {
+                    anyhow::bail!(
+                        "Invalid header name '{}' for 'latency_from_header' in scenario '{}'",
+                        header,
+                        scenario.name
+                    );
+                }
+            }
+
+            if let Some(ref success_if) = scenario.success_if {
+                success_if.validate().map_err(|e| {
+                    anyhow::anyhow!(
+                        "Invalid 'success_if' in scenario '{}': {}",
+                        scenario.name,
+                        e
+                    )
+                })?;
+            }
+
+            if let Some(ref parts) = scenario.multipart {
                 for part in parts {
                     if part.part_type == "file" && part.path.is_none() {
                         anyhow::bail!(
@@ -177,25 +1705,292 @@ impl Config {
             }
         }
 
+        self.resolved_variables()?;
+        self.check_variables()?;
+        self.check_bodies()
+    }
+
+    /// Every step in running order: setup, each scenario between its
+    /// hooks, then teardown
+    pub fn steps(&self) -> impl Iterator<Item = &Scenario> + '_ {
+        let scenarios = self.scenarios.iter().flat_map(|scenario| {
+            scenario
+                .before_each
+                .iter()
+                .chain(std::iter::once(scenario))
+                .chain(&scenario.after_each)
+        });
+        self.setup.iter().chain(scenarios).chain(&self.teardown)
+    }
+
+    /// Local files the config reads, each with the field naming it: body
+    /// templates, multipart file parts, GraphQL query files and the gRPC
+    /// proto file
+    pub fn file_references(&self) -> Vec<(&'static str, &str)> {
+        let mut files = Vec::new();
+        let parts = std::iter::once(&self.multipart)
+            .chain(self.steps().map(|step| &step.multipart))
+            .flatten()
+            .flatten();
+        for part in parts {
+            if let Some(ref path) = part.path {
+                files.push(("multipart", path.as_str()));
+            }
+        }
+        for step in self.steps() {
+            if let Some(ref path) = step.body_template_file {
+                files.push(("body_template_file", path.as_str()));
+            }
+            if let Some(path) = step.graphql.as_ref().and_then(|g| g.query_file.as_ref()) {
+                files.push(("graphql.query_file", path.as_str()));
+            }
+        }
+        if let Some(ref grpc) = self.grpc {
+            files.push(("grpc.proto_file", grpc.proto_file.as_str()));
+        }
+        files
+    }
+
+    /// Warn about GET and HEAD requests carrying a body, which some servers
+    /// reject; with `strict_bodies` these are errors
+    fn check_bodies(&self) -> anyhow::Result<()> {
+        let mut requests = Vec::new();
+        if self.is_simple_mode() {
+            let has_body = self.body.is_some() || self.multipart.is_some();
+            requests.push(("simple request", self.resolved_method(), has_body));
+        }
+        for request in &self.requests {
+            let method = request.method.as_deref().unwrap_or("GET");
+            requests.push((request.name.as_str(), method, request.body.is_some()));
+        }
+        for step in self.steps() {
+            let has_body = step.body.is_some()
+                || step.body_template_file.is_some()
+                || step.multipart.is_some()
+                || step.form.is_some()
+                || step.graphql.is_some();
+            requests.push((step.name.as_str(), step.method.as_str(), has_body));
+        }
+
+        let problems: Vec<String> = requests
+            .into_iter()
+            .filter(|(_, method, has_body)| {
+                *has_body && ["GET", "HEAD"].contains(&method.to_uppercase().as_str())
+            })
+            .map(|(name, method, _)| format!("'{}' sends a body with {}", name, method))
+            .collect();
+
+        if self.strict_bodies && !problems.is_empty() {
+            anyhow::bail!("{}", problems.join("; "));
+        }
+        for problem in problems {
+            warn!("{}", problem);
+        }
+
+        Ok(())
+    }
+
+    /// Warn about extracted variables that are never used and used variables
+    /// that are never extracted; with `strict_variable_usage` these are errors
+    fn check_variables(&self) -> anyhow::Result<()> {
+        let extracted: BTreeSet<&str> = self
+            .steps()
+            .flat_map(|step| {
+                step.extract
+                    .keys()
+                    .chain(&step.capture_body_as)
+                    .chain(&step.capture_response_hash_as)
+                    .map(String::as_str)
+            })
+            .collect();
+        let mut used = BTreeSet::new();
+        let mut required = BTreeSet::new();
+
+        // Global headers are merged into every request, scenario steps
+        // included, and simple-mode requests are substituted like steps
+        let mut templates: Vec<&str> = Vec::new();
+        for (name, value) in &self.headers {
+            templates.extend([name.as_str(), value.as_str()]);
+        }
+        templates.extend(self.host.as_deref());
+        templates.extend(self.body.as_deref());
+        for request in &self.requests {
+            templates.extend(request.url.as_deref());
+            for (name, value) in &request.headers {
+                templates.extend([name.as_str(), value.as_str()]);
+            }
+            templates.extend(request.body.as_deref());
+        }
+
+        for step in self.steps() {
+            templates.push(step.url.as_str());
+            for (name, value) in &step.headers {
+                templates.extend([name.as_str(), value.as_str()]);
+            }
+            templates.extend(step.host.as_deref());
+            templates.extend(step.body.as_deref());
+            templates.extend(step.assert_response_hash.as_deref());
+            for (name, value) in step.form.iter().flatten() {
+                templates.extend([name.as_str(), value.as_str()]);
+            }
+            templates.extend(
+                step.multipart
+                    .iter()
+                    .flatten()
+                    .flat_map(|p| p.value.as_deref()),
+            );
+            if let Some(variables) = step.graphql.as_ref().and_then(|g| g.variables.as_ref()) {
+                json_strings(variables, &mut templates);
+            }
+
+            // Tera decides what a body template needs at render time, so
+            // any name it mentions counts as used but none as required
+            if let Some(ref path) = step.body_template_file {
+                let template = std::fs::read_to_string(path)
+                    .map_err(|e| anyhow::anyhow!("Failed to read body template {}: {}", path, e))?;
+                used.extend(
+                    template
+                        .split(|c: char| !c.is_alphanumeric() && c != '_')
+                        .filter(|word| !word.is_empty())
+                        .map(String::from),
+                );
+            }
+
+            if let Some(condition) = step.when.as_ref().and_then(|w| w.parse::<Condition>().ok()) {
+                used.insert(condition.variable().to_string());
+            }
+            if let Some(ref skip_if) = step.skip_if {
+                used.insert(skip_if.variable.clone());
+            }
+        }
+        for (name, default) in templates.into_iter().flat_map(placeholders) {
+            used.insert(name.to_string());
+            if default.is_none() {
+                required.insert(name.to_string());
+            }
+        }
+        // Propagated into every request's `traceparent`
+        if self.tracing_enabled {
+            used.extend([TRACE_ID_VARIABLE, SPAN_ID_VARIABLE].map(String::from));
+        }
+
+        let mut problems: Vec<String> = extracted
+            .iter()
+            .filter(|name| !used.contains(**name))
+            .map(|name| format!("Variable '{}' is extracted but never used", name))
+            .collect();
+        problems.extend(
+            required
+                .iter()
+                .filter(|name| {
+                    !extracted.contains(name.as_str())
+                        && !self.variables.contains_key(name.as_str())
+                        && ![REPEAT_INDEX_VARIABLE, REQUEST_ID_VARIABLE].contains(&name.as_str())
+                })
+                .map(|name| format!("Variable '{}' is used but never extracted", name)),
+        );
+
+        if self.strict_variable_usage && !problems.is_empty() {
+            anyhow::bail!("{}", problems.join("; "));
+        }
+        for problem in problems {
+            warn!("{}", problem);
+        }
+
         Ok(())
     }
 
+    /// The global `variables` with environment variables and functions
+    /// evaluated
+    pub fn resolved_variables(&self) -> anyhow::Result<HashMap<String, String>> {
+        let now = chrono::Utc::now();
+        let functions = HashMap::from([
+            ("$timestamp".to_string(), now.timestamp().to_string()),
+            (
+                "$timestamp_ms".to_string(),
+                now.timestamp_millis().to_string(),
+            ),
+            ("$datetime".to_string(), now.to_rfc3339()),
+            ("$uuid".to_string(), uuid::Uuid::new_v4().to_string()),
+        ]);
+
+        self.variables
+            .iter()
+            .map(|(name, value)| {
+                let value = expand_env(value)
+                    .map_err(|e| anyhow::anyhow!("Invalid variable '{}': {}", name, e))?;
+                Ok((name.clone(), substitute_functions(&value, &functions)))
+            })
+            .collect()
+    }
+
     /// Parse duration string to seconds
     pub fn parse_duration(&self) -> anyhow::Result<u64> {
-        let duration_str = self.duration.trim();
-
-        if let Some(stripped) = duration_str.strip_suffix('s') {
-            Ok(stripped.parse()?)
-        } else if let Some(stripped) = duration_str.strip_suffix('m') {
-            Ok(stripped.parse::<u64>()? * 60)
-        } else if let Some(stripped) = duration_str.strip_suffix('h') {
-            Ok(stripped.parse::<u64>()? * 3600)
-        } else {
-            // Default to seconds if no suffix
-            Ok(duration_str.parse()?)
+        Ok(parse_duration(&self.duration)?.as_secs())
+    }
+
+    /// Check that `reloaded` only changes settings a running test can adopt:
+    /// the target, scenarios, mode and phases are fixed for the run
+    pub fn check_reload(&self, reloaded: &Config) -> anyhow::Result<()> {
+        let scenarios = |config: &Config| serde_json::to_value(&config.scenarios).ok();
+        let phases = |config: &Config| serde_json::to_value(&config.phases).ok();
+        let fixed = [
+            ("target", self.target != reloaded.target),
+            ("scenarios", scenarios(self) != scenarios(reloaded)),
+            ("mode", self.mode != reloaded.mode),
+            ("phases", phases(self) != phases(reloaded)),
+        ];
+        if let Some((name, _)) = fixed.iter().find(|(_, changed)| *changed) {
+            anyhow::bail!("'{}' cannot change while the test is running", name);
+        }
+        Ok(())
+    }
+
+    /// Range and precision of the latency histogram, defaults filled in
+    pub fn histogram_bounds(&self) -> HistogramBounds {
+        let defaults = HistogramBounds::default();
+        HistogramBounds {
+            min_ms: self.histogram_min_ms.unwrap_or(defaults.min_ms),
+            max_ms: self.histogram_max_ms.unwrap_or(defaults.max_ms),
+            significant_figures: self
+                .histogram_significant_figures
+                .unwrap_or(defaults.significant_figures),
         }
     }
 
+    /// Length of the whole test in seconds: the sum of the phases if any,
+    /// otherwise `duration`
+    pub fn total_duration_secs(&self) -> anyhow::Result<u64> {
+        match self.phases {
+            Some(ref phases) => phases
+                .iter()
+                .map(|phase| Ok(parse_duration(&phase.duration)?.as_secs()))
+                .sum(),
+            None => self.parse_duration(),
+        }
+    }
+
+    /// Most workers running at once: the largest phase concurrency if
+    /// there are phases, otherwise `concurrency`
+    pub fn max_concurrency(&self) -> usize {
+        self.phases
+            .iter()
+            .flatten()
+            .map(|phase| phase.concurrency)
+            .max()
+            .unwrap_or(self.concurrency)
+    }
+
+    /// In-flight iteration cap for the open workload model
+    pub fn resolved_max_outstanding(&self) -> usize {
+        self.max_outstanding.unwrap_or(DEFAULT_MAX_OUTSTANDING)
+    }
+
+    /// Check if iterations are launched at a fixed arrival rate
+    pub fn is_open_model(&self) -> bool {
+        self.workload_model.as_deref() == Some("open")
+    }
+
     /// Check if running in simple mode (single request type)
     pub fn is_simple_mode(&self) -> bool {
         self.scenarios.is_empty()
@@ -206,21 +2001,103 @@ impl Config {
 mod tests {
     use super::*;
 
+    /// Parse a simple-mode config with `extra` settings
+    fn parse_with(extra: &str) -> anyhow::Result<Config> {
+        Config::from_str(&format!(
+            "target: http://example.com\n{}\noutput:\n  json: a\n  html: b\n",
+            extra
+        ))
+    }
+
+    /// Parse a scenario config of `steps` with the boolean `flag` set to
+    /// `strict`
+    fn parse_scenarios(flag: &str, strict: bool, steps: &str) -> anyhow::Result<Config> {
+        Config::from_str(&format!(
+            "{}: {}\nscenarios:\n{}\noutput:\n  json: a\n  html: b\n",
+            flag, strict, steps
+        ))
+    }
+
     #[test]
     fn test_parse_duration() {
         let config = Config {
             target: Some("http://example.com".to_string()),
             method: Some("GET".to_string()),
+            allow_custom_method: false,
+            custom_methods: Vec::new(),
             headers: HashMap::new(),
+            default_headers: DefaultHeaders::default(),
+            host: None,
             body: None,
             multipart: None,
+            requests: Vec::new(),
             scenarios: vec![],
+            scenario_order: None,
+            on_scenario_failure: None,
+            scenario_chain_timeout_secs: None,
+            grpc: None,
+            setup: vec![],
+            teardown: vec![],
+            variables: HashMap::new(),
             concurrency: 10,
             duration: "30s".to_string(),
+            phases: None,
             mode: "async".to_string(),
+            ramp_up: None,
+            ramp_up_jitter_pct: None,
+            worker_stagger_ms: 10,
+            think_time_ms: 10,
+            workload_model: None,
+            arrival_rate: None,
+            max_outstanding: None,
+            auto_content_type: true,
+            omit_empty_headers: true,
+            decompress: true,
+            accept_encoding: None,
+            respect_rate_limit: false,
+            resolve: HashMap::new(),
+            dns_cache_ttl_secs: None,
+            local_address: None,
+            pool_max_idle_per_host: 100,
+            pool_idle_timeout: None,
+            connection_ttl: None,
+            tcp_keepalive: None,
+            tcp_nodelay: true,
+            follow_redirects: true,
+            detailed_timing: false,
+            compress_body: false,
+            minify_json_body: true,
+            pretty_print_json_body: false,
+            strict_variables: false,
+            strict_variable_usage: false,
+            strict_bodies: false,
+            tracing_enabled: false,
+            trace_header: None,
+            seed: None,
+            ui_refresh_ms: 1000,
+            alert_p99_ms: None,
+            latency_precision: 0,
+            apdex_threshold_ms: None,
+            sla_latency_ms: None,
+            histogram_min_ms: None,
+            histogram_max_ms: None,
+            histogram_significant_figures: None,
+            sample_rate: None,
+            capture_sample_rate: None,
+            dashboard_port: None,
+            notify: None,
             output: OutputConfig {
                 json: "/app/results/output.json".to_string(),
                 html: "/app/results/output.html".to_string(),
+                junit: None,
+                request_log: None,
+                json_stream: None,
+                har: None,
+                grafana_dashboard: None,
+                timestamp_outputs: false,
+                pretty_json: false,
+                prometheus_push_gateway: None,
+                prometheus_job: None,
             },
         };
 
@@ -237,5 +2114,441 @@ mod tests {
             ..config
         };
         assert_eq!(config_hour.parse_duration().unwrap(), 7200);
+
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_from_str() {
+        let config = Config::from_str(
+            "target: http://example.com\nconcurrency: 5\noutput:\n  json: out.json\n  html: out.html\n",
+        )
+        .unwrap();
+        assert_eq!(config.target.as_deref(), Some("http://example.com"));
+        assert_eq!(config.concurrency, 5);
+        assert_eq!(config.mode, "async");
+        assert_eq!(config.worker_stagger_ms, 10);
+        assert_eq!(config.think_time_ms, 10);
+        assert_eq!(config.ui_refresh_ms, 1000);
+
+        let err = Config::from_str("concurrency: 5\noutput:\n  json: a\n  html: b\n").unwrap_err();
+        assert!(err.to_string().contains("'target' or 'scenarios'"));
+    }
+
+    #[test]
+    fn test_before_all_and_after_all_aliases() {
+        let config = Config::from_str(
+            r#"
+target: http://a
+before_all:
+  - { name: create, method: POST, url: /data }
+after_all:
+  - { name: delete, method: DELETE, url: /data }
+output: { json: a, html: b }
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.setup[0].name, "create");
+        assert_eq!(config.teardown[0].name, "delete");
+
+        let err = Config::from_str(
+            "target: http://a\nsetup: []\nbefore_all: []\noutput: { json: a, html: b }\n",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("duplicate field"));
+    }
+
+    #[test]
+    fn test_method_validation() {
+        assert_eq!(
+            parse_with("method: PATCH").unwrap().resolved_method(),
+            "PATCH"
+        );
+        assert_eq!(parse_with("").unwrap().resolved_method(), "GET");
+
+        let err = parse_with("method: GETT").unwrap_err();
+        assert!(err.to_string().contains("Unsupported HTTP method 'GETT'"));
+        assert!(parse_with("method: PURGE\nallow_custom_method: true").is_ok());
+        assert!(parse_with("method: PROPFIND\ncustom_methods: [PROPFIND]").is_ok());
+
+        let err =
+            parse_with("requests:\n  - { name: list, method: GETT }\ncustom_methods: [PROPFIND]")
+                .unwrap_err();
+        assert!(err.to_string().contains("'GETT'"));
+        assert!(err.to_string().contains("in request 'list'"));
+        assert!(parse_with("method: \"GE T\"\nallow_custom_method: true").is_err());
+    }
+
+    #[test]
+    fn test_connection_options() {
+        assert!(parse_with("local_address: 10.0.0.5").is_ok());
+        assert!(parse_with("local_address: \"::1\"").is_ok());
+
+        let err = parse_with("local_address: eth0").unwrap_err();
+        assert!(err.to_string().contains("Invalid 'local_address' 'eth0'"));
+
+        let config = parse_with("pool_idle_timeout: 5s\ntcp_keepalive: 30s").unwrap();
+        assert_eq!(config.pool_max_idle_per_host, 100);
+        assert!(config.tcp_nodelay);
+        assert!(!parse_with("tcp_nodelay: false").unwrap().tcp_nodelay);
+        assert_eq!(config.tcp_keepalive.as_deref(), Some("30s"));
+
+        let err = parse_with("connection_ttl: soon").unwrap_err();
+        assert!(err.to_string().contains("Invalid 'connection_ttl'"));
+
+        let err = parse_with("tcp_keepalive: often").unwrap_err();
+        assert!(err.to_string().contains("Invalid 'tcp_keepalive'"));
+    }
+
+    #[test]
+    fn test_from_env_vars() {
+        let vars = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect::<Vec<_>>()
+        };
+
+        let config = Config::from_vars(vars(&[
+            ("FLUX_TARGET", "http://localhost:8080/api"),
+            ("FLUX_CONCURRENCY", "5"),
+            ("FLUX_MODE", "sync"),
+            ("FLUX_WORKER_STAGGER_MS", "0"),
+            ("FLUX_METHOD", "POST"),
+            ("FLUX_BODY", r#"{"name": "a"}"#),
+            ("FLUX_HEADERS", r#"{"Content-Type": "application/json"}"#),
+            ("FLUX_UNKNOWN", "ignored"),
+            ("HOME", "/root"),
+        ]))
+        .unwrap();
+        assert_eq!(config.target.as_deref(), Some("http://localhost:8080/api"));
+        assert_eq!(config.concurrency, 5);
+        assert_eq!(config.mode, "sync");
+        assert_eq!(config.worker_stagger_ms, 0);
+        assert_eq!(config.body.as_deref(), Some(r#"{"name": "a"}"#));
+        assert_eq!(config.headers["Content-Type"], "application/json");
+        assert_eq!(config.output.json, ENV_DEFAULT_OUTPUT.0);
+
+        let err = Config::from_vars(vars(&[("FLUX_CONCURRENCY", "5")])).unwrap_err();
+        assert!(err.to_string().contains("FLUX_TARGET is not set"));
+
+        let err = Config::from_vars(vars(&[
+            ("FLUX_TARGET", "http://a"),
+            ("FLUX_CONCURRENCY", "many"),
+        ]))
+        .unwrap_err();
+        assert!(err.to_string().starts_with("Invalid FLUX_CONCURRENCY"));
+    }
+
+    #[test]
+    fn test_resolved_variables() {
+        std::env::set_var("FLUX_TEST_TENANT", "acme");
+        let config = Config::from_str(
+            r#"
+target: http://a
+variables:
+  tenant: "${FLUX_TEST_TENANT}"
+  base_path: "/api/${FLUX_TEST_TENANT}/v1"
+  started: "{{ $timestamp }}"
+  token: "{{ token }}"
+output: { json: a, html: b }
+"#,
+        )
+        .unwrap();
+
+        let variables = config.resolved_variables().unwrap();
+        assert_eq!(variables["tenant"], "acme");
+        assert_eq!(variables["base_path"], "/api/acme/v1");
+        assert!(variables["started"].parse::<i64>().is_ok());
+        assert_eq!(variables["token"], "{{ token }}");
+
+        let err = Config::from_str(
+            "target: http://a\nvariables: { key: \"${FLUX_TEST_UNSET}\" }\noutput: { json: a, html: b }\n",
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("environment variable 'FLUX_TEST_UNSET' is not set"));
+    }
+
+    #[test]
+    fn test_body_on_get_check() {
+        let parse = |strict: bool, steps: &str| parse_scenarios("strict_bodies", strict, steps);
+        let get = "  - { name: search, method: GET, url: http://a/s, body: '{}' }";
+        let post = "  - { name: create, method: POST, url: http://a/c, body: '{}' }";
+
+        assert!(parse(false, get).is_ok());
+        let err = parse(true, get).unwrap_err();
+        assert!(err.to_string().contains("'search' sends a body with GET"));
+        assert!(parse(true, post).is_ok());
+
+        let config = "target: http://a\nmethod: HEAD\nbody: x\nstrict_bodies: true\noutput: { json: a, html: b }\n";
+        assert!(Config::from_str(config).is_err());
+    }
+
+    #[test]
+    fn test_capture_body_encoding_validation() {
+        let parse = |step: &str| {
+            Config::from_str(&format!(
+                "scenarios:\n  - {{ name: fetch, method: GET, url: http://a/f, {} }}\noutput: {{ json: a, html: b }}\n",
+                step
+            ))
+        };
+
+        assert!(parse("capture_body_as: doc, capture_body_encoding: base64").is_ok());
+        assert!(parse("capture_body_as: doc, capture_body_encoding: text").is_ok());
+        let err = parse("capture_body_as: doc, capture_body_encoding: hex").unwrap_err();
+        assert!(err.to_string().contains("must be one of text, base64"));
+        let err = parse("capture_body_encoding: base64").unwrap_err();
+        assert!(err.to_string().contains("requires 'capture_body_as'"));
+    }
+
+    #[test]
+    fn test_variable_usage_check() {
+        let parse =
+            |strict: bool, steps: &str| parse_scenarios("strict_variable_usage", strict, steps);
+        let login =
+            r#"  - { name: login, method: POST, url: /login, extract: { token: "$.token" } }"#;
+
+        let err = parse(true, login).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Variable 'token' is extracted but never used"
+        );
+        assert!(parse(false, login).is_ok());
+        // Runtime strictness alone leaves these as warnings
+        assert!(parse_scenarios("strict_variables", true, login).is_ok());
+
+        let used = r#"
+  - { name: me, method: GET, url: /me, headers: { Authorization: "Bearer {{ token }}" } }
+  - { name: logout, method: POST, url: /logout, when: "token exists" }"#;
+        assert!(parse(true, &format!("{}{}", login, used)).is_ok());
+        let global_header = r#"headers: { Authorization: "Bearer {{ token }}" }"#;
+        assert!(parse(true, &format!("{}\n{}", login, global_header)).is_ok());
+        let err = parse(
+            true,
+            "  - { name: a, method: GET, url: /a }\nheaders: { X-Id: \"{{ typo }}\" }",
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Variable 'typo' is used but never extracted"
+        );
+        let header_name = r#"
+  - { name: me, method: GET, url: /me, headers: { "X-Token-{{ token }}": "1" } }"#;
+        assert!(parse(true, &format!("{}{}", login, header_name)).is_ok());
+
+        let err = parse(
+            true,
+            "  - { name: a, method: GET, url: \"/users/{{ id }}\" }",
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "Variable 'id' is used but never extracted");
+        assert!(parse(
+            true,
+            r#"  - { name: a, method: GET, url: "/users/{{ id | default('1') }}" }"#
+        )
+        .is_ok());
+
+        let trace = r#"  - { name: start, method: GET, url: /start, extract: { trace_id: "$.trace", span_id: "$.span" } }"#;
+        let err = parse(true, trace).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("'span_id' is extracted but never used"));
+        assert!(parse(true, &format!("{}\ntracing_enabled: true", trace)).is_ok());
+
+        let path = std::env::temp_dir().join(format!("flux-vars-{}.j2", std::process::id()));
+        std::fs::write(&path, "{\"auth\": \"{{ token }}\"}").unwrap();
+        let template = format!(
+            "\n  - {{ name: me, method: POST, url: /me, body_template_file: {} }}",
+            path.display()
+        );
+        assert!(parse(true, &format!("{}{}", login, template)).is_ok());
+        std::fs::remove_file(&path).unwrap();
+        let err = parse(true, &format!("{}{}", login, template)).unwrap_err();
+        assert!(err.to_string().contains("Body template file"));
+    }
+
+    #[test]
+    fn test_workload_model_validation() {
+        let config = parse_with("workload_model: open\narrival_rate: 20").unwrap();
+        assert!(config.is_open_model());
+        assert_eq!(config.resolved_max_outstanding(), 1000);
+
+        let err = parse_with("workload_model: open").unwrap_err();
+        assert!(err.to_string().contains("requires an 'arrival_rate'"));
+        assert!(parse_with("workload_model: open\narrival_rate: 5\nmax_outstanding: 0").is_err());
+        assert!(parse_with("workload_model: open\narrival_rate: 0").is_err());
+        assert!(parse_with("workload_model: open\narrival_rate: 1e-30").is_err());
+        assert!(parse_with("workload_model: open\narrival_rate: 1e12").is_err());
+        let err =
+            parse_with("phases: [{ name: a, concurrency: 1, duration: 1s, rate_limit: 1e-30 }]")
+                .unwrap_err();
+        assert!(err.to_string().contains("Rate limit must be between"));
+        assert!(parse_with("workload_model: poisson").is_err());
+    }
+
+    #[test]
+    fn test_check_reload() {
+        let parse = |extra: &str| parse_with(extra).unwrap();
+        let current = parse("concurrency: 2");
+
+        assert!(current
+            .check_reload(&parse("concurrency: 8\nheaders: { X-Run: b }"))
+            .is_ok());
+        let err = current.check_reload(&parse("mode: sync")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "'mode' cannot change while the test is running"
+        );
+        let mut moved = parse("");
+        moved.target = Some("http://other.example.com".to_string());
+        assert!(current.check_reload(&moved).is_err());
+    }
+
+    #[test]
+    fn test_histogram_bounds_validation() {
+        let bounds = parse_with("histogram_max_ms: 600000")
+            .unwrap()
+            .histogram_bounds();
+        assert_eq!((bounds.min_ms, bounds.max_ms), (1, 600_000));
+        assert_eq!(bounds.significant_figures, 3);
+
+        assert!(parse_with("histogram_min_ms: 0").is_err());
+        assert!(parse_with("histogram_min_ms: 100\nhistogram_max_ms: 150").is_err());
+        let err = parse_with("histogram_significant_figures: 6").unwrap_err();
+        assert!(err.to_string().contains("between 1 and 5"));
+    }
+
+    #[test]
+    fn test_request_mix_validation() {
+        let parse =
+            |yaml: &str| Config::from_str(&format!("{}\noutput: {{ json: a, html: b }}\n", yaml));
+
+        let config = parse(
+            "target: http://example.com/\nrequests:\n  - { name: read, url: /items, weight: 7 }\n  - { name: home }",
+        )
+        .unwrap();
+        assert_eq!(config.requests[1].weight, 1);
+        assert_eq!(
+            config.requests[0].resolved_url(config.target.as_deref()),
+            "http://example.com/items"
+        );
+        assert_eq!(
+            config.requests[1].resolved_url(config.target.as_deref()),
+            "http://example.com/"
+        );
+
+        assert!(parse("requests: [{ name: a, url: \"http://a.test/x\" }]").is_ok());
+        let err = parse("requests: [{ name: a, url: /x }]").unwrap_err();
+        assert!(err.to_string().contains("needs a full 'url'"));
+        let err = parse("target: http://a\nrequests: [{ name: a }, { name: a }]").unwrap_err();
+        assert!(err.to_string().contains("Duplicate request name 'a'"));
+        assert!(parse("target: http://a\nrequests: [{ name: a, weight: 0 }]").is_err());
+    }
+
+    #[test]
+    fn test_expand_placeholders() {
+        let mut output = OutputConfig {
+            json: "/app/results/output.json".to_string(),
+            html: "/app/results/{run_id}/report.html".to_string(),
+            junit: Some("junit".to_string()),
+            request_log: None,
+            json_stream: None,
+            har: None,
+            grafana_dashboard: None,
+            timestamp_outputs: false,
+            pretty_json: false,
+            prometheus_push_gateway: None,
+            prometheus_job: None,
+        };
+
+        output.expand_placeholders("20240101-120000", "ab12cd34");
+        assert_eq!(output.json, "/app/results/output.json");
+        assert_eq!(output.html, "/app/results/ab12cd34/report.html");
+
+        output.timestamp_outputs = true;
+        output.expand_placeholders("20240101-120000", "ab12cd34");
+        assert_eq!(output.json, "/app/results/output-20240101-120000.json");
+        assert_eq!(output.junit.as_deref(), Some("junit-20240101-120000"));
+    }
+
+    #[test]
+    fn test_use_output_dir() {
+        let dir = std::env::temp_dir().join("flux_test_output_dir/nested");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let mut output = OutputConfig {
+            json: "/app/results/output.json".to_string(),
+            html: "/app/results/output.html".to_string(),
+            junit: Some("/app/results/junit.xml".to_string()),
+            request_log: None,
+            json_stream: None,
+            har: None,
+            grafana_dashboard: None,
+            timestamp_outputs: false,
+            pretty_json: false,
+            prometheus_push_gateway: None,
+            prometheus_job: None,
+        };
+        output.use_output_dir(&dir).unwrap();
+
+        assert!(dir.is_dir());
+        assert_eq!(output.json, dir.join("flux_report.json").to_string_lossy());
+        assert_eq!(output.html, dir.join("flux_report.html").to_string_lossy());
+        assert_eq!(
+            output.junit,
+            Some(dir.join("flux_report.xml").to_string_lossy().into_owned())
+        );
+        assert!(output.request_log.is_none());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_condition() {
+        let variables = HashMap::from([("role".to_string(), "admin".to_string())]);
+
+        assert!(r#"role == "admin""#.parse::<Condition>().unwrap().evaluate(&variables));
+        assert!(!"role != admin"
+            .parse::<Condition>()
+            .unwrap()
+            .evaluate(&variables));
+        assert!("role exists"
+            .parse::<Condition>()
+            .unwrap()
+            .evaluate(&variables));
+        assert!(!"token exists"
+            .parse::<Condition>()
+            .unwrap()
+            .evaluate(&variables));
+        assert!("token != admin"
+            .parse::<Condition>()
+            .unwrap()
+            .evaluate(&variables));
+        assert!("role > admin".parse::<Condition>().is_err());
+    }
+
+    #[test]
+    fn test_skip_condition() {
+        let variables = HashMap::from([
+            ("stock".to_string(), "12".to_string()),
+            ("status".to_string(), "out of stock".to_string()),
+        ]);
+        let skip = |yaml: &str| {
+            let condition: SkipCondition = serde_yaml::from_str(yaml).unwrap();
+            condition.validate().map(|_| condition.evaluate(&variables))
+        };
+
+        assert!(skip("{ variable: stock, operator: gt, value: '9' }").unwrap());
+        assert!(!skip("{ variable: stock, operator: lt, value: '9' }").unwrap());
+        assert!(skip("{ variable: status, operator: contains, value: out }").unwrap());
+        assert!(skip("{ variable: cart, operator: empty }").unwrap());
+        assert!(!skip("{ variable: cart, operator: eq, value: '' }").unwrap());
+        assert!(skip("{ variable: cart, operator: ne, value: x }").unwrap());
+
+        assert!(skip("{ variable: stock, operator: gt, value: many }").is_err());
+        assert!(skip("{ variable: stock, operator: eq }").is_err());
+        assert!(skip("{ variable: stock, operator: like, value: x }").is_err());
     }
 }
@@ -0,0 +1,142 @@
+use crate::metrics::MetricsCollector;
+use anyhow::Result;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{Html, IntoResponse, Json};
+use axum::routing::get;
+use axum::Router;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tera::{Context, Tera};
+
+/// Serve the live dashboard on `port` until `shutdown` completes
+pub async fn serve<F>(port: u16, metrics: Arc<MetricsCollector>, shutdown: F) -> Result<()>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let listener = tokio::net::TcpListener::bind(SocketAddr::from(([0, 0, 0, 0], port))).await?;
+
+    axum::serve(listener, router(metrics))
+        .with_graceful_shutdown(shutdown)
+        .await?;
+
+    Ok(())
+}
+
+/// Dashboard routes
+fn router(metrics: Arc<MetricsCollector>) -> Router {
+    Router::new()
+        .route("/", get(index))
+        .route("/metrics", get(live_metrics))
+        .route("/summary", get(summary))
+        .with_state(metrics)
+}
+
+/// Dashboard page polling `/metrics` every second
+async fn index() -> impl IntoResponse {
+    match render_index() {
+        Ok(html) => Html(html).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+fn render_index() -> Result<String> {
+    let mut tera = Tera::default();
+    tera.add_raw_template("dashboard.html", include_str!("templates/dashboard.html"))?;
+
+    let mut context = Context::new();
+    context.insert("charts_js", include_str!("templates/charts.js"));
+
+    Ok(tera.render("dashboard.html", &context)?)
+}
+
+/// Current live metrics
+async fn live_metrics(State(metrics): State<Arc<MetricsCollector>>) -> impl IntoResponse {
+    Json(metrics.get_live_metrics())
+}
+
+/// Summary of everything recorded so far
+async fn summary(State(metrics): State<Arc<MetricsCollector>>) -> impl IntoResponse {
+    // Generating the summary drains the channel and locks storage
+    match tokio::task::spawn_blocking(move || metrics.generate_summary()).await {
+        Ok(summary) => Json(summary).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::RequestResult;
+    use chrono::Utc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Issue a plain HTTP/1.0 GET and return the response body
+    async fn get_body(port: u16, path: &str) -> String {
+        let mut stream = tokio::net::TcpStream::connect(("127.0.0.1", port))
+            .await
+            .unwrap();
+        stream
+            .write_all(format!("GET {} HTTP/1.0\r\n\r\n", path).as_bytes())
+            .await
+            .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+            .split("\r\n\r\n")
+            .nth(1)
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_dashboard_endpoints() {
+        let metrics = Arc::new(MetricsCollector::new());
+        metrics.record(RequestResult {
+            scenario_name: None,
+            latency_ms: 25,
+            status_code: 200,
+            error: None,
+            request_start_timestamp: Utc::now(),
+            request_end_timestamp: Utc::now(),
+            sse_total_ms: None,
+            wall_latency_ms: None,
+            timing: None,
+            request_id: None,
+            content_encoding: None,
+            is_hook: false,
+        });
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let server = tokio::spawn(serve(port, Arc::clone(&metrics), async {
+            let _ = stopped.await;
+        }));
+
+        // Wait for the server to start listening
+        while tokio::net::TcpStream::connect(("127.0.0.1", port))
+            .await
+            .is_err()
+        {
+            tokio::task::yield_now().await;
+        }
+
+        let live: serde_json::Value =
+            serde_json::from_str(&get_body(port, "/metrics").await).unwrap();
+        assert_eq!(live["total_requests"], 1);
+
+        let summary: serde_json::Value =
+            serde_json::from_str(&get_body(port, "/summary").await).unwrap();
+        assert_eq!(summary["p50_latency_ms"], 25);
+
+        assert!(get_body(port, "/").await.contains("FluxCharts"));
+
+        stop.send(()).unwrap();
+        server.await.unwrap().unwrap();
+    }
+}
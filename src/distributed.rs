@@ -0,0 +1,269 @@
+use crate::config::Config;
+use crate::executor::Executor;
+use crate::metrics::MetricsCollector;
+use crate::reporter::Report;
+use anyhow::Result;
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Json, Response};
+use axum::routing::post;
+use axum::Router;
+use futures::future::try_join_all;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+/// Port agents listen on unless told otherwise
+pub const DEFAULT_AGENT_PORT: u16 = 7070;
+
+/// Run the test described by `config` on this machine and summarize it.
+/// Raw results are dropped; the summary's histogram is what gets merged.
+pub async fn run_local(config: Config) -> Result<Report> {
+    let duration_secs = config.total_duration_secs()?;
+    let metrics = Arc::new(
+        MetricsCollector::with_histogram_bounds(config.histogram_bounds())
+            .with_apdex_threshold(config.apdex_threshold_ms)
+            .with_sla_latency(config.sla_latency_ms),
+    );
+
+    let executor = Executor::new(config, Arc::clone(&metrics))?;
+    executor.run(duration_secs).await?;
+
+    let mut summary = metrics.generate_summary();
+    summary.peak_concurrency = Some(executor.peak_concurrency());
+    summary.dns_cache_hits = executor.dns_cache_hits();
+    if let Some(stats) = executor.open_model_stats() {
+        summary.max_outstanding = Some(stats.max_outstanding);
+        summary.dropped_iterations = Some(stats.dropped_iterations);
+    }
+
+    Ok(Report {
+        summary,
+        results: Vec::new(),
+        phase_summaries: executor.phase_summaries(),
+        request_summaries: executor.request_summaries(),
+    })
+}
+
+/// Serve agent requests on `addr` until the process exits, accepting only
+/// those that present `token`
+pub async fn serve_agent(addr: SocketAddr, token: String) -> Result<()> {
+    if token.is_empty() {
+        anyhow::bail!("The agent token must not be empty");
+    }
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    info!("Agent listening on {}", listener.local_addr()?);
+    axum::serve(listener, agent_router(token)).await?;
+    Ok(())
+}
+
+/// Agent routes: `POST /run` takes a YAML config, runs it and replies with
+/// the JSON report. One test runs at a time. Every request must carry the
+/// shared token as a bearer token.
+fn agent_router(token: String) -> Router {
+    Router::new()
+        .route("/run", post(run))
+        .with_state(Arc::new(Mutex::new(())))
+        .layer(middleware::from_fn_with_state(
+            Arc::<str>::from(token),
+            authorize,
+        ))
+}
+
+/// Reject requests without the shared token
+async fn authorize(State(token): State<Arc<str>>, request: Request, next: Next) -> Response {
+    if !has_token(request.headers(), &token) {
+        warn!("Rejected an agent request without a valid token");
+        return (StatusCode::UNAUTHORIZED, "Missing or invalid agent token").into_response();
+    }
+    next.run(request).await
+}
+
+/// Whether `headers` carry `token` as a bearer token, compared in constant
+/// time so it can't be guessed byte by byte
+fn has_token(headers: &HeaderMap, token: &str) -> bool {
+    let Some(presented) = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+    presented.len() == token.len()
+        && presented
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+async fn run(State(busy): State<Arc<Mutex<()>>>, yaml: String) -> impl IntoResponse {
+    let Ok(_running) = busy.try_lock() else {
+        return (
+            StatusCode::CONFLICT,
+            "A test is already running".to_string(),
+        )
+            .into_response();
+    };
+
+    let config: Config = match yaml.parse() {
+        Ok(config) => config,
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+    if let Err(e) = check_no_files(&config) {
+        return (StatusCode::BAD_REQUEST, e.to_string()).into_response();
+    }
+
+    info!("Running test for coordinator");
+    match run_local(config).await {
+        Ok(report) => Json(report).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Agents don't read local files named by a config sent over the network
+fn check_no_files(config: &Config) -> Result<()> {
+    if let Some((field, path)) = config.file_references().first() {
+        anyhow::bail!(
+            "'{}' reads the local file '{}', which distributed runs don't support",
+            field,
+            path
+        );
+    }
+    Ok(())
+}
+
+/// Run `config` on every agent at once and merge their reports. Each agent
+/// generates the full configured load, with seeds offset so no two workers
+/// across agents share a random stream.
+pub async fn coordinate(config: &Config, agents: &[String], token: &str) -> Result<Report> {
+    check_no_files(config)?;
+    let seed = config.seed.unwrap_or_else(rand::random);
+    let client = reqwest::Client::new();
+
+    let runs = agents.iter().enumerate().map(|(index, agent)| {
+        let mut config = config.clone();
+        config.seed = Some(agent_seed(&config, seed, index));
+        let client = client.clone();
+
+        async move {
+            let yaml = serde_yaml::to_string(&config)?;
+            let response = client
+                .post(agent_url(agent))
+                .bearer_auth(token)
+                .body(yaml)
+                .send()
+                .await
+                .map_err(|e| anyhow::anyhow!("Agent {} is unreachable: {}", agent, e))?;
+
+            let status = response.status();
+            if !status.is_success() {
+                let message = response.text().await.unwrap_or_default();
+                anyhow::bail!("Agent {} failed with HTTP {}: {}", agent, status, message);
+            }
+            info!("Agent {} finished", agent);
+            Ok::<Report, anyhow::Error>(response.json().await?)
+        }
+    });
+
+    Report::merge(try_join_all(runs).await?)
+}
+
+/// Seed of the agent at `index`, past every worker id of the agents before
+/// it; phases may run more workers than the top-level `concurrency`
+fn agent_seed(config: &Config, seed: u64, index: usize) -> u64 {
+    seed.wrapping_add(index as u64 * config.max_concurrency() as u64)
+}
+
+/// `POST /run` URL of an agent given as `host[:port]` or a full URL
+fn agent_url(agent: &str) -> String {
+    let base = if agent.contains("://") {
+        agent.trim_end_matches('/').to_string()
+    } else if agent.contains(':') {
+        format!("http://{}", agent)
+    } else {
+        format!("http://{}:{}", agent, DEFAULT_AGENT_PORT)
+    };
+    format!("{}/run", base)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_agent_url() {
+        assert_eq!(agent_url("10.0.0.5"), "http://10.0.0.5:7070/run");
+        assert_eq!(agent_url("load-1:9000"), "http://load-1:9000/run");
+        assert_eq!(agent_url("https://agent/"), "https://agent/run");
+    }
+
+    #[test]
+    fn test_agent_seed() {
+        let config: Config = "target: http://a\nconcurrency: 2\nphases:\n  - { name: warm, concurrency: 2, duration: 1s }\n  - { name: peak, concurrency: 8, duration: 1s }\noutput: { json: a, html: b }\n"
+            .parse()
+            .unwrap();
+        assert_eq!(agent_seed(&config, 100, 0), 100);
+        assert_eq!(agent_seed(&config, 100, 1), 108);
+        assert_eq!(agent_seed(&config, 100, 2), 116);
+    }
+
+    #[tokio::test]
+    async fn test_coordinate_merges_agent_reports() {
+        let serve = |router: Router| async move {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+            addr.to_string()
+        };
+        let target = serve(Router::new().route("/", axum::routing::get(|| async { "ok" }))).await;
+        let agents = vec![
+            serve(agent_router("secret".to_string())).await,
+            serve(agent_router("secret".to_string())).await,
+        ];
+
+        let config: Config = format!(
+            "target: http://{}/\nconcurrency: 2\nduration: 1s\noutput: {{ json: a, html: b }}\n",
+            target
+        )
+        .parse()
+        .unwrap();
+
+        let report = coordinate(&config, &agents, "secret").await.unwrap();
+        assert!(report.summary.total_requests > 0);
+        assert_eq!(report.summary.failed_requests, 0);
+        assert!(report.summary.latency_histogram.is_some());
+
+        let err = coordinate(&config, &agents, "guess").await.unwrap_err();
+        assert!(err.to_string().contains("HTTP 401"));
+
+        let err = coordinate(&config, &["127.0.0.1:1".to_string()], "secret")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Agent 127.0.0.1:1 is unreachable"));
+    }
+
+    #[tokio::test]
+    async fn test_agent_rejects_configs_reading_files() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let agent = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, agent_router("secret".to_string()))
+                .await
+                .unwrap()
+        });
+
+        let yaml = "target: http://a/\nmultipart:\n  - { type: file, name: f, path: /etc/passwd }\noutput: { json: a, html: b }\n";
+        let response = reqwest::Client::new()
+            .post(agent_url(&agent.to_string()))
+            .bearer_auth("secret")
+            .body(yaml)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 400);
+        assert!(response.text().await.unwrap().contains("/etc/passwd"));
+    }
+}
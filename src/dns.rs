@@ -0,0 +1,123 @@
+use crate::config::Config;
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long a lookup is reused when `dns_cache_ttl_secs` is unset
+pub const DEFAULT_DNS_CACHE_TTL_SECS: u64 = 300;
+
+/// Addresses of each host with when they were looked up
+type Entries = HashMap<String, (Vec<IpAddr>, Instant)>;
+
+/// Resolver reusing system lookups per host for a TTL, so new connections
+/// under high concurrency don't each repeat the lookup. Clones share the
+/// cache.
+#[derive(Clone)]
+pub struct DnsCache {
+    ttl: Duration,
+    entries: Arc<Mutex<Entries>>,
+    hits: Arc<AtomicUsize>,
+}
+
+impl DnsCache {
+    /// Cache with the configured TTL, or none when `dns_cache_ttl_secs` is 0
+    pub fn from_config(config: &Config) -> Option<Self> {
+        match config
+            .dns_cache_ttl_secs
+            .unwrap_or(DEFAULT_DNS_CACHE_TTL_SECS)
+        {
+            0 => None,
+            secs => Some(Self::new(Duration::from_secs(secs))),
+        }
+    }
+
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Arc::new(Mutex::new(HashMap::new())),
+            hits: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Lookups answered from the cache so far
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Unexpired addresses of `host`, counting the hit
+    fn cached(&self, host: &str) -> Option<Vec<IpAddr>> {
+        let entries = self.entries.lock().unwrap();
+        let (ips, resolved) = entries.get(host)?;
+        if resolved.elapsed() >= self.ttl {
+            return None;
+        }
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        Some(ips.clone())
+    }
+
+    /// Addresses of `host`, from the cache or the system resolver
+    async fn lookup(&self, host: &str) -> std::io::Result<Vec<IpAddr>> {
+        if let Some(ips) = self.cached(host) {
+            return Ok(ips);
+        }
+        let ips: Vec<IpAddr> = tokio::net::lookup_host((host, 0))
+            .await?
+            .map(|addr| addr.ip())
+            .collect();
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(host.to_string(), (ips.clone(), Instant::now()));
+        Ok(ips)
+    }
+}
+
+impl Resolve for DnsCache {
+    fn resolve(&self, name: Name) -> Resolving {
+        let cache = self.clone();
+        Box::pin(async move {
+            let ips = cache.lookup(name.as_str()).await?;
+            let addrs: Addrs = Box::new(ips.into_iter().map(|ip| SocketAddr::new(ip, 0)));
+            Ok(addrs)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_lookups_are_cached_until_ttl() {
+        let cache = DnsCache::new(Duration::from_millis(200));
+        let first = cache.lookup("localhost").await.unwrap();
+        assert!(!first.is_empty());
+        assert_eq!(cache.hits(), 0);
+
+        let clone = cache.clone();
+        assert_eq!(clone.lookup("localhost").await.unwrap(), first);
+        assert_eq!(cache.hits(), 1);
+
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        cache.lookup("localhost").await.unwrap();
+        assert_eq!(cache.hits(), 1);
+
+        let config = |ttl: &str| -> Config {
+            format!(
+                "target: http://localhost\n{}output: {{ json: a, html: b }}\n",
+                ttl
+            )
+            .parse()
+            .unwrap()
+        };
+        assert_eq!(
+            DnsCache::from_config(&config("")).unwrap().ttl.as_secs(),
+            300
+        );
+        assert!(DnsCache::from_config(&config("dns_cache_ttl_secs: 0\n")).is_none());
+    }
+}
@@ -1,77 +1,691 @@
-use crate::client::HttpClient;
-use crate::config::{Config, Scenario};
-use crate::metrics::{MetricsCollector, RequestResult};
-use anyhow::Result;
-use chrono::Utc;
+use crate::client::{classify_error, graphql_error, HttpClient};
+use crate::config::{
+    parse_duration, Condition, Config, ExtractRule, Phase, Scenario, WeightedRequest,
+    REPEAT_INDEX_VARIABLE,
+};
+use crate::dns::DnsCache;
+use crate::grpc::GrpcClient;
+use crate::har::{HarEntry, HarRecorder, HarRequest, HarResponse};
+use crate::metrics::{
+    MetricsCollector, MetricsSummary, RequestError, RequestResult, TimingBreakdown,
+};
+use crate::reporter::{RequestLog, StreamingJsonWriter};
+use crate::{sse, websocket};
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use futures::stream::{FuturesUnordered, StreamExt};
 use jsonpath_rust::JsonPathFinder;
-use std::collections::HashMap;
-use std::sync::Arc;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use reqwest::header::{HeaderMap, CONTENT_ENCODING, RETRY_AFTER};
+use reqwest::{Request, Response};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::Instant;
-use tokio::time::{sleep, Duration};
-use tracing::{debug, error, warn};
+use tokio::sync::{watch, Semaphore};
+use tokio::task::{JoinHandle, JoinSet};
+use tokio::time::{sleep, Duration, Interval, MissedTickBehavior};
+use tracing::{debug, error, info, warn};
+
+/// WebSocket and SSE timeout when a step sets no `timeout_secs`
+const DEFAULT_STREAM_TIMEOUT_SECS: u64 = 30;
+
+/// Worker pause after a 429 without a usable `Retry-After`
+const DEFAULT_RATE_LIMIT_PAUSE: Duration = Duration::from_secs(1);
+
+/// Longest pause honoured from a `Retry-After` header
+const MAX_RATE_LIMIT_PAUSE: Duration = Duration::from_secs(60);
+
+/// Times a step is resent after a 429 with `Retry-After` before the last
+/// 429 is recorded
+const MAX_RETRY_AFTER_RETRIES: u32 = 3;
+
+/// What the open workload model achieved
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct OpenModelStats {
+    /// Peak number of iterations in flight at once
+    pub max_outstanding: usize,
+    /// Launches skipped because the cap was reached
+    pub dropped_iterations: usize,
+}
+
+/// One request sent by a dry run
+#[derive(Debug, Clone)]
+pub struct DryRunStep {
+    /// Step or request name
+    pub name: String,
+    pub method: String,
+    /// URL with variables substituted
+    pub url: String,
+    pub result: RequestResult,
+    /// Variables the step set or changed, sorted by name
+    pub extracted: Vec<(String, String)>,
+}
+
+/// Name and time window of a completed phase
+#[derive(Debug, Clone)]
+struct PhaseWindow {
+    name: String,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+}
+
+/// Deadline of one pass through the scenarios with
+/// `scenario_chain_timeout_secs`, and whether it cut a request short
+struct ChainDeadline {
+    at: tokio::time::Instant,
+    expired: AtomicBool,
+}
+
+impl ChainDeadline {
+    fn after(timeout: Duration) -> Self {
+        Self {
+            at: tokio::time::Instant::now() + timeout,
+            expired: AtomicBool::new(false),
+        }
+    }
+
+    fn expired(&self) -> bool {
+        self.expired.load(Ordering::Relaxed)
+    }
+}
+
+/// Optional pacing interval shared by the workers of a phase
+type Pacer = Arc<tokio::sync::Mutex<Option<Interval>>>;
+
+/// Runtime instruction for a running test
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ControlMessage {
+    /// Grow or shrink the async worker pool to this many workers
+    SetConcurrency(usize),
+}
+
+/// Resizable pool of workers, each stopped by its own flag. Surplus workers
+/// finish their current iteration before exiting.
+#[derive(Default)]
+struct WorkerPool {
+    workers: Vec<(Arc<AtomicBool>, JoinHandle<()>)>,
+    retired: Vec<JoinHandle<()>>,
+    pacer: Pacer,
+    /// Ramp-up delay before each worker's first iteration, by worker id
+    start_delays: Vec<Duration>,
+}
+
+impl WorkerPool {
+    fn len(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Grow or shrink the pool to `size` workers
+    fn resize(&mut self, executor: &Executor, size: usize, variables: &HashMap<String, String>) {
+        executor.metrics.reserve_workers(size);
+        while self.workers.len() < size {
+            let worker_id = self.workers.len();
+            let mut worker = executor.clone_for_worker(worker_id, variables);
+            let stop = Arc::new(AtomicBool::new(false));
+            let start_delay = self.start_delays.get(worker_id).copied();
+            let handle = tokio::spawn({
+                let stop = Arc::clone(&stop);
+                let pacer = Arc::clone(&self.pacer);
+                async move {
+                    if let Some(start_delay) = start_delay {
+                        sleep(start_delay).await;
+                    }
+                    worker.pool_worker_loop(worker_id, stop, pacer).await
+                }
+            });
+            self.workers.push((stop, handle));
+        }
+
+        if self.workers.len() > size {
+            for (stop, handle) in self.workers.drain(size..) {
+                stop.store(true, Ordering::Relaxed);
+                self.retired.push(handle);
+            }
+        }
+    }
+
+    /// Stop every worker and wait for them to finish
+    async fn shutdown(self) {
+        for (stop, _) in &self.workers {
+            stop.store(true, Ordering::Relaxed);
+        }
+        for handle in self.workers.into_iter().map(|(_, h)| h).chain(self.retired) {
+            let _ = handle.await;
+        }
+    }
+}
+
+/// Requests currently in flight across all workers, and the most at once
+#[derive(Debug, Default)]
+struct InFlight {
+    current: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+impl InFlight {
+    /// Count a request as in flight until the guard is dropped
+    fn start(&self) -> InFlightGuard<'_> {
+        let now = self.current.fetch_add(1, Ordering::Relaxed) + 1;
+        self.peak.fetch_max(now, Ordering::Relaxed);
+        InFlightGuard(self)
+    }
+}
+
+struct InFlightGuard<'a>(&'a InFlight);
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.0.current.fetch_sub(1, Ordering::Relaxed);
+    }
+}
 
 /// Executor for running load tests
 pub struct Executor {
     config: Config,
     client: HttpClient,
+    grpc: Option<GrpcClient>,
     metrics: Arc<MetricsCollector>,
+    request_log: Option<Arc<RequestLog>>,
+    json_streams: Vec<Arc<StreamingJsonWriter>>,
+    har: Option<Arc<HarRecorder>>,
+    /// Global `variables` of the config plus those extracted by the setup
+    /// scenarios
+    global_variables: HashMap<String, String>,
+    /// Run seed; worker N draws from a stream seeded with `seed + N`
+    seed: u64,
+    rng: Mutex<StdRng>,
+    /// Run-wide cap of `concurrency` on copies of concurrent steps
+    step_permits: Arc<Semaphore>,
+    in_flight: Arc<InFlight>,
+    /// Steps already warned about a missing `latency_from_header`, shared
+    /// by all workers so each is warned about once per run
+    missing_latency_headers: Arc<RwLock<HashSet<String>>>,
+    open_model_stats: Mutex<Option<OpenModelStats>>,
+    phase_windows: Mutex<Vec<PhaseWindow>>,
+    control: Option<watch::Receiver<ControlMessage>>,
+    /// Config reloaded on SIGHUP, adopted by workers between iterations
+    reload: Option<watch::Receiver<Config>>,
+    /// Worker whose per-worker stats this executor's results count towards
+    worker_id: usize,
+    /// Steps sent so far when running a dry run
+    dry_run_steps: Option<Mutex<Vec<DryRunStep>>>,
 }
 
 impl Executor {
     /// Create a new executor
     pub fn new(config: Config, metrics: Arc<MetricsCollector>) -> Result<Self> {
-        let client = HttpClient::new()?;
+        let client = HttpClient::from_config(&config)?;
+        let grpc = config
+            .grpc
+            .as_ref()
+            .map(GrpcClient::from_config)
+            .transpose()?;
+        let seed = config.seed.unwrap_or_else(rand::random);
+        let global_variables = config.resolved_variables()?;
+        let concurrency = config.max_concurrency();
+        metrics.reserve_workers(concurrency);
         Ok(Self {
             config,
             client,
+            grpc,
             metrics,
+            request_log: None,
+            json_streams: Vec::new(),
+            har: None,
+            global_variables,
+            seed,
+            rng: Mutex::new(worker_rng(seed, 0)),
+            step_permits: Arc::new(Semaphore::new(concurrency)),
+            in_flight: Arc::default(),
+            missing_latency_headers: Arc::default(),
+            open_model_stats: Mutex::new(None),
+            phase_windows: Mutex::new(Vec::new()),
+            control: None,
+            reload: None,
+            worker_id: 0,
+            dry_run_steps: None,
         })
     }
 
-    /// Run the load test
+    /// Write (a sample of) completed requests to an NDJSON log
+    pub fn with_request_log(mut self, request_log: Arc<RequestLog>) -> Self {
+        self.request_log = Some(request_log);
+        self
+    }
+
+    /// Stream every result as NDJSON as it completes; may be called once
+    /// per output
+    pub fn with_json_stream(mut self, json_stream: Arc<StreamingJsonWriter>) -> Self {
+        self.json_streams.push(json_stream);
+        self
+    }
+
+    /// Resize the async worker pool on `SetConcurrency` messages
+    pub fn with_control(mut self, control: watch::Receiver<ControlMessage>) -> Self {
+        self.control = Some(control);
+        self
+    }
+
+    /// Have workers adopt each config sent on `reload` at the start of
+    /// their next iteration
+    pub fn with_reload(mut self, reload: watch::Receiver<Config>) -> Self {
+        self.reload = Some(reload);
+        self
+    }
+
+    /// Capture a sample of request/response pairs for a HAR file
+    pub fn with_har(mut self, har: Arc<HarRecorder>) -> Self {
+        self.har = Some(har);
+        self
+    }
+
+    /// Send every request once to check a config before running it: the
+    /// setup steps, one pass of the scenario chain and the teardown steps,
+    /// sharing their variables, or each simple request. The chain applies
+    /// its conditions, hooks, repeats and failure policy as a real run does.
+    pub async fn dry_run(mut self) -> Vec<DryRunStep> {
+        self.dry_run_steps = Some(Mutex::default());
+
+        if let Some(ref grpc) = self.grpc {
+            let result = self.call_grpc(grpc).await;
+            let config = self.config.grpc.as_ref().unwrap();
+            self.note_dry_run_step(DryRunStep {
+                name: config.method.clone(),
+                method: "gRPC".to_string(),
+                url: format!("{}/{}/{}", config.endpoint, config.service, config.method),
+                result: result.clone(),
+                extracted: Vec::new(),
+            });
+            self.record(result);
+        } else if self.config.is_simple_mode() {
+            let target = self.config.target.as_deref();
+            let picks: Vec<Option<&WeightedRequest>> = if self.config.requests.is_empty() {
+                vec![None]
+            } else {
+                self.config.requests.iter().map(Some).collect()
+            };
+            for picked in picks {
+                let (result, _) = self.send_simple_request(picked).await;
+                self.note_dry_run_step(DryRunStep {
+                    name: picked.map_or_else(|| "request".to_string(), |p| p.name.clone()),
+                    method: picked
+                        .and_then(|p| p.method.as_deref())
+                        .unwrap_or(self.config.resolved_method())
+                        .to_string(),
+                    url: picked.map_or_else(
+                        || target.unwrap_or_default().to_string(),
+                        |p| p.resolved_url(target),
+                    ),
+                    result: result.clone(),
+                    extracted: Vec::new(),
+                });
+                self.record(result);
+            }
+        } else {
+            // As in a real run, a failed setup step stops the run and the
+            // chain starts from the variables the setup steps extracted
+            let mut variables = self.global_variables.clone();
+            let mut setup_failed = false;
+            for step in &self.config.setup {
+                if !self
+                    .execute_scenario_step(step, &mut variables, false)
+                    .await
+                {
+                    setup_failed = true;
+                    break;
+                }
+            }
+
+            if !setup_failed {
+                self.global_variables = variables.clone();
+                self.execute_scenarios().await;
+                for step in &self.config.teardown {
+                    self.execute_scenario_step(step, &mut variables, false)
+                        .await;
+                }
+            }
+        }
+
+        self.dry_run_steps
+            .take()
+            .map(|steps| steps.into_inner().unwrap())
+            .unwrap_or_default()
+    }
+
+    /// Keep a step sent by a dry run
+    fn note_dry_run_step(&self, step: DryRunStep) {
+        if let Some(ref steps) = self.dry_run_steps {
+            steps.lock().unwrap().push(step);
+        }
+    }
+
+    /// Run the load test, wrapped in the setup and teardown scenarios
     pub async fn run(&self, duration_secs: u64) -> Result<()> {
+        let mut variables = self.global_variables.clone();
+        for scenario in &self.config.setup {
+            self.execute_once(scenario, &mut variables)
+                .await
+                .with_context(|| format!("Setup scenario '{}' failed", scenario.name))?;
+        }
+
         let start = Instant::now();
         let duration = Duration::from_secs(duration_secs);
 
-        if self.config.mode == "async" {
-            self.run_async(start, duration).await?;
+        if let Some(ref phases) = self.config.phases {
+            self.run_phases(phases, &variables).await?;
+        } else if self.config.is_open_model() {
+            self.run_open(start, duration, &variables).await;
+        } else if self.config.mode == "async" {
+            self.run_async(start, duration, &variables).await?;
         } else {
-            self.run_sync(start, duration).await?;
+            self.run_sync(start, duration, &variables).await?;
+        }
+
+        // Keep tearing down after a failed step so cleanup is best effort
+        for scenario in &self.config.teardown {
+            if let Err(e) = self.execute_once(scenario, &mut variables).await {
+                warn!("Teardown scenario '{}' failed: {:#}", scenario.name, e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Execute a setup or teardown scenario outside of the load test metrics
+    async fn execute_once(
+        &self,
+        scenario: &Scenario,
+        variables: &mut HashMap<String, String>,
+    ) -> Result<()> {
+        let response = self
+            .client
+            .execute_scenario(self.config.target.as_deref(), scenario, variables)
+            .await?;
+
+        let status = response.status().as_u16();
+        if status_error(status).is_some() {
+            anyhow::bail!("HTTP {}", status);
+        }
+
+        if scenario.extracts_from_body()
+            || scenario.capture_body_as.is_some()
+            || scenario.hashes_response()
+        {
+            let body = response.bytes().await?;
+            if let Some(e) = self
+                .capture_body(&body, scenario, variables)
+                .or_else(|| self.check_response_hash(&body, scenario, variables))
+            {
+                anyhow::bail!("{}", e);
+            }
+            self.extract_variables(&String::from_utf8_lossy(&body), scenario, variables);
         }
 
+        info!(
+            "Scenario '{}' completed with HTTP {}",
+            scenario.name, status
+        );
         Ok(())
     }
 
     /// Run in async mode
-    async fn run_async(&self, start: Instant, duration: Duration) -> Result<()> {
-        let mut handles = vec![];
+    async fn run_async(
+        &self,
+        start: Instant,
+        duration: Duration,
+        variables: &HashMap<String, String>,
+    ) -> Result<()> {
+        let mut pool = WorkerPool::default();
+        if let Some(ref ramp_up) = self.config.ramp_up {
+            pool.start_delays = ramp_up_delays(
+                self.config.concurrency,
+                parse_duration(ramp_up)?,
+                self.config.ramp_up_jitter_pct.unwrap_or(0.0),
+                &mut self.rng.lock().unwrap(),
+            );
+        }
+        pool.resize(self, self.config.concurrency, variables);
 
-        for worker_id in 0..self.config.concurrency {
-            let executor = self.clone_for_worker();
-            let start_clone = start;
-            let duration_clone = duration;
+        let deadline = tokio::time::Instant::from_std(start + duration);
+        let mut control = self.control.clone();
 
-            let handle = tokio::spawn(async move {
-                executor
-                    .worker_loop(worker_id, start_clone, duration_clone)
-                    .await;
-            });
+        loop {
+            let changed = async {
+                match control.as_mut() {
+                    Some(control) => control.changed().await.is_ok(),
+                    None => std::future::pending().await,
+                }
+            };
 
-            handles.push(handle);
+            tokio::select! {
+                _ = tokio::time::sleep_until(deadline) => break,
+                open = changed => {
+                    if !open {
+                        control = None;
+                        continue;
+                    }
+                    let message = *control.as_mut().unwrap().borrow_and_update();
+                    let ControlMessage::SetConcurrency(size) = message;
+                    info!("Resizing worker pool from {} to {} workers", pool.len(), size);
+                    pool.resize(self, size, variables);
+                }
+            }
         }
 
-        // Wait for all workers to complete
-        for handle in handles {
-            let _ = handle.await;
+        // Let in-flight iterations finish
+        pool.shutdown().await;
+
+        Ok(())
+    }
+
+    /// Launch iterations at `arrival_rate` without waiting for earlier ones
+    /// to complete, dropping launches while `max_outstanding` are in flight
+    async fn run_open(
+        &self,
+        start: Instant,
+        duration: Duration,
+        variables: &HashMap<String, String>,
+    ) {
+        let rate = self.config.arrival_rate.unwrap_or(1.0);
+        let max_outstanding = self.config.resolved_max_outstanding();
+        let executor = Arc::new(self.clone_for_worker(0, variables));
+
+        // Catch up on late ticks so the average rate holds
+        let mut ticker = tokio::time::interval(Duration::from_secs_f64(1.0 / rate));
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Burst);
+
+        let mut in_flight = JoinSet::new();
+        let mut stats = OpenModelStats::default();
+
+        loop {
+            ticker.tick().await;
+            if start.elapsed() >= duration {
+                break;
+            }
+
+            while in_flight.try_join_next().is_some() {}
+            if in_flight.len() >= max_outstanding {
+                stats.dropped_iterations += 1;
+                continue;
+            }
+
+            let executor = Arc::clone(&executor);
+            in_flight.spawn(async move { executor.execute_iteration().await });
+            stats.max_outstanding = stats.max_outstanding.max(in_flight.len());
+        }
+
+        while in_flight.join_next().await.is_some() {}
+
+        if stats.dropped_iterations > 0 {
+            warn!(
+                "Dropped {} iterations with {} outstanding",
+                stats.dropped_iterations, max_outstanding
+            );
         }
+        *self.open_model_stats.lock().unwrap() = Some(stats);
+    }
+
+    /// Peak concurrency and dropped launches of an open-model run
+    pub fn open_model_stats(&self) -> Option<OpenModelStats> {
+        *self.open_model_stats.lock().unwrap()
+    }
+
+    /// Run each phase in turn, growing or shrinking the worker pool to the
+    /// phase's concurrency. Surplus workers finish their current iteration
+    /// and exit.
+    async fn run_phases(
+        &self,
+        phases: &[Phase],
+        variables: &HashMap<String, String>,
+    ) -> Result<()> {
+        let mut pool = WorkerPool::default();
+
+        for (i, phase) in phases.iter().enumerate() {
+            let duration = parse_duration(&phase.duration)?;
+            // Live metrics and the histogram cover one phase at a time
+            if i > 0 {
+                self.metrics.reset_histogram()?;
+            }
+
+            *pool.pacer.lock().await = phase.rate_limit.map(|rate| {
+                let mut interval = tokio::time::interval(Duration::from_secs_f64(1.0 / rate));
+                interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+                interval
+            });
+            pool.resize(self, phase.concurrency, variables);
+
+            info!(
+                "Phase '{}' started with {} workers for {}",
+                phase.name, phase.concurrency, phase.duration
+            );
+            let start = Utc::now();
+            sleep(duration).await;
+            self.phase_windows.lock().unwrap().push(PhaseWindow {
+                name: phase.name.clone(),
+                start,
+                end: Utc::now(),
+            });
+        }
+
+        pool.shutdown().await;
 
         Ok(())
     }
 
+    /// Worker loop for resizable pools, run until its stop flag is set
+    async fn pool_worker_loop(&mut self, worker_id: usize, stop: Arc<AtomicBool>, pacer: Pacer) {
+        debug!("Worker {} started", worker_id);
+
+        while !stop.load(Ordering::Relaxed) {
+            self.adopt_reloaded_config();
+
+            // Holding the lock while waiting paces the workers as a group
+            if let Some(interval) = pacer.lock().await.as_mut() {
+                interval.tick().await;
+            }
+            if stop.load(Ordering::Relaxed) {
+                break;
+            }
+
+            self.execute_iteration().await;
+
+            if self.config.mode == "sync" {
+                sleep(Duration::from_millis(self.config.think_time_ms)).await;
+            }
+        }
+
+        debug!("Worker {} finished", worker_id);
+    }
+
+    /// Most requests in flight at once so far
+    pub fn peak_concurrency(&self) -> usize {
+        self.in_flight.peak.load(Ordering::Relaxed)
+    }
+
+    /// Connection lookups answered from the shared DNS cache so far
+    pub fn dns_cache_hits(&self) -> usize {
+        self.client.dns_cache().map_or(0, DnsCache::hits)
+    }
+
+    /// Summary of each request in the mix over the whole run
+    pub fn request_summaries(&self) -> Vec<(String, MetricsSummary)> {
+        if self.config.requests.is_empty() {
+            return Vec::new();
+        }
+
+        let results = self.metrics.get_results();
+        let (Some(start), Some(end)) = (
+            results.iter().map(|r| r.request_start_timestamp).min(),
+            results.iter().map(|r| r.request_end_timestamp).max(),
+        ) else {
+            return Vec::new();
+        };
+        self.config
+            .requests
+            .iter()
+            .map(|request| {
+                let of_request: Vec<RequestResult> = results
+                    .iter()
+                    .filter(|r| r.scenario_name.as_deref() == Some(request.name.as_str()))
+                    .cloned()
+                    .collect();
+                let summary = MetricsSummary::from_results_with_bounds(
+                    &of_request,
+                    self.metrics.histogram_bounds(),
+                    start,
+                    end,
+                );
+                (request.name.clone(), summary)
+            })
+            .collect()
+    }
+
+    /// Summary of the requests started during each completed phase
+    pub fn phase_summaries(&self) -> Vec<(String, MetricsSummary)> {
+        let windows = self.phase_windows.lock().unwrap().clone();
+        if windows.is_empty() {
+            return Vec::new();
+        }
+
+        let results = self.metrics.get_results();
+        windows
+            .into_iter()
+            .map(|window| {
+                let in_phase: Vec<RequestResult> = results
+                    .iter()
+                    .filter(|r| {
+                        r.request_start_timestamp >= window.start
+                            && r.request_start_timestamp < window.end
+                    })
+                    .cloned()
+                    .collect();
+                let summary = MetricsSummary::from_results_with_bounds(
+                    &in_phase,
+                    self.metrics.histogram_bounds(),
+                    window.start,
+                    window.end,
+                );
+                (window.name, summary)
+            })
+            .collect()
+    }
+
     /// Run in sync mode
-    async fn run_sync(&self, start: Instant, duration: Duration) -> Result<()> {
+    async fn run_sync(
+        &self,
+        start: Instant,
+        duration: Duration,
+        variables: &HashMap<String, String>,
+    ) -> Result<()> {
         for worker_id in 0..self.config.concurrency {
-            let executor = self.clone_for_worker();
+            let mut executor = self.clone_for_worker(worker_id, variables);
             let start_clone = start;
             let duration_clone = duration;
 
@@ -81,8 +695,8 @@ impl Executor {
                     .await;
             });
 
-            // Small delay between workers in sync mode
-            sleep(Duration::from_millis(10)).await;
+            // Stagger worker starts in sync mode
+            sleep(Duration::from_millis(self.config.worker_stagger_ms)).await;
         }
 
         // Wait for duration
@@ -92,74 +706,268 @@ impl Executor {
     }
 
     /// Worker loop that executes requests
-    async fn worker_loop(&self, worker_id: usize, start: Instant, duration: Duration) {
+    async fn worker_loop(&mut self, worker_id: usize, start: Instant, duration: Duration) {
         debug!("Worker {} started", worker_id);
 
         while start.elapsed() < duration {
-            if self.config.is_simple_mode() {
-                self.execute_simple_request().await;
-            } else {
-                self.execute_scenarios().await;
-            }
+            self.adopt_reloaded_config();
+            self.execute_iteration().await;
 
-            // Small delay in sync mode
+            // Think time in sync mode
             if self.config.mode == "sync" {
-                sleep(Duration::from_millis(10)).await;
+                sleep(Duration::from_millis(self.config.think_time_ms)).await;
             }
         }
 
         debug!("Worker {} finished", worker_id);
     }
 
-    /// Execute a simple request
+    /// Switch to a config reloaded since the last iteration, rebuilding the
+    /// HTTP client so new headers and client options apply
+    fn adopt_reloaded_config(&mut self) {
+        let Some(ref mut reload) = self.reload else {
+            return;
+        };
+        if !reload.has_changed().unwrap_or(false) {
+            return;
+        }
+        let config = reload.borrow_and_update().clone();
+
+        match HttpClient::with_dns_cache(&config, self.client.dns_cache().cloned()) {
+            Ok(client) => {
+                self.client = client;
+                self.config = config;
+            }
+            Err(e) => warn!("Keeping the previous config: {}", e),
+        }
+    }
+
+    /// Execute one iteration: a gRPC call, a simple request or every scenario
+    async fn execute_iteration(&self) {
+        if let Some(ref grpc) = self.grpc {
+            self.execute_grpc_request(grpc).await;
+        } else if self.config.is_simple_mode() {
+            self.execute_simple_request().await;
+        } else {
+            self.execute_scenarios().await;
+        }
+    }
+
+    /// Execute a simple request, or one picked by weight from the mix
     async fn execute_simple_request(&self) {
+        let picked = (!self.config.requests.is_empty())
+            .then(|| pick_request(&self.config.requests, &mut *self.rng.lock().unwrap()));
+        let (request_result, pause) = self.send_simple_request(picked).await;
+        self.record(request_result);
+        self.back_off(pause).await;
+    }
+
+    /// Send the simple request, or `picked` from the mix, returning its
+    /// result and any rate-limit pause to take before the next request
+    async fn send_simple_request(
+        &self,
+        picked: Option<&WeightedRequest>,
+    ) -> (RequestResult, Option<Duration>) {
         let start_time = Utc::now();
         let request_start = Instant::now();
 
-        let result = self
-            .client
-            .execute_simple(
-                self.config.target.as_ref().unwrap(),
-                self.config.method.as_ref().unwrap_or(&"GET".to_string()),
-                &self.config.headers,
-                self.config.body.as_deref(),
+        let scenario_name = picked.map(|request| request.name.clone());
+        let (url, method, headers, body, multipart) = match picked {
+            Some(picked) => {
+                let mut headers = self.config.headers.clone();
+                headers.extend(picked.headers.clone());
+                (
+                    Cow::Owned(picked.resolved_url(self.config.target.as_deref())),
+                    picked.method.as_deref().unwrap_or("GET"),
+                    Cow::Owned(headers),
+                    picked.body.as_deref().map(Cow::Borrowed),
+                    None,
+                )
+            }
+            None => (
+                Cow::Borrowed(self.config.target.as_deref().unwrap_or_default()),
+                self.config.resolved_method(),
+                Cow::Borrowed(&self.config.headers),
+                self.config.body.as_deref().map(Cow::Borrowed),
                 self.config.multipart.as_ref(),
-            )
-            .await;
+            ),
+        };
+        let request = match self.substitute_globals(url, headers, body) {
+            Ok((url, headers, body)) => {
+                self.client
+                    .build_simple(&url, method, &headers, body.as_deref(), multipart)
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        let request_id = request
+            .as_ref()
+            .ok()
+            .and_then(|r| self.client.request_id(r));
+        let result = self.send_captured(request).await;
 
         let latency = request_start.elapsed().as_millis() as u64;
         let end_time = Utc::now();
 
+        let mut pause = None;
         let request_result = match result {
-            Ok(response) => RequestResult {
-                scenario_name: None,
-                latency_ms: latency,
-                status_code: response.status().as_u16(),
-                error: None,
-                request_start_timestamp: start_time,
-                request_end_timestamp: end_time,
-            },
+            Ok((response, captured, timing)) => {
+                let status = response.status().as_u16();
+                let content_encoding = content_encoding(response.headers());
+                pause = self.rate_limit_pause(status, response.headers());
+
+                if let Some(request) = captured {
+                    let head = HarResponse::from_response(&response);
+                    let body = response.text().await.unwrap_or_default();
+                    self.capture(HarEntry::new(
+                        start_time,
+                        latency,
+                        request,
+                        head.with_body(&body),
+                    ));
+                }
+
+                RequestResult {
+                    scenario_name,
+                    latency_ms: latency,
+                    status_code: status,
+                    error: self.response_error(status),
+                    request_start_timestamp: start_time,
+                    request_end_timestamp: end_time,
+                    sse_total_ms: None,
+                    wall_latency_ms: None,
+                    timing,
+                    request_id,
+                    content_encoding,
+                    is_hook: false,
+                }
+            }
             Err(e) => {
                 error!("Request failed: {}", e);
                 RequestResult {
-                    scenario_name: None,
+                    scenario_name,
                     latency_ms: latency,
                     status_code: 0,
-                    error: Some(e.to_string()),
+                    error: Some(classify_error(&e)),
                     request_start_timestamp: start_time,
                     request_end_timestamp: end_time,
+                    sse_total_ms: None,
+                    wall_latency_ms: None,
+                    timing: None,
+                    request_id,
+                    content_encoding: None,
+                    is_hook: false,
                 }
             }
         };
 
-        self.metrics.record(request_result);
+        (request_result, pause)
+    }
+
+    /// Substitute the global `variables` into a simple request's URL,
+    /// headers and body; untouched when none are set
+    #[allow(clippy::type_complexity)]
+    fn substitute_globals<'a>(
+        &self,
+        url: Cow<'a, str>,
+        headers: Cow<'a, HashMap<String, String>>,
+        body: Option<Cow<'a, str>>,
+    ) -> Result<(
+        Cow<'a, str>,
+        Cow<'a, HashMap<String, String>>,
+        Option<Cow<'a, str>>,
+    )> {
+        if self.global_variables.is_empty() {
+            return Ok((url, headers, body));
+        }
+
+        let substitute = |template: &str| {
+            self.client
+                .substitute_variables(template, &self.global_variables)
+        };
+        let headers = headers
+            .iter()
+            .map(|(name, value)| Ok((name.clone(), substitute(value)?)))
+            .collect::<Result<_>>()?;
+        let body = match body {
+            Some(body) => Some(Cow::Owned(substitute(&body)?)),
+            None => None,
+        };
+        Ok((Cow::Owned(substitute(&url)?), Cow::Owned(headers), body))
+    }
+
+    /// Execute a unary gRPC call and record its result
+    async fn execute_grpc_request(&self, grpc: &GrpcClient) {
+        let request_result = self.call_grpc(grpc).await;
+        self.record(request_result);
+    }
+
+    /// Make a unary gRPC call; the status code is 0 for OK and 1 otherwise
+    async fn call_grpc(&self, grpc: &GrpcClient) -> RequestResult {
+        let start_time = Utc::now();
+        let request_start = Instant::now();
+
+        let result = {
+            let _in_flight = self.in_flight.start();
+            grpc.call().await
+        };
+
+        let latency = request_start.elapsed().as_millis() as u64;
+        let end_time = Utc::now();
+
+        let (status_code, error) = match result {
+            Ok(_) => (0, None),
+            Err(status) => {
+                error!("gRPC call failed: {}", status);
+                (
+                    1,
+                    Some(RequestError::Other(format!(
+                        "gRPC {:?}: {}",
+                        status.code(),
+                        status.message()
+                    ))),
+                )
+            }
+        };
+
+        RequestResult {
+            scenario_name: None,
+            latency_ms: latency,
+            status_code,
+            error,
+            request_start_timestamp: start_time,
+            request_end_timestamp: end_time,
+            sse_total_ms: None,
+            wall_latency_ms: None,
+            timing: None,
+            request_id: None,
+            content_encoding: None,
+            is_hook: false,
+        }
     }
 
-    /// Execute all scenarios in sequence
+    /// Execute all scenarios in sequence, within the chain timeout if set
     async fn execute_scenarios(&self) {
-        let mut variables: HashMap<String, String> = HashMap::new();
+        let scenarios = if self.config.scenario_order.as_deref() == Some("random") {
+            shuffle_scenarios(&self.config.scenarios, &mut *self.rng.lock().unwrap())
+        } else {
+            self.config.scenarios.iter().collect()
+        };
 
-        for scenario in &self.config.scenarios {
+        let deadline = self
+            .config
+            .scenario_chain_timeout_secs
+            .map(|secs| ChainDeadline::after(Duration::from_secs(secs)));
+        self.execute_chain(&scenarios, deadline.as_ref()).await;
+    }
+
+    /// Execute `scenarios` in order. Once the `deadline` cuts a request
+    /// short, that request and every step not yet started are recorded as
+    /// chain timeouts.
+    async fn execute_chain(&self, scenarios: &[&Scenario], deadline: Option<&ChainDeadline>) {
+        let mut variables = self.global_variables.clone();
+
+        for (i, scenario) in scenarios.iter().enumerate() {
             // Check dependencies
             if let Some(ref depends_on) = scenario.depends_on {
                 if !self.has_executed_scenario(depends_on, &variables) {
@@ -171,55 +979,719 @@ impl Executor {
                 }
             }
 
-            let start_time = Utc::now();
-            let request_start = Instant::now();
+            // Conditions are validated when the config is loaded
+            if let Some(ref when) = scenario.when {
+                let met = when
+                    .parse::<Condition>()
+                    .map(|condition| condition.evaluate(&variables))
+                    .unwrap_or(true);
 
-            let result = self
-                .client
-                .execute_scenario(self.config.target.as_deref(), scenario, &variables)
-                .await;
+                if !met {
+                    debug!(
+                        "Skipping scenario '{}' - condition '{}' is false",
+                        scenario.name, when
+                    );
+                    continue;
+                }
+            }
 
-            let latency = request_start.elapsed().as_millis() as u64;
-            let end_time = Utc::now();
+            if let Some(ref skip_if) = scenario.skip_if {
+                if skip_if.evaluate(&variables) {
+                    debug!(
+                        "Skipping scenario '{}' - skip_if on '{}' holds",
+                        scenario.name, skip_if.variable
+                    );
+                    self.record_unsent(scenario, RequestError::Skipped);
+                    continue;
+                }
+            }
 
-            match result {
-                Ok(response) => {
-                    let status = response.status().as_u16();
+            let policy = if scenario.critical {
+                "abort"
+            } else {
+                self.config
+                    .on_scenario_failure
+                    .as_deref()
+                    .unwrap_or("continue")
+            };
 
-                    // Extract variables if needed
-                    if !scenario.extract.is_empty() {
-                        if let Ok(body) = response.text().await {
-                            self.extract_variables(&body, scenario, &mut variables);
+            let failed = match scenario.repeat {
+                Some(repeat) if repeat > 1 => {
+                    let mut failed = false;
+                    for index in 0..repeat {
+                        variables.insert(REPEAT_INDEX_VARIABLE.to_string(), index.to_string());
+                        if !self
+                            .execute_with_hooks(scenario, &mut variables, deadline)
+                            .await
+                        {
+                            failed = true;
+                            if policy != "continue" || deadline.is_some_and(ChainDeadline::expired)
+                            {
+                                break;
+                            }
                         }
                     }
-
-                    let request_result = RequestResult {
-                        scenario_name: Some(scenario.name.clone()),
-                        latency_ms: latency,
-                        status_code: status,
-                        error: None,
-                        request_start_timestamp: start_time,
-                        request_end_timestamp: end_time,
-                    };
-
-                    self.metrics.record(request_result);
+                    variables.remove(REPEAT_INDEX_VARIABLE);
+                    failed
                 }
-                Err(e) => {
-                    error!("Scenario '{}' failed: {}", scenario.name, e);
-
-                    let request_result = RequestResult {
-                        scenario_name: Some(scenario.name.clone()),
-                        latency_ms: latency,
-                        status_code: 0,
-                        error: Some(e.to_string()),
-                        request_start_timestamp: start_time,
-                        request_end_timestamp: end_time,
-                    };
+                _ => {
+                    !self
+                        .execute_with_hooks(scenario, &mut variables, deadline)
+                        .await
+                }
+            };
 
-                    self.metrics.record(request_result);
+            if deadline.is_some_and(ChainDeadline::expired) {
+                debug!(
+                    "Scenario chain timed out after {}s - cancelling {} step(s)",
+                    self.config.scenario_chain_timeout_secs.unwrap_or_default(),
+                    scenarios.len() - i - 1
+                );
+                for remaining in &scenarios[i + 1..] {
+                    self.record_unsent(remaining, RequestError::ChainTimeout);
                 }
+                self.metrics.record_chain_timeout();
+                break;
             }
-        }
+
+            if failed && policy != "continue" {
+                debug!(
+                    "Scenario '{}' failed - stopping the chain ({})",
+                    scenario.name, policy
+                );
+                if policy == "skip-remaining" {
+                    for remaining in &scenarios[i + 1..] {
+                        self.record_unsent(remaining, RequestError::Skipped);
+                    }
+                }
+                self.metrics.record_aborted_chain();
+                break;
+            }
+        }
+    }
+
+    /// Record a step that was skipped or cancelled without a response
+    fn record_unsent(&self, scenario: &Scenario, error: RequestError) {
+        self.record(unsent_result(scenario, error));
+    }
+
+    /// Execute a step between its `before_each` and `after_each` hooks. A
+    /// failed `before_each` hook skips the step, which then counts as
+    /// failed; the `after_each` hooks run either way. The `deadline` covers
+    /// the `before_each` hooks and the step but not the `after_each` hooks,
+    /// so a timed-out step is still cleaned up after.
+    async fn execute_with_hooks(
+        &self,
+        scenario: &Scenario,
+        variables: &mut HashMap<String, String>,
+        deadline: Option<&ChainDeadline>,
+    ) -> bool {
+        if !scenario.has_hooks() {
+            return self
+                .execute_step_copies(scenario, variables, deadline)
+                .await;
+        }
+
+        let mut ready = true;
+        for hook in &scenario.before_each {
+            if !self
+                .execute_step_within(hook, variables, true, deadline)
+                .await
+            {
+                debug!(
+                    "Skipping scenario '{}' - before_each hook '{}' failed",
+                    scenario.name, hook.name
+                );
+                ready = false;
+                break;
+            }
+        }
+
+        let success = if ready {
+            self.execute_step_copies(scenario, variables, deadline)
+                .await
+        } else if deadline.is_some_and(ChainDeadline::expired) {
+            self.record_unsent(scenario, RequestError::ChainTimeout);
+            false
+        } else {
+            self.record_unsent(scenario, RequestError::Skipped);
+            false
+        };
+
+        for hook in &scenario.after_each {
+            self.execute_scenario_step(hook, variables, true).await;
+        }
+        success
+    }
+
+    /// Execute a step once, or as `concurrency` copies sharing the input
+    /// variables. Only the first copy to succeed has its extraction kept.
+    /// Returns whether any copy succeeded.
+    async fn execute_step_copies(
+        &self,
+        scenario: &Scenario,
+        variables: &mut HashMap<String, String>,
+        deadline: Option<&ChainDeadline>,
+    ) -> bool {
+        let copies = scenario.concurrency.unwrap_or(1);
+        if copies <= 1 {
+            return self
+                .execute_step_within(scenario, variables, false, deadline)
+                .await;
+        }
+
+        let mut pending: FuturesUnordered<_> = (0..copies)
+            .map(|_| {
+                let mut copy = variables.clone();
+                async move {
+                    let _permit = self.step_permits.acquire().await;
+                    let success = self
+                        .execute_step_within(scenario, &mut copy, false, deadline)
+                        .await;
+                    (success, copy)
+                }
+            })
+            .collect();
+
+        let mut extracted = false;
+        while let Some((success, copy)) = pending.next().await {
+            if extracted {
+                debug!(
+                    "Skipping extraction for a later copy of scenario '{}'",
+                    scenario.name
+                );
+            } else if success {
+                *variables = copy;
+                extracted = true;
+            }
+        }
+        extracted
+    }
+
+    /// Execute one request of a scenario and record its result, flagged as
+    /// a `hook`'s if it is one, returning whether it succeeded
+    async fn execute_scenario_step(
+        &self,
+        scenario: &Scenario,
+        variables: &mut HashMap<String, String>,
+        hook: bool,
+    ) -> bool {
+        self.execute_step_within(scenario, variables, hook, None)
+            .await
+    }
+
+    /// Execute one request of a scenario as `execute_scenario_step` does,
+    /// recording it as a chain timeout if the `deadline` passes first
+    async fn execute_step_within(
+        &self,
+        scenario: &Scenario,
+        variables: &mut HashMap<String, String>,
+        hook: bool,
+        deadline: Option<&ChainDeadline>,
+    ) -> bool {
+        let dry_run = self.dry_run_steps.as_ref().map(|_| {
+            let url = self
+                .client
+                .scenario_url(self.config.target.as_deref(), scenario, variables)
+                .unwrap_or_else(|_| scenario.url.clone());
+            (url, variables.clone())
+        });
+
+        let sent = self.send_step(scenario, variables, hook);
+        let (request_result, pause) = match deadline {
+            Some(deadline) => match tokio::time::timeout_at(deadline.at, sent).await {
+                Ok(sent) => sent,
+                Err(_) => {
+                    deadline.expired.store(true, Ordering::Relaxed);
+                    let result = RequestResult {
+                        is_hook: hook,
+                        ..unsent_result(scenario, RequestError::ChainTimeout)
+                    };
+                    (result, None)
+                }
+            },
+            None => sent.await,
+        };
+        let success = request_result.error.is_none();
+
+        if let Some((url, before)) = dry_run {
+            let mut extracted: Vec<(String, String)> = variables
+                .iter()
+                .filter(|(name, value)| before.get(*name) != Some(*value))
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect();
+            extracted.sort();
+            self.note_dry_run_step(DryRunStep {
+                name: scenario.name.clone(),
+                method: scenario.method.clone(),
+                url,
+                result: request_result.clone(),
+                extracted,
+            });
+        }
+
+        self.record(request_result);
+        self.back_off(pause).await;
+        success
+    }
+
+    /// Send one request of a scenario over its protocol, returning its
+    /// result and any rate-limit pause to take before the next request
+    async fn send_step(
+        &self,
+        scenario: &Scenario,
+        variables: &mut HashMap<String, String>,
+        hook: bool,
+    ) -> (RequestResult, Option<Duration>) {
+        if scenario.is_websocket() {
+            (
+                self.send_websocket_step(scenario, variables, hook).await,
+                None,
+            )
+        } else if let Some(count) = scenario.sse_event_count {
+            (
+                self.send_sse_step(scenario, count, variables, hook).await,
+                None,
+            )
+        } else {
+            self.send_scenario_step(scenario, variables, hook).await
+        }
+    }
+
+    /// Send one HTTP request of a scenario, returning its result and any
+    /// rate-limit pause to take before the next request
+    async fn send_scenario_step(
+        &self,
+        scenario: &Scenario,
+        variables: &mut HashMap<String, String>,
+        hook: bool,
+    ) -> (RequestResult, Option<Duration>) {
+        let mut retries = 0;
+        let (start_time, request_start, request_id, result) = loop {
+            let start_time = Utc::now();
+            let request_start = Instant::now();
+
+            let request = self
+                .client
+                .build_scenario(self.config.target.as_deref(), scenario, variables)
+                .await;
+            let request_id = request
+                .as_ref()
+                .ok()
+                .and_then(|r| self.client.request_id(r));
+            let result = self.send_captured(request).await;
+
+            // A 429 asking to retry later is not recorded; the step is
+            // sent again once the delay has passed
+            if let Ok((ref response, _, _)) = result {
+                if let Some(delay) = self.retry_delay(scenario, response, retries) {
+                    debug!(
+                        "Scenario '{}' throttled, retrying in {:?}",
+                        scenario.name, delay
+                    );
+                    if !hook {
+                        self.metrics.record_throttled();
+                    }
+                    retries += 1;
+                    sleep(delay).await;
+                    continue;
+                }
+            }
+            break (start_time, request_start, request_id, result);
+        };
+
+        let latency = request_start.elapsed().as_millis() as u64;
+        let end_time = Utc::now();
+
+        match result {
+            Ok((response, captured, timing)) => {
+                let status = response.status().as_u16();
+                let content_encoding = content_encoding(response.headers());
+                let pause = self.rate_limit_pause(status, response.headers());
+                let (latency_ms, wall_latency_ms) = match scenario.latency_from_header {
+                    Some(ref name) => {
+                        let reported = header_latency(response.headers(), name);
+                        if reported.is_none() {
+                            self.warn_missing_latency_header(scenario, name, latency);
+                        }
+                        (reported.unwrap_or(latency), Some(latency))
+                    }
+                    None => (latency, None),
+                };
+                let head = captured
+                    .as_ref()
+                    .map(|_| HarResponse::from_response(&response));
+                self.extract_header_variables(response.headers(), scenario, variables);
+
+                // GraphQL reports failures in the body, usually with a 200
+                let body = if scenario.extracts_from_body()
+                    || scenario.capture_body_as.is_some()
+                    || scenario.hashes_response()
+                    || scenario.graphql.is_some()
+                    || captured.is_some()
+                {
+                    response.bytes().await.ok()
+                } else {
+                    None
+                };
+                let mut body_error = None;
+                if let Some(ref body) = body {
+                    body_error = self
+                        .capture_body(body, scenario, variables)
+                        .or_else(|| self.check_response_hash(body, scenario, variables));
+                }
+                let body = body.map(|b| String::from_utf8_lossy(&b).into_owned());
+
+                if let (Some(request), Some(head)) = (captured, head) {
+                    let body = body.as_deref().unwrap_or_default();
+                    self.capture(HarEntry::new(
+                        start_time,
+                        latency,
+                        request,
+                        head.with_body(body),
+                    ));
+                }
+
+                // Extract variables if needed
+                if let Some(ref body) = body {
+                    if scenario.extracts_from_body() {
+                        self.extract_variables(body, scenario, variables);
+                    }
+                }
+
+                // Accepted statuses in `success_if` replace the 4xx/5xx rule
+                let status_error = match scenario.success_if {
+                    Some(ref criteria) if !criteria.status.is_empty() => None,
+                    _ => self.response_error(status),
+                };
+                let error = status_error
+                    .or_else(|| match (&scenario.graphql, &body) {
+                        (Some(_), Some(body)) => graphql_error(body),
+                        _ => None,
+                    })
+                    .or_else(|| {
+                        let criteria = scenario.success_if.as_ref()?;
+                        criteria
+                            .check(status, latency_ms)
+                            .map(RequestError::AssertionFailed)
+                    })
+                    .or(body_error);
+
+                let request_result = RequestResult {
+                    scenario_name: Some(scenario.name.clone()),
+                    latency_ms,
+                    status_code: status,
+                    error,
+                    request_start_timestamp: start_time,
+                    request_end_timestamp: end_time,
+                    sse_total_ms: None,
+                    wall_latency_ms,
+                    timing,
+                    request_id,
+                    content_encoding,
+                    is_hook: hook,
+                };
+                (request_result, pause)
+            }
+            Err(e) => {
+                error!("Scenario '{}' failed: {}", scenario.name, e);
+
+                let request_result = RequestResult {
+                    scenario_name: Some(scenario.name.clone()),
+                    latency_ms: latency,
+                    status_code: 0,
+                    error: Some(classify_error(&e)),
+                    request_start_timestamp: start_time,
+                    request_end_timestamp: end_time,
+                    sse_total_ms: None,
+                    wall_latency_ms: None,
+                    timing: None,
+                    request_id,
+                    content_encoding: None,
+                    is_hook: hook,
+                };
+                (request_result, None)
+            }
+        }
+    }
+
+    /// Read `count` Server-Sent Events. Latency is the time to the first
+    /// event; `sse_total_ms` the time to the last.
+    async fn send_sse_step(
+        &self,
+        scenario: &Scenario,
+        count: usize,
+        variables: &mut HashMap<String, String>,
+        hook: bool,
+    ) -> RequestResult {
+        let timeout = scenario.timeout_secs.unwrap_or(DEFAULT_STREAM_TIMEOUT_SECS);
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(timeout);
+
+        let start_time = Utc::now();
+        let request_start = Instant::now();
+
+        let _in_flight = self.in_flight.start();
+        let result = self
+            .client
+            .execute_scenario(self.config.target.as_deref(), scenario, variables)
+            .await;
+
+        let (status_code, outcome) = match result {
+            Ok(response) => {
+                let status = response.status().as_u16();
+                match status_error(status) {
+                    Some(e) => (status, Err(e)),
+                    None => (status, sse::read_events(response, count, deadline).await),
+                }
+            }
+            Err(e) => (0, Err(classify_error(&e))),
+        };
+
+        match outcome {
+            Ok(events) => {
+                if !scenario.extract.is_empty() {
+                    self.extract_variables(&events.data.join("\n"), scenario, variables);
+                }
+
+                RequestResult {
+                    scenario_name: Some(scenario.name.clone()),
+                    latency_ms: (events.first_event - request_start).as_millis() as u64,
+                    status_code,
+                    error: None,
+                    request_start_timestamp: start_time,
+                    request_end_timestamp: Utc::now(),
+                    sse_total_ms: Some((events.last_event - request_start).as_millis() as u64),
+                    wall_latency_ms: None,
+                    timing: None,
+                    request_id: None,
+                    content_encoding: None,
+                    is_hook: hook,
+                }
+            }
+            Err(e) => {
+                error!("SSE scenario '{}' failed: {}", scenario.name, e);
+                RequestResult {
+                    scenario_name: Some(scenario.name.clone()),
+                    latency_ms: request_start.elapsed().as_millis() as u64,
+                    status_code,
+                    error: Some(e),
+                    request_start_timestamp: start_time,
+                    request_end_timestamp: Utc::now(),
+                    sse_total_ms: None,
+                    wall_latency_ms: None,
+                    timing: None,
+                    request_id: None,
+                    content_encoding: None,
+                    is_hook: hook,
+                }
+            }
+        }
+    }
+
+    /// Exchange one WebSocket message
+    async fn send_websocket_step(
+        &self,
+        scenario: &Scenario,
+        variables: &mut HashMap<String, String>,
+        hook: bool,
+    ) -> RequestResult {
+        let url = websocket::websocket_url(self.config.target.as_deref(), &scenario.url);
+        let headers: Result<HashMap<String, String>> = scenario
+            .headers
+            .iter()
+            .map(|(k, v)| Ok((k.clone(), self.client.substitute_variables(v, variables)?)))
+            .collect();
+        let message = scenario
+            .body
+            .as_deref()
+            .map(|body| self.client.substitute_variables(body, variables))
+            .transpose();
+        let timeout =
+            Duration::from_secs(scenario.timeout_secs.unwrap_or(DEFAULT_STREAM_TIMEOUT_SECS));
+
+        let start_time = Utc::now();
+        let request_start = Instant::now();
+
+        let result = match (headers, message) {
+            (Ok(headers), Ok(message)) => {
+                let _in_flight = self.in_flight.start();
+                websocket::exchange(&url, &headers, message, timeout).await
+            }
+            (Err(e), _) | (_, Err(e)) => Err(RequestError::Other(e.to_string())),
+        };
+
+        let latency = request_start.elapsed().as_millis() as u64;
+        let end_time = Utc::now();
+
+        match result {
+            Ok(reply) => {
+                if !scenario.extract.is_empty() {
+                    self.extract_variables(&reply.text, scenario, variables);
+                }
+
+                // Measured from opening the connection to the reply, not
+                // including the close handshake
+                RequestResult {
+                    scenario_name: Some(scenario.name.clone()),
+                    latency_ms: reply.connect_ms + reply.reply_ms,
+                    status_code: reply.status,
+                    error: None,
+                    request_start_timestamp: start_time,
+                    request_end_timestamp: end_time,
+                    sse_total_ms: None,
+                    wall_latency_ms: None,
+                    timing: Some(TimingBreakdown {
+                        connect_ms: Some(reply.connect_ms),
+                        reply_ms: Some(reply.reply_ms),
+                        ..TimingBreakdown::default()
+                    }),
+                    request_id: None,
+                    content_encoding: None,
+                    is_hook: hook,
+                }
+            }
+            Err(e) => {
+                error!("WebSocket scenario '{}' failed: {}", scenario.name, e);
+                RequestResult {
+                    scenario_name: Some(scenario.name.clone()),
+                    latency_ms: latency,
+                    status_code: match e {
+                        RequestError::HttpError { status } => status,
+                        _ => 0,
+                    },
+                    error: Some(e),
+                    request_start_timestamp: start_time,
+                    request_end_timestamp: end_time,
+                    sse_total_ms: None,
+                    wall_latency_ms: None,
+                    timing: None,
+                    request_id: None,
+                    content_encoding: None,
+                    is_hook: hook,
+                }
+            }
+        }
+    }
+
+    /// Error for a response status; a 429 is `RateLimited` when
+    /// `respect_rate_limit` is set
+    fn response_error(&self, status: u16) -> Option<RequestError> {
+        if self.config.respect_rate_limit && status == 429 {
+            Some(RequestError::RateLimited)
+        } else {
+            status_error(status)
+        }
+    }
+
+    /// How long to pause the worker after a 429, if `respect_rate_limit`
+    /// is set: the `Retry-After` delay, capped, or a default without one
+    fn rate_limit_pause(&self, status: u16, headers: &HeaderMap) -> Option<Duration> {
+        if !self.config.respect_rate_limit || status != 429 {
+            return None;
+        }
+
+        let pause = retry_after(headers, Utc::now()).unwrap_or(DEFAULT_RATE_LIMIT_PAUSE);
+        Some(pause.min(MAX_RATE_LIMIT_PAUSE))
+    }
+
+    /// Delay before resending a step answered with a 429 and a
+    /// `Retry-After` header, capped at the step's `max_retry_after_secs`;
+    /// `None` when the response should be recorded instead
+    fn retry_delay(
+        &self,
+        scenario: &Scenario,
+        response: &Response,
+        retries: u32,
+    ) -> Option<Duration> {
+        if !scenario.respect_retry_after
+            || response.status().as_u16() != 429
+            || retries >= MAX_RETRY_AFTER_RETRIES
+        {
+            return None;
+        }
+
+        let delay = retry_after(response.headers(), Utc::now())?;
+        Some(delay.min(Duration::from_secs(scenario.max_retry_after_secs)))
+    }
+
+    /// Warn the first time a step's response lacks its
+    /// `latency_from_header`, then only log at debug level
+    fn warn_missing_latency_header(&self, scenario: &Scenario, header: &str, latency: u64) {
+        // Only the first miss of each step takes the write lock
+        let warned = self
+            .missing_latency_headers
+            .read()
+            .unwrap()
+            .contains(&scenario.name);
+        if !warned
+            && self
+                .missing_latency_headers
+                .write()
+                .unwrap()
+                .insert(scenario.name.clone())
+        {
+            warn!(
+                "Scenario '{}': no latency in header '{}', using the measured {}ms (further misses are logged at debug level)",
+                scenario.name, header, latency
+            );
+        } else {
+            debug!(
+                "Scenario '{}': no latency in header '{}', using the measured {}ms",
+                scenario.name, header, latency
+            );
+        }
+    }
+
+    /// Pause the worker for a rate-limit back-off
+    async fn back_off(&self, pause: Option<Duration>) {
+        if let Some(pause) = pause {
+            debug!("Rate limited, pausing worker for {:?}", pause);
+            sleep(pause).await;
+        }
+    }
+
+    /// Send a built request, capturing it for the HAR file when sampled
+    async fn send_captured(
+        &self,
+        request: Result<Request>,
+    ) -> Result<(Response, Option<HarRequest>, Option<TimingBreakdown>)> {
+        let request = request?;
+        let captured = self
+            .har
+            .as_ref()
+            .filter(|har| har.should_capture(self.rng.lock().unwrap().gen()))
+            .map(|_| HarRequest::from_request(&request));
+
+        let _in_flight = self.in_flight.start();
+        let (response, timing) = self.client.send_timed(request).await?;
+        Ok((response, captured, timing))
+    }
+
+    /// Keep a sampled request/response pair for the HAR file
+    fn capture(&self, entry: HarEntry) {
+        if let Some(ref har) = self.har {
+            har.record(entry);
+        }
+    }
+
+    /// Record a result in the metrics and, if sampled, the request log
+    fn record(&self, request_result: RequestResult) {
+        if let Some(ref request_log) = self.request_log {
+            let sample_rate = self.config.sample_rate.unwrap_or(1.0);
+
+            if self.rng.lock().unwrap().gen::<f64>() < sample_rate {
+                if let Err(e) = request_log.write(&request_result, sample_rate < 1.0) {
+                    warn!("Failed to write request log entry: {}", e);
+                }
+            }
+        }
+
+        for json_stream in &self.json_streams {
+            json_stream.write(&request_result);
+        }
+
+        self.metrics.record_worker(self.worker_id, &request_result);
+        self.metrics.record(request_result);
     }
 
     /// Extract variables from response body using JSONPath
@@ -229,48 +1701,107 @@ impl Executor {
         scenario: &Scenario,
         variables: &mut HashMap<String, String>,
     ) {
-        for (var_name, json_path) in &scenario.extract {
-            match serde_json::from_str::<serde_json::Value>(body) {
-                Ok(_json) => {
-                    match JsonPathFinder::from_str(body, json_path) {
-                        Ok(finder) => {
-                            let result = finder.find();
-                            if let serde_json::Value::Array(arr) = result {
-                                if let Some(value) = arr.first() {
-                                    let extracted = match value {
-                                        serde_json::Value::String(s) => s.clone(),
-                                        serde_json::Value::Number(n) => n.to_string(),
-                                        serde_json::Value::Bool(b) => b.to_string(),
-                                        _ => value.to_string(),
-                                    };
-
-                                    debug!("Extracted variable '{}' = '{}'", var_name, extracted);
-                                    variables.insert(var_name.clone(), extracted);
-                                }
-                            } else if result != serde_json::Value::Null {
-                                // Single value result
-                                let extracted = match result {
-                                    serde_json::Value::String(s) => s,
-                                    serde_json::Value::Number(n) => n.to_string(),
-                                    serde_json::Value::Bool(b) => b.to_string(),
-                                    _ => result.to_string(),
-                                };
-                                debug!("Extracted variable '{}' = '{}'", var_name, extracted);
-                                variables.insert(var_name.clone(), extracted);
-                            }
-                        }
-                        Err(e) => {
-                            warn!("JSONPath error for '{}': {}", json_path, e);
-                        }
-                    }
+        for (var_name, rule) in &scenario.extract {
+            let extracted = match rule {
+                ExtractRule::JsonPath(json_path) => extract_json_path(body, json_path),
+                ExtractRule::Transformed {
+                    json_path,
+                    transform,
+                } => find_json_path(body, json_path).and_then(|value| {
+                    value
+                        .map(|value| apply_transform(transform, value))
+                        .transpose()
+                }),
+                ExtractRule::Header { .. } => continue,
+            };
+            match extracted {
+                Ok(Some(extracted)) => {
+                    debug!("Extracted variable '{}' = '{}'", var_name, extracted);
+                    variables.insert(var_name.clone(), extracted);
                 }
-                Err(e) => {
-                    warn!("Failed to parse JSON response: {}", e);
+                Ok(None) => {}
+                Err(e) => warn!("{}", e),
+            }
+        }
+    }
+
+    /// Extract variables from response headers
+    fn extract_header_variables(
+        &self,
+        headers: &HeaderMap,
+        scenario: &Scenario,
+        variables: &mut HashMap<String, String>,
+    ) {
+        for (var_name, rule) in &scenario.extract {
+            let ExtractRule::Header { header } = rule else {
+                continue;
+            };
+            match headers.get(header).and_then(|v| v.to_str().ok()) {
+                Some(value) => {
+                    debug!("Extracted variable '{}' = '{}'", var_name, value);
+                    variables.insert(var_name.clone(), value.to_string());
                 }
+                None => warn!("Response header '{}' not found", header),
             }
         }
     }
 
+    /// Store the raw response body for `capture_body_as` in its
+    /// `capture_body_encoding`, returning the error for a body that is not
+    /// valid UTF-8 when stored as text
+    fn capture_body(
+        &self,
+        body: &[u8],
+        scenario: &Scenario,
+        variables: &mut HashMap<String, String>,
+    ) -> Option<RequestError> {
+        let var_name = scenario.capture_body_as.as_ref()?;
+        let value = if scenario.capture_body_encoding.as_deref() == Some("base64") {
+            BASE64.encode(body)
+        } else {
+            let Ok(text) = std::str::from_utf8(body) else {
+                return Some(RequestError::Other(format!(
+                    "body captured as '{}' is not valid UTF-8; set 'capture_body_encoding: base64'",
+                    var_name
+                )));
+            };
+            text.to_string()
+        };
+        debug!("Captured {} byte body as '{}'", body.len(), var_name);
+        variables.insert(var_name.clone(), value);
+        None
+    }
+
+    /// Hash the response body for `capture_response_hash_as` and
+    /// `assert_response_hash`, returning the assertion failure on a mismatch
+    fn check_response_hash(
+        &self,
+        body: &[u8],
+        scenario: &Scenario,
+        variables: &mut HashMap<String, String>,
+    ) -> Option<RequestError> {
+        if !scenario.hashes_response() {
+            return None;
+        }
+        let hash = hex::encode(Sha256::digest(body));
+
+        if let Some(ref var_name) = scenario.capture_response_hash_as {
+            debug!("Captured response hash as '{}'", var_name);
+            variables.insert(var_name.clone(), hash.clone());
+        }
+        let expected = scenario.assert_response_hash.as_ref()?;
+        let expected = match self.client.substitute_variables(expected, variables) {
+            Ok(expected) => expected,
+            Err(e) => return Some(RequestError::AssertionFailed(e.to_string())),
+        };
+        (!expected.eq_ignore_ascii_case(&hash)).then(|| {
+            RequestError::AssertionFailed(format!(
+                "response hash {} does not match {}",
+                hash, expected
+            ))
+        })
+    }
+
     /// Check if a scenario has been executed (simple check via variables)
     fn has_executed_scenario(
         &self,
@@ -282,36 +1813,291 @@ impl Executor {
         !variables.is_empty()
     }
 
-    /// Clone executor for worker
-    fn clone_for_worker(&self) -> Self {
+    /// Clone executor for worker, sharing the setup variables
+    fn clone_for_worker(&self, worker_id: usize, variables: &HashMap<String, String>) -> Self {
         Self {
             config: self.config.clone(),
-            client: HttpClient::new().expect("Failed to create client"),
+            client: HttpClient::with_dns_cache(&self.config, self.client.dns_cache().cloned())
+                .expect("Failed to create client"),
+            grpc: self.grpc.clone(),
             metrics: Arc::clone(&self.metrics),
+            request_log: self.request_log.clone(),
+            json_streams: self.json_streams.clone(),
+            har: self.har.clone(),
+            global_variables: variables.clone(),
+            seed: self.seed,
+            rng: Mutex::new(worker_rng(self.seed, worker_id)),
+            step_permits: Arc::clone(&self.step_permits),
+            in_flight: Arc::clone(&self.in_flight),
+            missing_latency_headers: Arc::clone(&self.missing_latency_headers),
+            open_model_stats: Mutex::new(None),
+            phase_windows: Mutex::new(Vec::new()),
+            control: None,
+            reload: self.reload.clone(),
+            worker_id,
+            dry_run_steps: None,
         }
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::config::OutputConfig;
+/// Result for a step that was skipped or cancelled without a response
+fn unsent_result(scenario: &Scenario, error: RequestError) -> RequestResult {
+    let now = Utc::now();
+    RequestResult {
+        scenario_name: Some(scenario.name.clone()),
+        latency_ms: 0,
+        status_code: 0,
+        error: Some(error),
+        request_start_timestamp: now,
+        request_end_timestamp: now,
+        sse_total_ms: None,
+        wall_latency_ms: None,
+        timing: None,
+        request_id: None,
+        content_encoding: None,
+        is_hook: false,
+    }
+}
 
-    #[test]
-    fn test_executor_creation() {
-        let config = Config {
-            target: Some("http://example.com".to_string()),
-            method: Some("GET".to_string()),
-            headers: HashMap::new(),
-            body: None,
-            multipart: None,
-            scenarios: vec![],
-            concurrency: 10,
-            duration: "30s".to_string(),
-            mode: "async".to_string(),
-            output: OutputConfig {
-                json: "/app/results/output.json".to_string(),
+/// Start delay of each of `count` workers spread evenly over `ramp_up`,
+/// each moved by up to `jitter` times the gap between starts either way
+fn ramp_up_delays(count: usize, ramp_up: Duration, jitter: f64, rng: &mut StdRng) -> Vec<Duration> {
+    let slot = ramp_up.as_secs_f64() / count.max(1) as f64;
+    (0..count)
+        .map(|i| {
+            let offset = if jitter > 0.0 {
+                rng.gen_range(-jitter * slot..=jitter * slot)
+            } else {
+                0.0
+            };
+            Duration::from_secs_f64((i as f64 * slot + offset).clamp(0.0, ramp_up.as_secs_f64()))
+        })
+        .collect()
+}
+
+/// Deterministic random stream for a worker
+fn worker_rng(seed: u64, worker_id: usize) -> StdRng {
+    StdRng::seed_from_u64(seed.wrapping_add(worker_id as u64))
+}
+
+/// Treat 4xx and 5xx responses as failed requests
+fn status_error(status: u16) -> Option<RequestError> {
+    if status >= 400 {
+        Some(RequestError::HttpError { status })
+    } else {
+        None
+    }
+}
+
+/// Delay requested by a `Retry-After` header, in seconds or as an HTTP date
+fn retry_after(headers: &HeaderMap, now: DateTime<Utc>) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&Utc) - now)
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+/// First value at `json_path` in a JSON response body, with strings
+/// unquoted; `None` when nothing matches
+pub fn extract_json_path(body: &str, json_path: &str) -> Result<Option<String>> {
+    Ok(find_json_path(body, json_path)?.map(scalar_string))
+}
+
+/// First value matching `json_path` in a JSON body
+fn find_json_path(body: &str, json_path: &str) -> Result<Option<serde_json::Value>> {
+    serde_json::from_str::<serde_json::Value>(body)
+        .map_err(|e| anyhow::anyhow!("Failed to parse JSON response: {}", e))?;
+    let finder = JsonPathFinder::from_str(body, json_path)
+        .map_err(|e| anyhow::anyhow!("JSONPath error for '{}': {}", json_path, e))?;
+
+    Ok(match finder.find() {
+        serde_json::Value::Array(values) => values.into_iter().next(),
+        serde_json::Value::Null => None,
+        value => Some(value),
+    })
+}
+
+/// Variable value of a JSON value: strings unquoted, anything else as JSON
+fn scalar_string(value: serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s,
+        serde_json::Value::Number(n) => n.to_string(),
+        serde_json::Value::Bool(b) => b.to_string(),
+        _ => value.to_string(),
+    }
+}
+
+/// Apply one of `EXTRACT_TRANSFORMS` to a JSONPath match
+fn apply_transform(transform: &str, value: serde_json::Value) -> Result<String> {
+    if transform == "json_encode" {
+        return Ok(value.to_string());
+    }
+
+    let value = scalar_string(value);
+    Ok(match transform {
+        "url_encode" => url_encode(&value),
+        "base64_encode" => BASE64.encode(&value),
+        "base64_decode" => {
+            let bytes = BASE64
+                .decode(value.trim())
+                .map_err(|e| anyhow::anyhow!("Failed to base64-decode '{}': {}", value, e))?;
+            String::from_utf8(bytes)
+                .map_err(|_| anyhow::anyhow!("Base64-decoded '{}' is not UTF-8", value))?
+        }
+        "trim" => value.trim().to_string(),
+        other => anyhow::bail!("Unknown transform '{}'", other),
+    })
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters
+fn url_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-_.~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// Compression of a response body that was not decoded on receipt
+fn content_encoding(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(CONTENT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.eq_ignore_ascii_case("identity"))
+        .map(str::to_string)
+}
+
+/// Latency in whole milliseconds reported in the response header `name`
+fn header_latency(headers: &HeaderMap, name: &str) -> Option<u64> {
+    headers.get(name)?.to_str().ok()?.trim().parse().ok()
+}
+
+/// Pick a request from the mix with probability proportional to its weight
+fn pick_request<'a, R: Rng>(requests: &'a [WeightedRequest], rng: &mut R) -> &'a WeightedRequest {
+    requests
+        .choose_weighted(rng, |request| request.weight)
+        .unwrap_or(&requests[0])
+}
+
+/// Shuffle scenarios while keeping every scenario after its `depends_on`.
+/// Scenarios are taken in shuffled order, deferring any whose dependency
+/// has not been placed yet.
+fn shuffle_scenarios<'a, R: Rng>(scenarios: &'a [Scenario], rng: &mut R) -> Vec<&'a Scenario> {
+    let mut pending: Vec<&Scenario> = scenarios.iter().collect();
+    pending.shuffle(rng);
+
+    let mut ordered: Vec<&Scenario> = Vec::with_capacity(pending.len());
+    while !pending.is_empty() {
+        let ready = pending.iter().position(|s| match s.depends_on {
+            Some(ref dep) => {
+                ordered.iter().any(|o| &o.name == dep) || !scenarios.iter().any(|o| &o.name == dep)
+            }
+            None => true,
+        });
+
+        // A dependency cycle can never be satisfied; keep the shuffled order
+        ordered.push(pending.remove(ready.unwrap_or(0)));
+    }
+
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::OutputConfig;
+
+    #[test]
+    fn test_executor_creation() {
+        let config = Config {
+            target: Some("http://example.com".to_string()),
+            method: Some("GET".to_string()),
+            allow_custom_method: false,
+            custom_methods: Vec::new(),
+            headers: HashMap::new(),
+            default_headers: crate::config::DefaultHeaders::default(),
+            host: None,
+            body: None,
+            multipart: None,
+            requests: Vec::new(),
+            scenarios: vec![],
+            scenario_order: None,
+            on_scenario_failure: None,
+            scenario_chain_timeout_secs: None,
+            grpc: None,
+            setup: vec![],
+            teardown: vec![],
+            variables: HashMap::new(),
+            concurrency: 10,
+            duration: "30s".to_string(),
+            phases: None,
+            mode: "async".to_string(),
+            ramp_up: None,
+            ramp_up_jitter_pct: None,
+            worker_stagger_ms: 10,
+            think_time_ms: 10,
+            workload_model: None,
+            arrival_rate: None,
+            max_outstanding: None,
+            auto_content_type: true,
+            omit_empty_headers: true,
+            decompress: true,
+            accept_encoding: None,
+            respect_rate_limit: false,
+            resolve: HashMap::new(),
+            dns_cache_ttl_secs: None,
+            local_address: None,
+            pool_max_idle_per_host: 100,
+            pool_idle_timeout: None,
+            connection_ttl: None,
+            tcp_keepalive: None,
+            tcp_nodelay: true,
+            follow_redirects: true,
+            detailed_timing: false,
+            compress_body: false,
+            minify_json_body: true,
+            pretty_print_json_body: false,
+            strict_variables: false,
+            strict_variable_usage: false,
+            strict_bodies: false,
+            tracing_enabled: false,
+            trace_header: None,
+            seed: None,
+            ui_refresh_ms: 1000,
+            alert_p99_ms: None,
+            latency_precision: 0,
+            apdex_threshold_ms: None,
+            sla_latency_ms: None,
+            histogram_min_ms: None,
+            histogram_max_ms: None,
+            histogram_significant_figures: None,
+            sample_rate: None,
+            capture_sample_rate: None,
+            dashboard_port: None,
+            notify: None,
+            output: OutputConfig {
+                json: "/app/results/output.json".to_string(),
                 html: "/app/results/output.html".to_string(),
+                junit: None,
+                request_log: None,
+                json_stream: None,
+                har: None,
+                grafana_dashboard: None,
+                timestamp_outputs: false,
+                pretty_json: false,
+                prometheus_push_gateway: None,
+                prometheus_job: None,
             },
         };
 
@@ -320,4 +2106,1485 @@ mod tests {
 
         assert!(executor.is_ok());
     }
+
+    #[test]
+    fn test_ramp_up_delays() {
+        let mut rng = worker_rng(7, 0);
+        let ramp_up = Duration::from_secs(4);
+
+        let even = ramp_up_delays(4, ramp_up, 0.0, &mut rng);
+        assert_eq!(even, (0..4).map(Duration::from_secs).collect::<Vec<_>>());
+
+        let jittered = ramp_up_delays(4, ramp_up, 0.2, &mut rng);
+        assert_ne!(jittered, even);
+        for (i, delay) in jittered.iter().enumerate() {
+            let offset = delay.as_secs_f64() - i as f64;
+            assert!(
+                (-0.2..=0.2).contains(&offset),
+                "worker {} at {:?}",
+                i,
+                delay
+            );
+        }
+    }
+
+    #[test]
+    fn test_shuffle_respects_depends_on() {
+        let config: Config = r#"
+target: http://example.com
+scenario_order: random
+scenarios:
+  - { name: login, method: POST, url: /login }
+  - { name: profile, method: GET, url: /me, depends_on: login }
+  - { name: orders, method: GET, url: /orders, depends_on: profile }
+  - { name: health, method: GET, url: /health }
+  - { name: search, method: GET, url: /search }
+output: { json: out.json, html: out.html }
+"#
+        .parse()
+        .unwrap();
+
+        let mut rng = rand::thread_rng();
+        let mut first_names = std::collections::HashSet::new();
+
+        for _ in 0..1000 {
+            let order = shuffle_scenarios(&config.scenarios, &mut rng);
+            assert_eq!(order.len(), config.scenarios.len());
+
+            let position = |name: &str| order.iter().position(|s| s.name == name).unwrap();
+            assert!(position("login") < position("profile"));
+            assert!(position("profile") < position("orders"));
+
+            first_names.insert(order[0].name.clone());
+        }
+
+        // Independent scenarios do get shuffled
+        assert!(first_names.len() > 1);
+    }
+
+    /// Serve `router` on a local port and return its base URL
+    async fn serve(router: axum::Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_request_mix_by_weight() {
+        let router = axum::Router::new()
+            .route("/items", axum::routing::get(|| async { "[]" }))
+            .route(
+                "/items",
+                axum::routing::post(|body: String| async move {
+                    if body == r#"{"name":"a"}"# {
+                        axum::http::StatusCode::CREATED
+                    } else {
+                        axum::http::StatusCode::BAD_REQUEST
+                    }
+                }),
+            );
+        let target = serve(router).await;
+
+        let config: Config = format!(
+            r#"
+target: {}
+seed: 7
+requests:
+  - {{ name: read, url: /items, weight: 3 }}
+  - {{ name: write, method: POST, url: /items, body: '{{"name": "a"}}', weight: 1 }}
+output: {{ json: out.json, html: out.html }}
+"#,
+            target
+        )
+        .parse()
+        .unwrap();
+
+        let metrics = Arc::new(MetricsCollector::new());
+        let executor = Executor::new(config, Arc::clone(&metrics)).unwrap();
+        for _ in 0..200 {
+            executor.execute_iteration().await;
+        }
+
+        let summaries = executor.request_summaries();
+        assert_eq!(summaries[0].0, "read");
+        assert_eq!(summaries[1].0, "write");
+        let (reads, writes) = (summaries[0].1.total_requests, summaries[1].1.total_requests);
+        assert_eq!(reads + writes, 200);
+        assert!((120..=180).contains(&reads), "{} reads", reads);
+        assert_eq!(summaries[1].1.failed_requests, 0);
+    }
+
+    fn setup_config(target: &str) -> Config {
+        format!(
+            r#"
+target: {}
+concurrency: 1
+setup:
+  - {{ name: login, method: POST, url: /login, extract: {{ token: "$.token" }} }}
+scenarios:
+  - {{ name: ping, method: GET, url: /ping, headers: {{ X-Token: "{{{{ token }}}}" }} }}
+teardown:
+  - {{ name: logout, method: DELETE, url: /logout, headers: {{ X-Token: "{{{{ token }}}}" }} }}
+output: {{ json: out.json, html: out.html }}
+"#,
+            target
+        )
+        .parse()
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_setup_and_teardown_run_once() {
+        use axum::http::HeaderMap;
+        use axum::routing::{delete, get, post};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let logins = Arc::new(AtomicUsize::new(0));
+        let logouts = Arc::new(std::sync::Mutex::new(Vec::new()));
+
+        let router = axum::Router::new()
+            .route(
+                "/login",
+                post({
+                    let logins = Arc::clone(&logins);
+                    move || async move {
+                        logins.fetch_add(1, Ordering::SeqCst);
+                        r#"{"token": "abc"}"#
+                    }
+                }),
+            )
+            .route(
+                "/ping",
+                get(|headers: HeaderMap| async move {
+                    if headers["x-token"] == "abc" {
+                        axum::http::StatusCode::OK
+                    } else {
+                        axum::http::StatusCode::UNAUTHORIZED
+                    }
+                }),
+            )
+            .route(
+                "/logout",
+                delete({
+                    let logouts = Arc::clone(&logouts);
+                    move |headers: HeaderMap| async move {
+                        let token = headers["x-token"].to_str().unwrap().to_string();
+                        logouts.lock().unwrap().push(token);
+                    }
+                }),
+            );
+        let target = serve(router).await;
+
+        let metrics = Arc::new(MetricsCollector::new());
+        let executor = Executor::new(setup_config(&target), Arc::clone(&metrics)).unwrap();
+        executor.run(1).await.unwrap();
+
+        let summary = metrics.generate_summary();
+        assert!(summary.total_requests > 0);
+        assert_eq!(summary.failed_requests, 0);
+        assert!(metrics
+            .get_results()
+            .iter()
+            .all(|r| r.scenario_name.as_deref() == Some("ping")));
+        assert_eq!(logins.load(Ordering::SeqCst), 1);
+        assert_eq!(*logouts.lock().unwrap(), vec!["abc".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_setup_failure_aborts_run() {
+        let router = axum::Router::new().route(
+            "/login",
+            axum::routing::post(|| async { axum::http::StatusCode::INTERNAL_SERVER_ERROR }),
+        );
+        let target = serve(router).await;
+
+        let metrics = Arc::new(MetricsCollector::new());
+        let executor = Executor::new(setup_config(&target), Arc::clone(&metrics)).unwrap();
+        let err = executor.run(1).await.unwrap_err();
+
+        assert!(err.to_string().contains("Setup scenario 'login' failed"));
+        assert_eq!(metrics.generate_summary().total_requests, 0);
+    }
+
+    #[tokio::test]
+    async fn test_repeat_runs_step_in_a_row() {
+        use axum::http::HeaderMap;
+
+        let pages = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let router = axum::Router::new().route(
+            "/items",
+            axum::routing::get({
+                let pages = Arc::clone(&pages);
+                move |headers: HeaderMap| async move {
+                    let page = headers["x-page"].to_str().unwrap().to_string();
+                    let cursor = headers["x-cursor"].to_str().unwrap().to_string();
+                    pages.lock().unwrap().push((page.clone(), cursor));
+                    format!(r#"{{"next": "after-{}"}}"#, page)
+                }
+            }),
+        );
+        let target = serve(router).await;
+
+        let config: Config = format!(
+            r#"
+target: {}
+scenarios:
+  - name: list
+    method: GET
+    url: /items
+    repeat: 3
+    headers: {{ X-Page: "{{{{ repeat_index }}}}", X-Cursor: "{{{{ next }}}}" }}
+    extract: {{ next: "$.next" }}
+output: {{ json: out.json, html: out.html }}
+"#,
+            target
+        )
+        .parse()
+        .unwrap();
+
+        let metrics = Arc::new(MetricsCollector::new());
+        let executor = Executor::new(config, Arc::clone(&metrics)).unwrap();
+        executor.execute_scenarios().await;
+
+        assert_eq!(
+            *pages.lock().unwrap(),
+            vec![
+                ("0".to_string(), "{{ next }}".to_string()),
+                ("1".to_string(), "after-0".to_string()),
+                ("2".to_string(), "after-1".to_string()),
+            ]
+        );
+        assert_eq!(metrics.generate_summary().total_requests, 3);
+    }
+
+    #[tokio::test]
+    async fn test_graphql_errors_are_failures() {
+        use axum::http::HeaderMap;
+
+        let router = axum::Router::new().route(
+            "/graphql",
+            axum::routing::post(|headers: HeaderMap, body: String| async move {
+                let request: serde_json::Value = serde_json::from_str(&body).unwrap();
+                assert_eq!(headers["content-type"], "application/json");
+                assert!(request["query"].as_str().unwrap().contains("user(id: $id)"));
+
+                if request["variables"]["id"] == "42" {
+                    r#"{"data": {"user": {"name": "Ada"}}}"#
+                } else {
+                    r#"{"data": null, "errors": [{"message": "User not found"}]}"#
+                }
+            }),
+        );
+        let target = serve(router).await;
+
+        let config: Config = format!(
+            r#"
+target: {}
+scenarios:
+  - name: user
+    method: POST
+    url: /graphql
+    graphql:
+      query: "query User($id: ID!) {{ user(id: $id) {{ name }} }}"
+      variables: {{ id: "{{{{ user_id }}}}" }}
+    extract: {{ name: "$.data.user.name" }}
+output: {{ json: out.json, html: out.html }}
+"#,
+            target
+        )
+        .parse()
+        .unwrap();
+
+        let metrics = Arc::new(MetricsCollector::new());
+        let executor = Executor::new(config.clone(), Arc::clone(&metrics)).unwrap();
+
+        for user_id in ["42", "7"] {
+            let mut variables = HashMap::from([("user_id".to_string(), user_id.to_string())]);
+            executor
+                .execute_scenario_step(&config.scenarios[0], &mut variables, false)
+                .await;
+
+            if user_id == "42" {
+                assert_eq!(variables["name"], "Ada");
+            }
+        }
+
+        let results = metrics.get_results();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].error, None);
+        assert_eq!(
+            results[1].error,
+            Some(RequestError::GraphQLError("User not found".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_seed_makes_workers_reproducible() {
+        let config: Config = r#"
+target: http://example.com
+seed: 42
+scenario_order: random
+scenarios:
+  - { name: a, method: GET, url: /a }
+  - { name: b, method: GET, url: /b }
+  - { name: c, method: GET, url: /c }
+  - { name: d, method: GET, url: /d }
+output: { json: out.json, html: out.html }
+"#
+        .parse()
+        .unwrap();
+
+        let orders = |worker_id: usize| {
+            let executor = Executor::new(config.clone(), Arc::new(MetricsCollector::new()))
+                .unwrap()
+                .clone_for_worker(worker_id, &HashMap::new());
+            let mut rng = executor.rng.lock().unwrap();
+            (0..20)
+                .map(|_| {
+                    shuffle_scenarios(&config.scenarios, &mut *rng)
+                        .iter()
+                        .map(|s| s.name.clone())
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(orders(1), orders(1));
+        assert_ne!(orders(1), orders(2));
+    }
+
+    #[tokio::test]
+    async fn test_head_skips_extraction() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("HEAD"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let config: Config = format!(
+            r#"
+target: {}
+scenarios:
+  - {{ name: check, method: HEAD, url: /resource, extract: {{ id: "$.id" }} }}
+output: {{ json: out.json, html: out.html }}
+"#,
+            server.uri()
+        )
+        .parse()
+        .unwrap();
+        let scenario = &config.scenarios[0];
+        assert!(!scenario.extracts_from_body());
+
+        let metrics = Arc::new(MetricsCollector::new());
+        let executor = Executor::new(config.clone(), Arc::clone(&metrics)).unwrap();
+        let mut variables = HashMap::new();
+        executor
+            .execute_scenario_step(scenario, &mut variables, false)
+            .await;
+
+        assert!(variables.is_empty());
+        assert_eq!(metrics.get_results()[0].error, None);
+    }
+
+    #[test]
+    fn test_extract_transforms() {
+        let config: Config = r#"
+target: http://localhost
+scenarios:
+  - name: list
+    method: GET
+    url: /items
+    extract:
+      items: { json_path: "$.items", transform: json_encode }
+      name: { json_path: "$.name", transform: json_encode }
+      query: { json_path: "$.name", transform: url_encode }
+      encoded: { json_path: "$.name", transform: base64_encode }
+      decoded: { json_path: "$.secret", transform: base64_decode }
+      padded: { json_path: "$.padded", transform: trim }
+output: { json: out.json, html: out.html }
+"#
+        .parse()
+        .unwrap();
+        let executor = Executor::new(config.clone(), Arc::new(MetricsCollector::new())).unwrap();
+
+        let body =
+            r#"{"items": [1, {"a": "b"}], "name": "a b&c", "secret": "aGk=", "padded": "  x "}"#;
+        let mut variables = HashMap::new();
+        executor.extract_variables(body, &config.scenarios[0], &mut variables);
+
+        assert_eq!(variables["items"], r#"[1,{"a":"b"}]"#);
+        assert_eq!(variables["name"], r#""a b&c""#);
+        assert_eq!(variables["query"], "a%20b%26c");
+        assert_eq!(variables["encoded"], "YSBiJmM=");
+        assert_eq!(variables["decoded"], "hi");
+        assert_eq!(variables["padded"], "x");
+
+        let err = r#"
+target: http://localhost
+scenarios:
+  - { name: list, method: GET, url: /items, extract: { id: { json_path: "$.id", transform: upper } } }
+output: { json: out.json, html: out.html }
+"#
+        .parse::<Config>()
+        .unwrap_err();
+        assert!(err.to_string().contains("Unknown transform 'upper'"));
+    }
+
+    #[tokio::test]
+    async fn test_open_model_caps_outstanding() {
+        let target = serve(axum::Router::new().route(
+            "/",
+            axum::routing::get(|| async {
+                sleep(Duration::from_millis(300)).await;
+                "ok"
+            }),
+        ))
+        .await;
+
+        let config: Config = format!(
+            r#"
+target: {}
+workload_model: open
+arrival_rate: 50
+max_outstanding: 5
+output: {{ json: out.json, html: out.html }}
+"#,
+            target
+        )
+        .parse()
+        .unwrap();
+
+        let metrics = Arc::new(MetricsCollector::new());
+        let executor = Executor::new(config, Arc::clone(&metrics)).unwrap();
+        executor.run(1).await.unwrap();
+
+        let stats = executor.open_model_stats().unwrap();
+        let summary = metrics.generate_summary();
+
+        // 50/s against a 300ms server needs ~15 in flight, so launches drop
+        assert_eq!(stats.max_outstanding, 5);
+        assert_eq!(executor.peak_concurrency(), 5);
+        assert!(stats.dropped_iterations > 0);
+        assert!(summary.total_requests >= 5);
+        assert_eq!(summary.failed_requests, 0);
+    }
+
+    #[tokio::test]
+    async fn test_har_captures_request_and_response() {
+        let target = serve(axum::Router::new().route(
+            "/login",
+            axum::routing::post(|body: String| async move { format!(r#"{{"echo": {}}}"#, body) }),
+        ))
+        .await;
+
+        let config: Config = format!(
+            r#"
+target: {}
+scenarios:
+  - {{ name: login, method: POST, url: /login, body: '{{"user": "a"}}' }}
+output: {{ json: out.json, html: out.html }}
+"#,
+            target
+        )
+        .parse()
+        .unwrap();
+
+        let har = Arc::new(HarRecorder::new(1.0));
+        let executor = Executor::new(config.clone(), Arc::new(MetricsCollector::new()))
+            .unwrap()
+            .with_har(Arc::clone(&har));
+        executor
+            .execute_scenario_step(&config.scenarios[0], &mut HashMap::new(), false)
+            .await;
+
+        let entries = har.entries();
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0];
+        assert_eq!(entry.request.method, "POST");
+        assert_eq!(entry.request.url, format!("{}/login", target));
+        assert_eq!(
+            entry.request.post_data.as_ref().unwrap().text,
+            r#"{"user":"a"}"#
+        );
+        assert_eq!(entry.response.status, 200);
+        assert_eq!(entry.response.content.text, r#"{"echo": {"user":"a"}}"#);
+    }
+
+    #[tokio::test]
+    async fn test_step_copies_share_run_permits() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let target = serve(axum::Router::new().route(
+            "/upload",
+            axum::routing::post({
+                let (in_flight, peak) = (Arc::clone(&in_flight), Arc::clone(&peak));
+                move || async move {
+                    let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    sleep(Duration::from_millis(100)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    r#"{"id": "file"}"#
+                }
+            }),
+        ))
+        .await;
+
+        let config: Config = format!(
+            r#"
+target: {}
+concurrency: 2
+scenarios:
+  - {{ name: upload, method: POST, url: /upload, concurrency: 4, extract: {{ id: "$.id" }} }}
+output: {{ json: out.json, html: out.html }}
+"#,
+            target
+        )
+        .parse()
+        .unwrap();
+
+        let metrics = Arc::new(MetricsCollector::new());
+        let executor = Executor::new(config.clone(), Arc::clone(&metrics)).unwrap();
+        let mut variables = HashMap::new();
+        executor
+            .execute_step_copies(&config.scenarios[0], &mut variables, None)
+            .await;
+
+        assert_eq!(metrics.generate_summary().total_requests, 4);
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+        assert_eq!(variables.get("id").map(String::as_str), Some("file"));
+    }
+
+    #[tokio::test]
+    async fn test_scenario_timeout_counts_as_timeout() {
+        let target = serve(axum::Router::new().route(
+            "/report",
+            axum::routing::get(|| async {
+                sleep(Duration::from_millis(500)).await;
+                "done"
+            }),
+        ))
+        .await;
+
+        let config: Config = format!(
+            r#"
+target: {}
+scenarios:
+  - {{ name: report, method: GET, url: /report, timeout: 50ms }}
+output: {{ json: out.json, html: out.html }}
+"#,
+            target
+        )
+        .parse()
+        .unwrap();
+
+        let metrics = Arc::new(MetricsCollector::new());
+        let executor = Executor::new(config.clone(), Arc::clone(&metrics)).unwrap();
+        let success = executor
+            .execute_scenario_step(&config.scenarios[0], &mut HashMap::new(), false)
+            .await;
+
+        assert!(!success);
+        assert_eq!(metrics.generate_summary().timeout_count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_phases_run_in_order() {
+        let target =
+            serve(axum::Router::new().route("/", axum::routing::get(|| async { "ok" }))).await;
+
+        let config: Config = format!(
+            r#"
+target: {}
+phases:
+  - {{ name: load, concurrency: 2, duration: 1s }}
+  - {{ name: cooldown, concurrency: 1, duration: 1s, rate_limit: 5 }}
+output: {{ json: out.json, html: out.html }}
+"#,
+            target
+        )
+        .parse()
+        .unwrap();
+        assert_eq!(config.total_duration_secs().unwrap(), 2);
+
+        let metrics = Arc::new(MetricsCollector::new());
+        let executor = Executor::new(config, Arc::clone(&metrics)).unwrap();
+        executor.run(0).await.unwrap();
+
+        let phases = executor.phase_summaries();
+        let names: Vec<&str> = phases.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["load", "cooldown"]);
+
+        // The unthrottled phase far outpaces the 5/s one
+        assert!(phases[0].1.total_requests > 20);
+        assert!(phases[1].1.total_requests <= 6);
+    }
+
+    #[tokio::test]
+    async fn test_accept_encoding_reported() {
+        use axum::response::IntoResponse;
+
+        let router = axum::Router::new().route(
+            "/",
+            axum::routing::get(|headers: axum::http::HeaderMap| async move {
+                let accept = headers["accept-encoding"].to_str().unwrap().to_string();
+                if accept.contains("gzip") {
+                    let body = crate::client::gzip(b"ok").unwrap();
+                    ([("content-encoding", "gzip")], body).into_response()
+                } else {
+                    accept.into_response()
+                }
+            }),
+        );
+        let target = serve(router).await;
+
+        for (accept_encoding, decompress, expected) in [
+            ("gzip, br", false, Some("gzip")),
+            ("gzip, br", true, None),
+            ("identity", true, None),
+        ] {
+            let config: Config = format!(
+                "target: {}\naccept_encoding: \"{}\"\ndecompress: {}\noutput: {{ json: a, html: b }}\n",
+                target, accept_encoding, decompress
+            )
+            .parse()
+            .unwrap();
+
+            let metrics = Arc::new(MetricsCollector::new());
+            let executor = Executor::new(config, Arc::clone(&metrics)).unwrap();
+            executor.execute_simple_request().await;
+
+            let results = metrics.get_results();
+            assert_eq!(results[0].status_code, 200);
+            assert_eq!(results[0].content_encoding.as_deref(), expected);
+            assert_eq!(
+                metrics.generate_summary().gzip_responses,
+                expected.is_some()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_trace_header_recorded() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let router = axum::Router::new().route(
+            "/",
+            axum::routing::get({
+                let seen = Arc::clone(&seen);
+                move |headers: axum::http::HeaderMap| async move {
+                    let id = headers["x-request-id"].to_str().unwrap().to_string();
+                    seen.lock().unwrap().push(id);
+                    "ok"
+                }
+            }),
+        );
+        let target = serve(router).await;
+
+        let config: Config = format!(
+            "target: {}\ntrace_header: X-Request-Id\noutput: {{ json: a, html: b }}\n",
+            target
+        )
+        .parse()
+        .unwrap();
+
+        let metrics = Arc::new(MetricsCollector::new());
+        let executor = Executor::new(config, Arc::clone(&metrics)).unwrap();
+        executor.execute_simple_request().await;
+        executor.execute_simple_request().await;
+
+        let recorded: Vec<String> = metrics
+            .get_results()
+            .into_iter()
+            .map(|r| r.request_id.unwrap())
+            .collect();
+        assert_eq!(recorded, *seen.lock().unwrap());
+        assert_ne!(recorded[0], recorded[1]);
+        assert_eq!(recorded[0].len(), 36);
+    }
+
+    #[tokio::test]
+    async fn test_trace_id_propagated_from_header() {
+        const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let record = |seen: &Arc<Mutex<Vec<String>>>| {
+            let seen = Arc::clone(seen);
+            move |headers: axum::http::HeaderMap| async move {
+                let traceparent = headers["traceparent"].to_str().unwrap().to_string();
+                seen.lock().unwrap().push(traceparent);
+                ([("x-trace-id", TRACE_ID)], "ok")
+            }
+        };
+        let router = axum::Router::new()
+            .route("/login", axum::routing::post(record(&seen)))
+            .route("/orders", axum::routing::get(record(&seen)));
+        let target = serve(router).await;
+
+        let config: Config = format!(
+            r#"
+target: {}
+tracing_enabled: true
+scenarios:
+  - {{ name: login, method: POST, url: /login, extract: {{ trace_id: {{ header: X-Trace-Id }} }} }}
+  - {{ name: orders, method: GET, url: /orders }}
+output: {{ json: out.json, html: out.html }}
+"#,
+            target
+        )
+        .parse()
+        .unwrap();
+
+        let executor = Executor::new(config, Arc::new(MetricsCollector::new())).unwrap();
+        executor.execute_scenarios().await;
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 2);
+        assert_eq!(seen[0].len(), 55);
+        assert!(!seen[0].contains(TRACE_ID));
+        assert!(seen[1].starts_with(&format!("00-{}-", TRACE_ID)));
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let now = DateTime::parse_from_rfc3339("2015-10-21T07:28:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let headers = |value: &str| HeaderMap::from_iter([(RETRY_AFTER, value.parse().unwrap())]);
+
+        assert_eq!(
+            retry_after(&headers("120"), now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            retry_after(&headers("Wed, 21 Oct 2015 07:28:30 GMT"), now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            retry_after(&headers("Wed, 21 Oct 2015 07:00:00 GMT"), now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(retry_after(&headers("soon"), now), None);
+        assert_eq!(retry_after(&HeaderMap::new(), now), None);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_responses() {
+        let router = axum::Router::new().route(
+            "/",
+            axum::routing::get(|| async {
+                (
+                    axum::http::StatusCode::TOO_MANY_REQUESTS,
+                    [("retry-after", "0")],
+                )
+            }),
+        );
+        let target = serve(router).await;
+
+        for respect_rate_limit in [true, false] {
+            let config: Config = format!(
+                "target: {}\nrespect_rate_limit: {}\noutput: {{ json: a, html: b }}\n",
+                target, respect_rate_limit
+            )
+            .parse()
+            .unwrap();
+
+            let metrics = Arc::new(MetricsCollector::new());
+            let executor = Executor::new(config, Arc::clone(&metrics)).unwrap();
+            executor.execute_simple_request().await;
+            executor.execute_simple_request().await;
+
+            let summary = metrics.generate_summary();
+            assert_eq!(summary.throttled_requests, 2);
+            if respect_rate_limit {
+                assert_eq!(summary.rate_limited_count, 2);
+                assert_eq!(summary.failed_requests, 0);
+                assert_eq!(summary.error_rate, 0.0);
+            } else {
+                assert_eq!(summary.rate_limited_count, 0);
+                assert_eq!(summary.failed_requests, 2);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_resends_step() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let seen = Arc::clone(&calls);
+        let router = axum::Router::new()
+            .route(
+                "/busy",
+                axum::routing::get(move || async move {
+                    if seen.fetch_add(1, Ordering::SeqCst) < 2 {
+                        (
+                            axum::http::StatusCode::TOO_MANY_REQUESTS,
+                            [("retry-after", "0")],
+                        )
+                    } else {
+                        (axum::http::StatusCode::OK, [("retry-after", "0")])
+                    }
+                }),
+            )
+            .route(
+                "/always-busy",
+                axum::routing::get(|| async {
+                    (
+                        axum::http::StatusCode::TOO_MANY_REQUESTS,
+                        [("retry-after", "0")],
+                    )
+                }),
+            );
+        let target = serve(router).await;
+
+        let config: Config = format!(
+            r#"
+target: {}
+scenarios:
+  - {{ name: busy, method: GET, url: /busy }}
+  - {{ name: always-busy, method: GET, url: /always-busy }}
+  - {{ name: no-retry, method: GET, url: /always-busy, respect_retry_after: false }}
+output: {{ json: out.json, html: out.html }}
+"#,
+            target
+        )
+        .parse()
+        .unwrap();
+
+        let metrics = Arc::new(MetricsCollector::new());
+        let executor = Executor::new(config, Arc::clone(&metrics)).unwrap();
+        executor.execute_scenarios().await;
+
+        let statuses: Vec<u16> = metrics
+            .get_results()
+            .iter()
+            .map(|r| r.status_code)
+            .collect();
+        assert_eq!(statuses, vec![200, 429, 429]);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        // Two resent for `busy`, the retries `always-busy` ran out of plus
+        // its recorded 429, and the one `no-retry` recorded
+        let summary = metrics.generate_summary();
+        assert_eq!(
+            summary.throttled_requests,
+            2 + MAX_RETRY_AFTER_RETRIES as usize + 2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_latency_from_header() {
+        let router = axum::Router::new().route(
+            "/",
+            axum::routing::get(|| async {
+                tokio::time::sleep(Duration::from_millis(60)).await;
+                [("x-upstream-latency-ms", "42")]
+            }),
+        );
+        let target = serve(router).await;
+
+        let config: Config = format!(
+            r#"
+target: {}
+scenarios:
+  - {{ name: proxied, method: GET, url: /, latency_from_header: X-Upstream-Latency-Ms }}
+  - {{ name: missing, method: GET, url: /, latency_from_header: X-Other-Latency }}
+output: {{ json: out.json, html: out.html }}
+"#,
+            target
+        )
+        .parse()
+        .unwrap();
+
+        let metrics = Arc::new(MetricsCollector::new());
+        let executor = Executor::new(config, Arc::clone(&metrics)).unwrap();
+        let steps = executor.dry_run().await;
+
+        let proxied = &steps[0].result;
+        assert_eq!(proxied.latency_ms, 42);
+        assert!(proxied.wall_latency_ms.unwrap() >= 60);
+
+        // Without the header the measured time is used
+        let missing = &steps[1].result;
+        assert!(missing.latency_ms >= 60);
+        assert_eq!(missing.wall_latency_ms, Some(missing.latency_ms));
+    }
+
+    #[tokio::test]
+    async fn test_skip_if_records_skipped_step() {
+        let router = axum::Router::new()
+            .route(
+                "/item",
+                axum::routing::get(|| async { r#"{"available": "false"}"# }),
+            )
+            .route(
+                "/cart",
+                axum::routing::post(|| async { axum::http::StatusCode::INTERNAL_SERVER_ERROR }),
+            );
+        let target = serve(router).await;
+
+        let config: Config = format!(
+            r#"
+target: {}
+scenarios:
+  - {{ name: item, method: GET, url: /item, extract: {{ available: "$.available" }} }}
+  - name: add-to-cart
+    method: POST
+    url: /cart
+    skip_if: {{ variable: available, operator: eq, value: "false" }}
+output: {{ json: out.json, html: out.html }}
+"#,
+            target
+        )
+        .parse()
+        .unwrap();
+
+        let metrics = Arc::new(MetricsCollector::new());
+        let executor = Executor::new(config, Arc::clone(&metrics)).unwrap();
+        executor.execute_scenarios().await;
+
+        let results = metrics.get_results();
+        assert_eq!(results[1].scenario_name.as_deref(), Some("add-to-cart"));
+        assert_eq!(results[1].status_code, 0);
+        assert_eq!(results[1].error, Some(RequestError::Skipped));
+
+        let summary = metrics.generate_summary();
+        assert_eq!(summary.skipped_requests, 1);
+        assert_eq!(summary.failed_requests, 0);
+        assert_eq!(summary.error_rate, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_reports_each_step() {
+        let router = axum::Router::new()
+            .route(
+                "/login",
+                axum::routing::post(|| async { r#"{"token": "abc", "user": 7}"# }),
+            )
+            .route(
+                "/users/:id",
+                axum::routing::get(|| async { axum::http::StatusCode::NOT_FOUND }),
+            );
+        let target = serve(router).await;
+
+        let config: Config = format!(
+            r#"
+target: {}
+on_scenario_failure: abort
+scenarios:
+  - {{ name: login, method: POST, url: /login, extract: {{ token: "$.token", user: "$.user" }} }}
+  - {{ name: profile, method: GET, url: "/users/{{{{ user }}}}", headers: {{ Authorization: "{{{{ token }}}}" }} }}
+  - {{ name: logout, method: GET, url: /users/logout }}
+output: {{ json: out.json, html: out.html }}
+"#,
+            target
+        )
+        .parse()
+        .unwrap();
+
+        let executor = Executor::new(config, Arc::new(MetricsCollector::new())).unwrap();
+        let steps = executor.dry_run().await;
+
+        // The failed profile step aborts the chain before logout
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps[0].result.status_code, 200);
+        assert_eq!(
+            steps[0].extracted,
+            vec![
+                ("token".to_string(), "abc".to_string()),
+                ("user".to_string(), "7".to_string())
+            ]
+        );
+        assert_eq!(steps[1].url, format!("{}/users/7", target));
+        assert!(steps[1].result.is_failure());
+        assert!(steps[1].extracted.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_success_if_criteria() {
+        let router = axum::Router::new()
+            .route("/fast", axum::routing::get(|| async { "ok" }))
+            .route(
+                "/slow",
+                axum::routing::get(|| async {
+                    tokio::time::sleep(Duration::from_millis(300)).await;
+                    "ok"
+                }),
+            )
+            .route(
+                "/gone",
+                axum::routing::get(|| async { axum::http::StatusCode::NOT_FOUND }),
+            );
+        let target = serve(router).await;
+
+        let config: Config = format!(
+            r#"
+target: {}
+scenarios:
+  - {{ name: fast, method: GET, url: /fast, success_if: {{ status: [200], max_latency_ms: 250 }} }}
+  - {{ name: slow, method: GET, url: /slow, success_if: {{ status: [200], max_latency_ms: 250 }} }}
+  - {{ name: gone, method: GET, url: /gone, success_if: {{ status: [200] }} }}
+  - {{ name: expected-gone, method: GET, url: /gone, success_if: {{ status: [404] }} }}
+output: {{ json: out.json, html: out.html }}
+"#,
+            target
+        )
+        .parse()
+        .unwrap();
+
+        let executor = Executor::new(config, Arc::new(MetricsCollector::new())).unwrap();
+        let steps = executor.dry_run().await;
+        let error = |i: usize| steps[i].result.error.as_ref().map(ToString::to_string);
+
+        assert_eq!(error(0), None);
+        assert!(error(1)
+            .unwrap()
+            .starts_with("assertion failed: too slow: "));
+        assert_eq!(
+            error(2).as_deref(),
+            Some("assertion failed: bad status 404, expected 200")
+        );
+        assert_eq!(error(3), None);
+    }
+
+    #[tokio::test]
+    async fn test_capture_body_as() {
+        let forwarded = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&forwarded);
+        let router = axum::Router::new()
+            .route(
+                "/text",
+                axum::routing::get(|| async { "line one\nline {{ two }}\n" }),
+            )
+            .route(
+                "/binary",
+                axum::routing::get(|| async { vec![0xffu8, 0x00, 0xfe] }),
+            )
+            .route(
+                "/forward",
+                axum::routing::post(move |body: String| async move {
+                    seen.lock().unwrap().push(body);
+                }),
+            );
+        let target = serve(router).await;
+
+        let config: Config = format!(
+            r#"
+target: {}
+scenarios:
+  - {{ name: text, method: GET, url: /text, capture_body_as: text }}
+  - {{ name: binary, method: GET, url: /binary, capture_body_as: binary, capture_body_encoding: base64 }}
+  - {{ name: forward, method: POST, url: /forward, body: "{{{{ text }}}}|{{{{ binary }}}}" }}
+  - {{ name: raw, method: GET, url: /binary, capture_body_as: raw }}
+output: {{ json: out.json, html: out.html }}
+"#,
+            target
+        )
+        .parse()
+        .unwrap();
+
+        let metrics = Arc::new(MetricsCollector::new());
+        let executor = Executor::new(config, Arc::clone(&metrics)).unwrap();
+        let steps = executor.dry_run().await;
+
+        assert_eq!(
+            *forwarded.lock().unwrap(),
+            vec!["line one\nline {{ two }}\n|/wD+".to_string()]
+        );
+        // A binary body can't be stored as text
+        assert!(matches!(
+            steps[3].result.error,
+            Some(RequestError::Other(_))
+        ));
+        assert!(steps[3].extracted.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_response_hash() {
+        let router = axum::Router::new()
+            .route("/hello", axum::routing::get(|| async { "hello" }))
+            .route(
+                "/echo",
+                axum::routing::post(|body: String| async move { body }),
+            );
+        let target = serve(router).await;
+        let hello = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+
+        let config: Config = format!(
+            r#"
+target: {target}
+scenarios:
+  - {{ name: capture, method: GET, url: /hello, capture_response_hash_as: hash }}
+  - {{ name: same, method: GET, url: /hello, assert_response_hash: "{upper}" }}
+  - {{ name: changed, method: POST, url: /echo, body: "bye", assert_response_hash: "{hello}" }}
+  - {{ name: again, method: GET, url: /hello, assert_response_hash: "{{{{ hash }}}}" }}
+output: {{ json: out.json, html: out.html }}
+"#,
+            upper = hello.to_uppercase()
+        )
+        .parse()
+        .unwrap();
+
+        let executor = Executor::new(config, Arc::new(MetricsCollector::new())).unwrap();
+        let steps = executor.dry_run().await;
+
+        assert_eq!(
+            steps[0].extracted,
+            vec![("hash".to_string(), hello.to_string())]
+        );
+        assert!(steps[1].result.error.is_none());
+        assert!(matches!(
+            steps[2].result.error,
+            Some(RequestError::AssertionFailed(ref message)) if message.contains(hello)
+        ));
+        assert!(steps[3].result.error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_before_each_and_after_each_hooks() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&log);
+        let router = axum::Router::new().fallback(
+            move |method: axum::http::Method, uri: axum::http::Uri| async move {
+                seen.lock()
+                    .unwrap()
+                    .push(format!("{} {}", method, uri.path()));
+                match (method.as_str(), uri.path()) {
+                    ("POST", "/items") => (axum::http::StatusCode::CREATED, r#"{"id": "7"}"#),
+                    ("POST", "/fail") => (axum::http::StatusCode::INTERNAL_SERVER_ERROR, ""),
+                    _ => (axum::http::StatusCode::OK, ""),
+                }
+            },
+        );
+        let target = serve(router).await;
+
+        let config: Config = format!(
+            r#"
+target: {}
+scenarios:
+  - name: read
+    method: GET
+    url: "/items/{{{{ id }}}}"
+    before_each:
+      - {{ name: create, method: POST, url: /items, extract: {{ id: $.id }} }}
+    after_each:
+      - {{ name: delete, method: DELETE, url: "/items/{{{{ id }}}}" }}
+  - name: unreachable
+    method: GET
+    url: /never
+    before_each:
+      - {{ name: fail, method: POST, url: /fail }}
+    after_each:
+      - {{ name: cleanup, method: DELETE, url: "/items/{{{{ id }}}}" }}
+output: {{ json: out.json, html: out.html }}
+"#,
+            target
+        )
+        .parse()
+        .unwrap();
+
+        let metrics = Arc::new(MetricsCollector::new());
+        let executor = Executor::new(config, Arc::clone(&metrics)).unwrap();
+        executor.execute_scenarios().await;
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![
+                "POST /items",
+                "GET /items/7",
+                "DELETE /items/7",
+                "POST /fail",
+                "DELETE /items/7"
+            ]
+        );
+
+        // Only the parent steps count towards the load test statistics
+        let summary = metrics.generate_summary();
+        assert_eq!(summary.total_requests, 2);
+        assert_eq!(summary.successful_requests, 1);
+        assert_eq!(summary.skipped_requests, 1);
+        assert_eq!(summary.hook_requests, 4);
+        assert_eq!(summary.failed_hook_requests, 1);
+    }
+
+    #[tokio::test]
+    async fn test_scenario_chain_timeout() {
+        let router = axum::Router::new()
+            .route("/fast", axum::routing::get(|| async { "ok" }))
+            .route(
+                "/slow",
+                axum::routing::get(|| async {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    "late"
+                }),
+            );
+        let target = serve(router).await;
+
+        let config: Config = format!(
+            r#"
+target: {}
+scenario_chain_timeout_secs: 1
+scenarios:
+  - {{ name: fast, method: GET, url: /fast }}
+  - {{ name: slow, method: GET, url: /slow, after_each: [{{ name: cleanup, method: GET, url: /fast }}] }}
+  - {{ name: after, method: GET, url: /fast }}
+output: {{ json: out.json, html: out.html }}
+"#,
+            target
+        )
+        .parse()
+        .unwrap();
+
+        let metrics = Arc::new(MetricsCollector::new());
+        let executor = Executor::new(config, Arc::clone(&metrics)).unwrap();
+        let start = Instant::now();
+        executor.execute_scenarios().await;
+        assert!(start.elapsed() < Duration::from_secs(3));
+
+        let errors: Vec<_> = metrics
+            .get_results()
+            .into_iter()
+            .map(|r| (r.scenario_name.unwrap(), r.error))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                ("fast".to_string(), None),
+                ("slow".to_string(), Some(RequestError::ChainTimeout)),
+                ("after".to_string(), Some(RequestError::ChainTimeout)),
+            ]
+        );
+        let summary = metrics.generate_summary();
+        assert_eq!(summary.chain_timeouts, 1);
+        // after_each hooks run outside the chain timeout
+        assert_eq!(summary.hook_requests, 1);
+        assert_eq!(summary.failed_hook_requests, 0);
+    }
+
+    #[tokio::test]
+    async fn test_redirect_not_followed() {
+        let router = axum::Router::new()
+            .route(
+                "/old",
+                axum::routing::get(|| async { axum::response::Redirect::temporary("/new") }),
+            )
+            .route(
+                "/new",
+                axum::routing::get(|| async {
+                    tokio::time::sleep(Duration::from_millis(300)).await;
+                    "moved"
+                }),
+            );
+        let target = serve(router).await;
+
+        let config: Config = format!(
+            r#"
+target: {}
+follow_redirects: false
+scenarios:
+  - {{ name: old, method: GET, url: /old, extract: {{ location: {{ header: Location }} }} }}
+output: {{ json: out.json, html: out.html }}
+"#,
+            target
+        )
+        .parse()
+        .unwrap();
+
+        let executor = Executor::new(config, Arc::new(MetricsCollector::new())).unwrap();
+        let steps = executor.dry_run().await;
+
+        assert_eq!(steps[0].result.status_code, 307);
+        assert!(!steps[0].result.is_failure());
+        assert!(steps[0].result.latency_ms < 300);
+        assert_eq!(
+            steps[0].extracted,
+            vec![("location".to_string(), "/new".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_global_variables_in_simple_mode() {
+        let router = axum::Router::new().route(
+            "/:tenant/health",
+            axum::routing::get(
+                |axum::extract::Path(tenant): axum::extract::Path<String>,
+                 headers: axum::http::HeaderMap| async move {
+                    if tenant == "acme" && headers["x-tenant"] == "acme" {
+                        axum::http::StatusCode::OK
+                    } else {
+                        axum::http::StatusCode::NOT_FOUND
+                    }
+                },
+            ),
+        );
+        let target = serve(router).await;
+
+        let config: Config = format!(
+            r#"
+target: "{}/{{{{ tenant }}}}/health"
+headers: {{ x-tenant: "{{{{ tenant }}}}" }}
+variables: {{ tenant: acme }}
+output: {{ json: out.json, html: out.html }}
+"#,
+            target
+        )
+        .parse()
+        .unwrap();
+
+        let metrics = Arc::new(MetricsCollector::new());
+        let executor = Executor::new(config, Arc::clone(&metrics)).unwrap();
+        executor.execute_simple_request().await;
+
+        assert_eq!(metrics.get_results()[0].status_code, 200);
+    }
+
+    #[tokio::test]
+    async fn test_on_scenario_failure_policies() {
+        let router = axum::Router::new()
+            .route("/ok", axum::routing::get(|| async { "ok" }))
+            .route(
+                "/fail",
+                axum::routing::get(|| async { axum::http::StatusCode::INTERNAL_SERVER_ERROR }),
+            );
+        let target = serve(router).await;
+
+        // (policy, critical, results recorded, skipped)
+        for (policy, critical, recorded, skipped) in [
+            ("continue", false, 3, 0),
+            ("abort", false, 1, 0),
+            ("skip-remaining", false, 3, 2),
+            ("continue", true, 1, 0),
+        ] {
+            let config: Config = format!(
+                r#"
+target: {}
+on_scenario_failure: {}
+scenarios:
+  - {{ name: login, method: GET, url: /fail, critical: {} }}
+  - {{ name: profile, method: GET, url: /ok }}
+  - {{ name: logout, method: GET, url: /ok }}
+output: {{ json: out.json, html: out.html }}
+"#,
+                target, policy, critical
+            )
+            .parse()
+            .unwrap();
+
+            let metrics = Arc::new(MetricsCollector::new());
+            let executor = Executor::new(config, Arc::clone(&metrics)).unwrap();
+            executor.execute_scenarios().await;
+
+            let summary = metrics.generate_summary();
+            assert_eq!(summary.total_requests, recorded, "{}", policy);
+            assert_eq!(summary.skipped_requests, skipped, "{}", policy);
+            assert_eq!(
+                summary.aborted_chains,
+                usize::from(recorded != 3 || skipped > 0)
+            );
+        }
+
+        let err =
+            "target: http://localhost\non_scenario_failure: stop\noutput: { json: a, html: b }\n"
+                .parse::<Config>()
+                .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("'on_scenario_failure' must be one of"));
+    }
+
+    #[tokio::test]
+    async fn test_control_resizes_pool() {
+        use std::sync::atomic::AtomicUsize;
+
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let router = axum::Router::new().route(
+            "/",
+            axum::routing::get({
+                let (in_flight, peak) = (Arc::clone(&in_flight), Arc::clone(&peak));
+                move || async move {
+                    let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    sleep(Duration::from_millis(50)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    "ok"
+                }
+            }),
+        );
+        let target = serve(router).await;
+
+        let config: Config = format!(
+            "target: {}\nconcurrency: 1\noutput: {{ json: out.json, html: out.html }}\n",
+            target
+        )
+        .parse()
+        .unwrap();
+
+        let (control, receiver) = watch::channel(ControlMessage::SetConcurrency(1));
+        let metrics = Arc::new(MetricsCollector::new());
+        let executor = Executor::new(config, Arc::clone(&metrics))
+            .unwrap()
+            .with_control(receiver);
+
+        let resize = tokio::spawn({
+            let peak = Arc::clone(&peak);
+            async move {
+                sleep(Duration::from_millis(300)).await;
+                let peak_before = peak.load(Ordering::SeqCst);
+                control.send(ControlMessage::SetConcurrency(3)).unwrap();
+                (peak_before, control)
+            }
+        });
+        executor.run(1).await.unwrap();
+
+        let (peak_before, _control) = resize.await.unwrap();
+        assert_eq!(peak_before, 1);
+        assert_eq!(peak.load(Ordering::SeqCst), 3);
+        assert_eq!(executor.peak_concurrency(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_workers_adopt_reloaded_config() {
+        let versions = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&versions);
+        let router = axum::Router::new().route(
+            "/",
+            axum::routing::get(move |headers: axum::http::HeaderMap| async move {
+                let version = headers["x-version"].to_str().unwrap().to_string();
+                seen.lock().unwrap().push(version);
+                sleep(Duration::from_millis(20)).await;
+                "ok"
+            }),
+        );
+        let target = serve(router).await;
+        let yaml = move |version: u32| {
+            format!(
+                "target: {}\nconcurrency: 2\nheaders: {{ X-Version: '{}' }}\noutput: {{ json: a, html: b }}\n",
+                target, version
+            )
+        };
+        let config: Config = yaml(1).parse().unwrap();
+
+        let (reload, receiver) = watch::channel(config.clone());
+        let executor = Executor::new(config, Arc::new(MetricsCollector::new()))
+            .unwrap()
+            .with_reload(receiver);
+
+        let reloader = tokio::spawn(async move {
+            sleep(Duration::from_millis(300)).await;
+            reload.send(yaml(2).parse().unwrap()).unwrap();
+            reload
+        });
+        executor.run(1).await.unwrap();
+        let _reload = reloader.await.unwrap();
+
+        let versions = versions.lock().unwrap();
+        assert_eq!(versions.first().map(String::as_str), Some("1"));
+        assert_eq!(versions.last().map(String::as_str), Some("2"));
+        let switched = versions.iter().position(|v| v == "2").unwrap();
+        assert!(versions[switched..].len() > versions.len() / 4);
+    }
 }
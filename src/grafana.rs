@@ -0,0 +1,210 @@
+use crate::metrics::{MetricsSummary, RequestResult};
+use crate::reporter::Report;
+use chrono::{DateTime, Duration, Utc};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+/// One series in the legacy `{target, datapoints}` format Grafana accepts
+/// as panel snapshot data
+struct Series {
+    target: String,
+    /// `[value, unix_ms]` pairs
+    datapoints: Vec<(f64, i64)>,
+}
+
+/// Body for Grafana's `POST /api/snapshots` with the run's time series
+/// inlined as panel snapshot data. Grafana only renders `snapshotData` in
+/// snapshots, so the file is not meant for Dashboard → Import.
+pub fn snapshot(report: &Report) -> Value {
+    let seconds = per_second(&report.results);
+    let series = |target: &str, value: fn(&MetricsSummary) -> f64| Series {
+        target: target.to_string(),
+        datapoints: seconds
+            .iter()
+            .map(|(time, summary)| (value(summary), time.timestamp_millis()))
+            .collect(),
+    };
+
+    let mut status_counts: BTreeMap<u16, usize> = BTreeMap::new();
+    for result in &report.results {
+        *status_counts.entry(result.status_code).or_default() += 1;
+    }
+    let end_ms = report.summary.end_time.timestamp_millis();
+    let status_series = status_counts
+        .into_iter()
+        .map(|(status, count)| Series {
+            target: status.to_string(),
+            datapoints: vec![(count as f64, end_ms)],
+        })
+        .collect();
+
+    let panels = vec![
+        panel(
+            1,
+            "Requests per second",
+            "timeseries",
+            "reqps",
+            (0, 0),
+            vec![series("rps", |s| s.throughput_rps)],
+        ),
+        panel(
+            2,
+            "Latency percentiles",
+            "timeseries",
+            "ms",
+            (12, 0),
+            vec![
+                series("p50", |s| s.p50_latency_ms as f64),
+                series("p95", |s| s.p95_latency_ms as f64),
+                series("p99", |s| s.p99_latency_ms as f64),
+            ],
+        ),
+        panel(
+            3,
+            "Error rate",
+            "timeseries",
+            "percent",
+            (0, 8),
+            vec![series("error_rate", |s| s.error_rate)],
+        ),
+        panel(
+            4,
+            "Status codes",
+            "piechart",
+            "short",
+            (12, 8),
+            status_series,
+        ),
+    ];
+
+    let title = format!(
+        "Flux run {}",
+        report.summary.start_time.format("%Y-%m-%d %H:%M:%S")
+    );
+    json!({
+        "name": title,
+        // Never expire
+        "expires": 0,
+        "dashboard": {
+            "title": title,
+            "uid": null,
+            "schemaVersion": 36,
+            "editable": false,
+            "tags": ["flux", "load-test"],
+            "time": {
+                "from": report.summary.start_time.to_rfc3339(),
+                "to": report.summary.end_time.to_rfc3339(),
+            },
+            "timepicker": {},
+            "panels": panels,
+        },
+    })
+}
+
+/// Summary of the requests started in each whole second of the run
+fn per_second(results: &[RequestResult]) -> BTreeMap<DateTime<Utc>, MetricsSummary> {
+    let mut seconds: BTreeMap<i64, Vec<RequestResult>> = BTreeMap::new();
+    for result in results {
+        seconds
+            .entry(result.request_start_timestamp.timestamp())
+            .or_default()
+            .push(result.clone());
+    }
+
+    seconds
+        .into_iter()
+        .filter_map(|(second, results)| {
+            let start = DateTime::from_timestamp(second, 0)?;
+            let summary =
+                MetricsSummary::from_results(&results, start, start + Duration::seconds(1));
+            Some((start, summary))
+        })
+        .collect()
+}
+
+/// Panel at grid position `(x, y)` carrying its series as snapshot data
+fn panel(
+    id: u32,
+    title: &str,
+    kind: &str,
+    unit: &str,
+    (x, y): (u32, u32),
+    series: Vec<Series>,
+) -> Value {
+    let targets: Vec<Value> = series
+        .iter()
+        .enumerate()
+        .map(|(i, s)| {
+            json!({
+                "refId": ((b'A' + i as u8) as char).to_string(),
+                "target": s.target,
+            })
+        })
+        .collect();
+    let snapshot_data: Vec<Value> = series
+        .iter()
+        .map(|s| json!({ "target": s.target, "datapoints": s.datapoints }))
+        .collect();
+
+    json!({
+        "id": id,
+        "title": title,
+        "type": kind,
+        "gridPos": { "x": x, "y": y, "w": 12, "h": 8 },
+        "fieldConfig": { "defaults": { "unit": unit }, "overrides": [] },
+        "targets": targets,
+        "snapshotData": snapshot_data,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dashboard_panels() {
+        let start = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let result = |offset_ms: i64, latency_ms: u64, status_code: u16| RequestResult {
+            scenario_name: None,
+            latency_ms,
+            status_code,
+            error: None,
+            request_start_timestamp: start + Duration::milliseconds(offset_ms),
+            request_end_timestamp: start + Duration::milliseconds(offset_ms),
+            sse_total_ms: None,
+            wall_latency_ms: None,
+            timing: None,
+            request_id: None,
+            content_encoding: None,
+            is_hook: false,
+        };
+        let results = vec![
+            result(0, 10, 200),
+            result(500, 30, 200),
+            result(1200, 50, 503),
+        ];
+        let report = Report {
+            summary: MetricsSummary::from_results(&results, start, start + Duration::seconds(2)),
+            results,
+            phase_summaries: Vec::new(),
+            request_summaries: Vec::new(),
+        };
+
+        let snapshot = snapshot(&report);
+        assert_eq!(snapshot["expires"], 0);
+        let panels = snapshot["dashboard"]["panels"].as_array().unwrap();
+        assert_eq!(panels.len(), 4);
+
+        let rps = &panels[0]["snapshotData"][0]["datapoints"];
+        assert_eq!(rps[0], json!([2.0, 1_700_000_000_000i64]));
+        assert_eq!(rps[1], json!([1.0, 1_700_000_001_000i64]));
+
+        let latency = panels[1]["snapshotData"].as_array().unwrap();
+        assert_eq!(latency.len(), 3);
+        assert_eq!(panels[1]["targets"][2]["refId"], "C");
+
+        let statuses = &panels[3]["snapshotData"];
+        assert_eq!(statuses[0]["target"], "200");
+        assert_eq!(statuses[1]["datapoints"][0][0], 1.0);
+    }
+}
@@ -0,0 +1,255 @@
+use crate::config::GrpcConfig;
+use anyhow::{Context, Result};
+use prost::Message;
+use prost_reflect::{DynamicMessage, MessageDescriptor};
+use std::path::Path;
+use tonic::codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder};
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::transport::Channel;
+use tonic::Status;
+
+/// Client for a unary gRPC method described by a `.proto` file at runtime
+#[derive(Clone)]
+pub struct GrpcClient {
+    grpc: tonic::client::Grpc<Channel>,
+    path: PathAndQuery,
+    request: DynamicMessage,
+    response: MessageDescriptor,
+}
+
+impl GrpcClient {
+    /// Compile the proto file, resolve the method and parse the request JSON
+    pub fn from_config(config: &GrpcConfig) -> Result<Self> {
+        let proto_file = Path::new(&config.proto_file);
+        let include_dir = proto_file.parent().unwrap_or_else(|| Path::new("."));
+        let file_name = proto_file.file_name().unwrap_or_default();
+
+        let pool = protox::Compiler::new([include_dir])?
+            .include_imports(true)
+            .open_file(file_name)
+            .with_context(|| format!("Failed to compile '{}'", config.proto_file))?
+            .descriptor_pool();
+
+        let service = pool
+            .get_service_by_name(&config.service)
+            .ok_or_else(|| anyhow::anyhow!("Service '{}' not found", config.service))?;
+        let method = service
+            .methods()
+            .find(|m| m.name() == config.method)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Method '{}' not found in service '{}'",
+                    config.method,
+                    config.service
+                )
+            })?;
+
+        if method.is_client_streaming() || method.is_server_streaming() {
+            anyhow::bail!("Only unary gRPC methods are supported");
+        }
+
+        let mut deserializer = serde_json::Deserializer::from_str(&config.request_json);
+        let request = DynamicMessage::deserialize(method.input(), &mut deserializer)
+            .context("Invalid 'request_json'")?;
+        deserializer.end().context("Invalid 'request_json'")?;
+
+        let path = format!("/{}/{}", service.full_name(), method.name());
+        let channel = Channel::from_shared(config.endpoint.clone())?.connect_lazy();
+
+        Ok(Self {
+            grpc: tonic::client::Grpc::new(channel),
+            path: PathAndQuery::from_maybe_shared(path)?,
+            request,
+            response: method.output(),
+        })
+    }
+
+    /// Make one call, returning the decoded response message
+    pub async fn call(&self) -> Result<DynamicMessage, Status> {
+        let mut grpc = self.grpc.clone();
+        grpc.ready()
+            .await
+            .map_err(|e| Status::unavailable(e.to_string()))?;
+
+        let response = grpc
+            .unary(
+                tonic::Request::new(self.request.clone()),
+                self.path.clone(),
+                DynamicCodec::new(self.response.clone()),
+            )
+            .await?;
+
+        Ok(response.into_inner())
+    }
+}
+
+/// Protobuf codec for messages only known at runtime
+#[derive(Clone)]
+pub struct DynamicCodec {
+    decode: MessageDescriptor,
+}
+
+impl DynamicCodec {
+    /// Codec decoding incoming messages as `decode`
+    pub fn new(decode: MessageDescriptor) -> Self {
+        Self { decode }
+    }
+}
+
+impl Codec for DynamicCodec {
+    type Encode = DynamicMessage;
+    type Decode = DynamicMessage;
+    type Encoder = DynamicCodec;
+    type Decoder = DynamicCodec;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        self.clone()
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        self.clone()
+    }
+}
+
+impl Encoder for DynamicCodec {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut EncodeBuf<'_>) -> Result<(), Self::Error> {
+        item.encode(dst)
+            .map_err(|e| Status::internal(e.to_string()))
+    }
+}
+
+impl Decoder for DynamicCodec {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
+        DynamicMessage::decode(self.decode.clone(), src)
+            .map(Some)
+            .map_err(|e| Status::internal(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost_reflect::ReflectMessage;
+    use std::convert::Infallible;
+    use std::future::Ready;
+    use std::task::{Context as TaskContext, Poll};
+    use tonic::body::Body;
+    use tonic::codegen::{http, BoxFuture, Service};
+    use tonic::server::{NamedService, UnaryService};
+
+    const PROTO: &str = r#"
+syntax = "proto3";
+package echo;
+
+message EchoRequest { string message = 1; }
+message EchoReply { string message = 1; }
+
+service Echo {
+  rpc Say (EchoRequest) returns (EchoReply);
+}
+"#;
+
+    /// Echo service built on the same dynamic codec
+    #[derive(Clone)]
+    struct EchoServer {
+        request: MessageDescriptor,
+        reply: MessageDescriptor,
+    }
+
+    impl NamedService for EchoServer {
+        const NAME: &'static str = "echo.Echo";
+    }
+
+    impl UnaryService<DynamicMessage> for EchoServer {
+        type Response = DynamicMessage;
+        type Future = Ready<Result<tonic::Response<DynamicMessage>, Status>>;
+
+        fn call(&mut self, request: tonic::Request<DynamicMessage>) -> Self::Future {
+            let request = request.into_inner();
+            let mut reply = DynamicMessage::new(self.reply.clone());
+            reply.set_field_by_name(
+                "message",
+                request.get_field_by_name("message").unwrap().into_owned(),
+            );
+            std::future::ready(Ok(tonic::Response::new(reply)))
+        }
+    }
+
+    impl Service<http::Request<Body>> for EchoServer {
+        type Response = http::Response<Body>;
+        type Error = Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+
+        fn poll_ready(&mut self, _cx: &mut TaskContext<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, req: http::Request<Body>) -> Self::Future {
+            let service = self.clone();
+            Box::pin(async move {
+                let mut grpc = tonic::server::Grpc::new(DynamicCodec::new(service.request.clone()));
+                Ok(grpc.unary(service, req).await)
+            })
+        }
+    }
+
+    fn grpc_config(dir: &Path, endpoint: &str, method: &str) -> GrpcConfig {
+        let proto_file = dir.join("echo.proto");
+        std::fs::write(&proto_file, PROTO).unwrap();
+
+        GrpcConfig {
+            proto_file: proto_file.to_string_lossy().into_owned(),
+            service: "echo.Echo".to_string(),
+            method: method.to_string(),
+            request_json: r#"{"message": "hello"}"#.to_string(),
+            endpoint: endpoint.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unknown_method_rejected() {
+        let dir = std::env::temp_dir().join("flux_grpc_unknown_method");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let err = GrpcClient::from_config(&grpc_config(&dir, "http://127.0.0.1:1", "Shout"))
+            .err()
+            .unwrap();
+
+        assert!(err.to_string().contains("Method 'Shout' not found"));
+    }
+
+    #[tokio::test]
+    async fn test_unary_call() {
+        let dir = std::env::temp_dir().join("flux_grpc_unary_call");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let config = grpc_config(&dir, &endpoint, "Say");
+
+        let client = GrpcClient::from_config(&config).unwrap();
+        let server = EchoServer {
+            request: client.request.descriptor(),
+            reply: client.response.clone(),
+        };
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(server)
+                .serve_with_incoming(futures::stream::unfold(listener, |listener| async {
+                    let stream = listener.accept().await.map(|(stream, _)| stream);
+                    Some((stream, listener))
+                })),
+        );
+
+        let reply = client.call().await.unwrap();
+        let message = reply.get_field_by_name("message").unwrap();
+
+        assert_eq!(message.as_str(), Some("hello"));
+    }
+}
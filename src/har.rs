@@ -0,0 +1,324 @@
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, CONTENT_TYPE};
+use reqwest::{Request, Response};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+/// Most entries kept in memory, whatever the sample rate
+pub const MAX_HAR_ENTRIES: usize = 1000;
+
+/// HAR 1.2 document
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Har {
+    pub log: HarLog,
+}
+
+impl Har {
+    /// Wrap captured entries in a HAR log created by flux
+    pub fn new(entries: Vec<HarEntry>) -> Self {
+        Self {
+            log: HarLog {
+                version: "1.2".to_string(),
+                creator: HarCreator {
+                    name: "flux".to_string(),
+                    version: env!("CARGO_PKG_VERSION").to_string(),
+                },
+                entries,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HarLog {
+    pub version: String,
+    pub creator: HarCreator,
+    pub entries: Vec<HarEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HarCreator {
+    pub name: String,
+    pub version: String,
+}
+
+/// One captured request/response pair
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarEntry {
+    pub started_date_time: DateTime<Utc>,
+    /// Latency in milliseconds, as recorded in the metrics
+    pub time: u64,
+    pub request: HarRequest,
+    pub response: HarResponse,
+    pub cache: serde_json::Map<String, serde_json::Value>,
+    pub timings: HarTimings,
+}
+
+impl HarEntry {
+    /// Entry for a request that received `response` after `time` ms
+    pub fn new(
+        started_date_time: DateTime<Utc>,
+        time: u64,
+        request: HarRequest,
+        response: HarResponse,
+    ) -> Self {
+        Self {
+            started_date_time,
+            time,
+            request,
+            response,
+            cache: serde_json::Map::new(),
+            // Only the total is measured, so it is all attributed to waiting
+            timings: HarTimings {
+                send: 0,
+                wait: time,
+                receive: 0,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarRequest {
+    pub method: String,
+    pub url: String,
+    pub http_version: String,
+    pub cookies: Vec<HarNameValue>,
+    pub headers: Vec<HarNameValue>,
+    pub query_string: Vec<HarNameValue>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_data: Option<HarPostData>,
+    pub headers_size: i64,
+    pub body_size: i64,
+}
+
+impl HarRequest {
+    /// Capture a request before it is sent. Streamed bodies (multipart
+    /// uploads) are recorded without text.
+    pub fn from_request(request: &Request) -> Self {
+        let body = request.body().map(|body| body.as_bytes());
+        let post_data = body.map(|bytes| HarPostData {
+            mime_type: content_type(request.headers()),
+            text: bytes
+                .map(|b| String::from_utf8_lossy(b).into_owned())
+                .unwrap_or_default(),
+        });
+
+        Self {
+            method: request.method().to_string(),
+            url: request.url().to_string(),
+            http_version: format!("{:?}", request.version()),
+            cookies: Vec::new(),
+            headers: header_list(request.headers()),
+            query_string: request
+                .url()
+                .query_pairs()
+                .map(|(name, value)| HarNameValue {
+                    name: name.into_owned(),
+                    value: value.into_owned(),
+                })
+                .collect(),
+            post_data,
+            headers_size: -1,
+            body_size: body.flatten().map_or(-1, |b| b.len() as i64),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarResponse {
+    pub status: u16,
+    pub status_text: String,
+    pub http_version: String,
+    pub cookies: Vec<HarNameValue>,
+    pub headers: Vec<HarNameValue>,
+    pub content: HarContent,
+    #[serde(rename = "redirectURL")]
+    pub redirect_url: String,
+    pub headers_size: i64,
+    pub body_size: i64,
+}
+
+impl HarResponse {
+    /// Capture a response's status line and headers; the body is read
+    /// separately and added with `with_body`
+    pub fn from_response(response: &Response) -> Self {
+        let status = response.status();
+        let headers = response.headers();
+
+        Self {
+            status: status.as_u16(),
+            status_text: status.canonical_reason().unwrap_or_default().to_string(),
+            http_version: format!("{:?}", response.version()),
+            cookies: Vec::new(),
+            headers: header_list(headers),
+            content: HarContent {
+                size: 0,
+                mime_type: content_type(headers),
+                text: String::new(),
+            },
+            redirect_url: headers
+                .get(reqwest::header::LOCATION)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or_default()
+                .to_string(),
+            headers_size: -1,
+            body_size: -1,
+        }
+    }
+
+    /// Attach the (decompressed) response body
+    pub fn with_body(mut self, body: &str) -> Self {
+        self.content.size = body.len() as i64;
+        self.content.text = body.to_string();
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HarNameValue {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarPostData {
+    pub mime_type: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarContent {
+    pub size: i64,
+    pub mime_type: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HarTimings {
+    pub send: u64,
+    pub wait: u64,
+    pub receive: u64,
+}
+
+/// Collects a bounded sample of request/response pairs during a run
+#[derive(Debug)]
+pub struct HarRecorder {
+    sample_rate: f64,
+    entries: Mutex<Vec<HarEntry>>,
+}
+
+impl HarRecorder {
+    /// Recorder capturing `sample_rate` (0.0-1.0) of requests
+    pub fn new(sample_rate: f64) -> Self {
+        Self {
+            sample_rate,
+            entries: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Whether a request drawing `roll` (0.0-1.0) should be captured
+    pub fn should_capture(&self, roll: f64) -> bool {
+        roll < self.sample_rate && self.entries.lock().unwrap().len() < MAX_HAR_ENTRIES
+    }
+
+    /// Keep an entry unless the cap has been reached
+    pub fn record(&self, entry: HarEntry) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() < MAX_HAR_ENTRIES {
+            entries.push(entry);
+        }
+    }
+
+    /// Entries captured so far, in completion order
+    pub fn entries(&self) -> Vec<HarEntry> {
+        self.entries.lock().unwrap().clone()
+    }
+}
+
+/// Headers as HAR name/value pairs, in the order they were set
+fn header_list(headers: &HeaderMap) -> Vec<HarNameValue> {
+    headers
+        .iter()
+        .map(|(name, value)| HarNameValue {
+            name: name.to_string(),
+            value: String::from_utf8_lossy(value.as_bytes()).into_owned(),
+        })
+        .collect()
+}
+
+fn content_type(headers: &HeaderMap) -> String {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_capture() {
+        let request = reqwest::Client::new()
+            .post("http://example.com/users?page=2")
+            .header(CONTENT_TYPE, "application/json")
+            .body(r#"{"name": "a"}"#)
+            .build()
+            .unwrap();
+
+        let captured = HarRequest::from_request(&request);
+        let json = serde_json::to_value(&captured).unwrap();
+
+        assert_eq!(json["method"], "POST");
+        assert_eq!(json["queryString"][0]["name"], "page");
+        assert_eq!(json["postData"]["mimeType"], "application/json");
+        assert_eq!(json["postData"]["text"], r#"{"name": "a"}"#);
+        assert_eq!(json["bodySize"], 13);
+    }
+
+    #[test]
+    fn test_recorder_caps_entries() {
+        let recorder = HarRecorder::new(0.5);
+        assert!(recorder.should_capture(0.1));
+        assert!(!recorder.should_capture(0.9));
+
+        let request = reqwest::Client::new()
+            .get("http://example.com/")
+            .build()
+            .unwrap();
+        let response = HarResponse {
+            status: 200,
+            status_text: "OK".to_string(),
+            http_version: "HTTP/1.1".to_string(),
+            cookies: Vec::new(),
+            headers: Vec::new(),
+            content: HarContent {
+                size: 0,
+                mime_type: String::new(),
+                text: String::new(),
+            },
+            redirect_url: String::new(),
+            headers_size: -1,
+            body_size: -1,
+        };
+
+        for _ in 0..MAX_HAR_ENTRIES + 5 {
+            recorder.record(HarEntry::new(
+                Utc::now(),
+                10,
+                HarRequest::from_request(&request),
+                response.clone(),
+            ));
+        }
+
+        assert_eq!(recorder.entries().len(), MAX_HAR_ENTRIES);
+        assert!(!recorder.should_capture(0.0));
+    }
+}
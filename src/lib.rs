@@ -0,0 +1,21 @@
+pub mod aggregate;
+pub mod append_vec;
+pub mod baseline;
+pub mod cli;
+pub mod client;
+pub mod config;
+pub mod dashboard;
+pub mod distributed;
+pub mod dns;
+pub mod executor;
+pub mod grafana;
+pub mod grpc;
+pub mod har;
+pub mod metrics;
+pub mod notify;
+pub mod reporter;
+pub mod sse;
+pub mod timing;
+pub mod ui;
+pub mod unix;
+pub mod websocket;
@@ -1,40 +1,66 @@
-mod client;
-mod config;
-mod executor;
-mod metrics;
-mod reporter;
-mod ui;
-
 use anyhow::Result;
-use config::Config;
-use executor::Executor;
-use metrics::MetricsCollector;
-use reporter::Reporter;
-use signal_hook::consts::SIGTERM;
+use clap::Parser;
+use flux::aggregate::AggregatedReport;
+use flux::baseline;
+use flux::cli::{Cli, Command};
+use flux::config::Config;
+use flux::dashboard;
+use flux::distributed;
+use flux::executor::{ControlMessage, Executor};
+use flux::har::HarRecorder;
+use flux::metrics::MetricsCollector;
+use flux::notify;
+use flux::reporter::{self, Report, Reporter, RequestLog, StreamingJsonWriter};
+use flux::ui::TerminalUI;
+use signal_hook::consts::{SIGHUP, SIGTERM, SIGUSR1};
 use signal_hook_tokio::Signals;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::time::{interval, Duration};
-use tracing::{error, info};
-use ui::TerminalUI;
+use tracing::{error, info, warn};
+
+/// How often the rolling P99 is checked against `alert_p99_ms`
+const P99_ALERT_INTERVAL_SECS: u64 = 5;
+
+/// File read on SIGUSR1 for the new worker count
+const CONCURRENCY_FILE: &str = "/tmp/flux.concurrency";
 
 /// Main entry point
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize tracing
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::from_default_env()
-                .add_directive(tracing::Level::INFO.into()),
-        )
-        .init();
+    let cli = Cli::parse();
+
+    // Initialize tracing; logs move to stderr when stdout carries events
+    let subscriber = tracing_subscriber::fmt().with_env_filter(
+        tracing_subscriber::EnvFilter::from_default_env()
+            .add_directive(tracing::Level::INFO.into()),
+    );
+    if cli.events {
+        subscriber.with_writer(std::io::stderr).init();
+    } else {
+        subscriber.init();
+    }
+
+    if let Some(command) = cli.command {
+        return run_command(command).await;
+    }
 
     info!("Starting Flux load testing tool");
 
-    // Load configuration
-    let config_path = PathBuf::from("/app/config.yaml");
-    let config = match Config::from_file(&config_path) {
+    // Load configuration; only a config file can be reloaded on SIGHUP
+    let config_path = cli.config.exists().then(|| cli.config.clone());
+    let loaded = if config_path.is_some() {
+        Config::from_file(&cli.config)
+    } else {
+        info!(
+            "{} not found, reading configuration from FLUX_* environment variables",
+            cli.config.display()
+        );
+        Config::from_env()
+    };
+    let mut config = match loaded {
         Ok(cfg) => cfg,
         Err(e) => {
             eprintln!("Failed to load configuration: {}", e);
@@ -42,8 +68,63 @@ async fn main() -> Result<()> {
         }
     };
 
+    // Apply report path overrides from the command line
+    if let Some(ref dir) = cli.output_dir {
+        if let Err(e) = config.output.use_output_dir(dir) {
+            eprintln!("Failed to create output directory {}: {}", dir.display(), e);
+            std::process::exit(1);
+        }
+    }
+    if let Some(ref path) = cli.output_json {
+        config.output.json = path.clone();
+    }
+    if let Some(ref path) = cli.output_html {
+        config.output.html = path.clone();
+    }
+
+    // One timestamp and run id shared by every output file of this run
+    let run_timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+    let run_id = format!("{:08x}", rand::random::<u32>());
+    config.output.expand_placeholders(&run_timestamp, &run_id);
+
+    // Always run with a known seed so the run can be replayed
+    let seed = *config.seed.get_or_insert_with(rand::random);
+    info!("Random seed: {}", seed);
+
+    if cli.dry_run {
+        let metrics = Arc::new(MetricsCollector::new());
+        let executor = match Executor::new(config.clone(), metrics) {
+            Ok(exec) => exec,
+            Err(e) => {
+                eprintln!("Failed to create executor: {}", e);
+                std::process::exit(1);
+            }
+        };
+
+        let steps = executor.dry_run().await;
+        let ui = TerminalUI::new(0, cli.quiet, 0).with_latency_precision(config.latency_precision);
+        ui.display_dry_run(&steps);
+
+        if steps.iter().any(|step| step.result.is_failure()) {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Load baseline report up front so a bad path fails before the test runs
+    let baseline_report = match cli.baseline {
+        Some(ref path) => match Report::from_file(path) {
+            Ok(report) => Some(report),
+            Err(e) => {
+                eprintln!("Failed to load baseline report: {}", e);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
     // Parse duration
-    let duration_secs = match config.parse_duration() {
+    let duration_secs = match config.total_duration_secs() {
         Ok(d) => d,
         Err(e) => {
             eprintln!("Failed to parse duration: {}", e);
@@ -52,46 +133,172 @@ async fn main() -> Result<()> {
     };
 
     // Create metrics collector
-    let metrics = Arc::new(MetricsCollector::new());
+    let metrics = Arc::new(
+        MetricsCollector::with_histogram_bounds(config.histogram_bounds())
+            .with_apdex_threshold(config.apdex_threshold_ms)
+            .with_sla_latency(config.sla_latency_ms),
+    );
 
     // Create terminal UI
-    let ui = TerminalUI::new(duration_secs);
+    let ui = TerminalUI::new(duration_secs, cli.quiet, config.max_concurrency())
+        .with_latency_precision(config.latency_precision)
+        .hidden(cli.events);
     ui.display_banner(&config, duration_secs);
 
     // Setup graceful shutdown
     let shutdown_flag = Arc::new(AtomicBool::new(false));
     let shutdown_flag_clone = Arc::clone(&shutdown_flag);
+    let (control_tx, control_rx) =
+        tokio::sync::watch::channel(ControlMessage::SetConcurrency(config.concurrency));
+    let (reload_tx, reload_rx) = tokio::sync::watch::channel(config.clone());
 
     tokio::spawn(async move {
         use futures::stream::StreamExt;
-        let mut signals = Signals::new([SIGTERM]).expect("Failed to create signal handler");
-        if let Some(signal) = signals.next().await {
+        let mut signals =
+            Signals::new([SIGTERM, SIGUSR1, SIGHUP]).expect("Failed to create signal handler");
+        while let Some(signal) = signals.next().await {
+            if signal == SIGHUP {
+                let Some(ref config_path) = config_path else {
+                    warn!("Ignoring SIGHUP: the configuration came from FLUX_* environment variables, which can't change while the test runs; start from a config file to reload it");
+                    continue;
+                };
+                let current = reload_tx.borrow().clone();
+                match reload_config(config_path, &current) {
+                    Ok(reloaded) => {
+                        info!("Reloaded configuration from {}", config_path.display());
+                        if reloaded.concurrency != current.concurrency {
+                            let size = reloaded.concurrency;
+                            let _ = control_tx.send(ControlMessage::SetConcurrency(size));
+                        }
+                        let _ = reload_tx.send(reloaded);
+                    }
+                    Err(e) => warn!("Ignoring SIGHUP, keeping the current config: {}", e),
+                }
+                continue;
+            }
+            if signal == SIGUSR1 {
+                match read_concurrency(Path::new(CONCURRENCY_FILE)) {
+                    Ok(size) => {
+                        let _ = control_tx.send(ControlMessage::SetConcurrency(size));
+                    }
+                    Err(e) => warn!("Ignoring SIGUSR1: {}", e),
+                }
+                continue;
+            }
+
             info!("Received signal: {:?}", signal);
             shutdown_flag_clone.store(true, Ordering::SeqCst);
+            break;
         }
     });
 
     // Create executor
-    let executor = match Executor::new(config.clone(), Arc::clone(&metrics)) {
+    let mut executor = match Executor::new(config.clone(), Arc::clone(&metrics)) {
         Ok(exec) => exec,
         Err(e) => {
             ui.display_error(&format!("Failed to create executor: {}", e));
             std::process::exit(1);
         }
     };
+    executor = executor.with_control(control_rx).with_reload(reload_rx);
+
+    // Open the per-request log
+    let request_log = match config.output.request_log {
+        Some(ref path) => match RequestLog::create(path) {
+            Ok(log) => Some(Arc::new(log)),
+            Err(e) => {
+                ui.display_error(&format!("Failed to create request log: {}", e));
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    if let Some(ref log) = request_log {
+        executor = executor.with_request_log(Arc::clone(log));
+    }
+
+    // Open the streaming JSON output and the `--events` stream on stdout
+    let mut json_streams = Vec::new();
+    if let Some(ref path) = config.output.json_stream {
+        match StreamingJsonWriter::create(path) {
+            Ok(stream) => json_streams.push(Arc::new(stream)),
+            Err(e) => {
+                ui.display_error(&format!("Failed to create JSON stream: {}", e));
+                std::process::exit(1);
+            }
+        }
+    }
+    if cli.events {
+        match StreamingJsonWriter::stdout() {
+            Ok(stream) => json_streams.push(Arc::new(stream)),
+            Err(e) => {
+                ui.display_error(&format!("Failed to start event stream: {}", e));
+                std::process::exit(1);
+            }
+        }
+    }
+
+    for stream in &json_streams {
+        executor = executor.with_json_stream(Arc::clone(stream));
+    }
+
+    // Capture sampled request/response pairs for the HAR file
+    let har = config
+        .output
+        .har
+        .as_ref()
+        .map(|_| Arc::new(HarRecorder::new(config.capture_sample_rate.unwrap_or(1.0))));
+
+    if let Some(ref har) = har {
+        executor = executor.with_har(Arc::clone(har));
+    }
+
+    // Serve the live dashboard until the test ends
+    let dashboard = config.dashboard_port.map(|port| {
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let handle = tokio::spawn(dashboard::serve(port, Arc::clone(&metrics), async {
+            let _ = stopped.await;
+        }));
+        (stop, handle)
+    });
 
     // Start live metrics update task
     let metrics_clone = Arc::clone(&metrics);
+    let alert_p99_ms = config.alert_p99_ms;
+    let ui_refresh_ms = config.ui_refresh_ms;
+    let events = cli.events;
     let ui_handle = tokio::spawn(async move {
-        let mut ticker = interval(Duration::from_secs(1));
-        let mut elapsed = 0u64;
+        // A zero interval disables live updates entirely
+        if ui_refresh_ms == 0 || events {
+            ui.hide_progress();
+            return;
+        }
+
+        let mut ticker = interval(Duration::from_millis(ui_refresh_ms));
+        let started = Instant::now();
+        let mut last_alert_check = 0u64;
 
         loop {
             ticker.tick().await;
-            elapsed += 1;
+            let elapsed = started.elapsed().as_secs();
 
             let live_metrics = metrics_clone.get_live_metrics();
             ui.update_progress(elapsed, &live_metrics);
+            ui.update_workers(&metrics_clone.worker_stats(), live_metrics.p95_latency_ms);
+
+            // Check the rolling P99 every 5 seconds
+            if let Some(threshold_ms) = alert_p99_ms {
+                if elapsed >= last_alert_check + P99_ALERT_INTERVAL_SECS {
+                    last_alert_check = elapsed;
+                    match metrics_clone.rolling_p99() {
+                        Some(p99_ms) if p99_ms > threshold_ms => {
+                            ui.show_p99_alert(p99_ms, threshold_ms)
+                        }
+                        _ => ui.clear_p99_alert(),
+                    }
+                }
+            }
 
             if elapsed >= duration_secs {
                 break;
@@ -111,31 +318,270 @@ async fn main() -> Result<()> {
     // Wait for UI updates to complete
     let _ = ui_handle.await;
 
+    if let Some((stop, handle)) = dashboard {
+        let _ = stop.send(());
+        match handle.await {
+            Ok(Err(e)) => error!("Dashboard server failed: {}", e),
+            Err(e) => error!("Dashboard server task panicked: {}", e),
+            Ok(Ok(())) => {}
+        }
+    }
+
     // Generate summary
     info!("Generating summary");
-    let summary = metrics.generate_summary();
+    let mut summary = metrics.generate_summary();
     let results = metrics.get_results();
 
+    summary.peak_concurrency = Some(executor.peak_concurrency());
+    summary.dns_cache_hits = executor.dns_cache_hits();
+    if let Some(stats) = executor.open_model_stats() {
+        summary.max_outstanding = Some(stats.max_outstanding);
+        summary.dropped_iterations = Some(stats.dropped_iterations);
+    }
+
+    if let Some(ref log) = request_log {
+        if let Err(e) = log.flush() {
+            error!("Failed to flush request log: {}", e);
+        }
+
+        let sample_rate = config.sample_rate.unwrap_or(1.0);
+        if sample_rate < 1.0 {
+            summary.sampled_requests = Some(log.written());
+            summary.sample_rate = Some(sample_rate);
+        }
+    }
+
+    for stream in &json_streams {
+        if let Err(e) = stream.finish(&summary) {
+            error!("Failed to finish JSON stream: {}", e);
+        }
+    }
+
     // Display summary in terminal
-    let ui = TerminalUI::new(duration_secs);
+    let ui = TerminalUI::new(duration_secs, cli.quiet, 0)
+        .with_latency_precision(config.latency_precision)
+        .hidden(cli.events);
     ui.display_summary(&summary);
 
+    let phase_summaries = executor.phase_summaries();
+    ui.display_phase_summaries(&phase_summaries);
+    let request_summaries = executor.request_summaries();
+    ui.display_request_summaries(&request_summaries);
+
+    // Compare against baseline report
+    let mut regressed = false;
+    if let Some(ref baseline_report) = baseline_report {
+        let deltas = baseline::compare(&baseline_report.summary, &summary, cli.baseline_tolerance);
+        ui.display_baseline_comparison(&deltas, cli.baseline_tolerance);
+        regressed = deltas.iter().any(|d| d.regressed);
+    }
+
     // Generate reports
     info!("Generating reports");
-    let reporter = Reporter::new(summary, results);
+    let mut reporter = Reporter::new(summary, results)
+        .with_phase_summaries(phase_summaries)
+        .with_request_summaries(request_summaries);
+    if let Some(ref har) = har {
+        reporter = reporter.with_har_entries(har.entries());
+    }
 
-    if let Err(e) = reporter.generate_json(&config.output.json) {
-        error!("Failed to generate JSON report: {}", e);
-    } else {
-        ui.display_success(&format!("JSON report saved to: {}", config.output.json));
+    for (format, path) in reporter::configured_formats(&config.output) {
+        if let Err(e) = format.write(reporter.report(), path) {
+            error!("Failed to generate {} report: {}", format.name(), e);
+        } else {
+            ui.display_success(&format!("{} report saved to: {}", format.name(), path));
+        }
     }
 
-    if let Err(e) = reporter.generate_html(&config.output.html) {
-        error!("Failed to generate HTML report: {}", e);
-    } else {
-        ui.display_success(&format!("HTML report saved to: {}", config.output.html));
+    if let Some(ref path) = config.output.har {
+        if let Err(e) = reporter.generate_har(path) {
+            error!("Failed to generate HAR file: {}", e);
+        } else {
+            ui.display_success(&format!("HAR file saved to: {}", path));
+        }
+    }
+
+    if let Some(ref gateway) = config.output.prometheus_push_gateway {
+        let job = config
+            .output
+            .prometheus_job
+            .as_deref()
+            .unwrap_or(reporter::DEFAULT_PROMETHEUS_JOB);
+        if let Err(e) = reporter.push_to_prometheus(gateway, job).await {
+            error!("Failed to push metrics to Prometheus: {}", e);
+        } else {
+            ui.display_success(&format!("Metrics pushed to Prometheus: {}", gateway));
+        }
+    }
+
+    if let Some(ref notify_config) = config.notify {
+        if let Err(e) =
+            notify::send_notification(notify_config, reporter.summary(), !regressed).await
+        {
+            warn!("Failed to send completion notification: {}", e);
+        }
+    }
+
+    if regressed {
+        ui.display_error("Performance regressed beyond the baseline tolerance");
+        std::process::exit(1);
     }
 
     info!("Flux load test completed successfully");
     Ok(())
 }
+
+/// Run a report tooling or distributed run subcommand
+async fn run_command(command: Command) -> Result<()> {
+    match command {
+        Command::Coordinate {
+            config,
+            workers,
+            token,
+        } => {
+            let config = Config::from_file(&config).map_err(|e| {
+                anyhow::anyhow!("Failed to load configuration {}: {}", config.display(), e)
+            })?;
+            let ui = TerminalUI::new(0, false, 0).with_latency_precision(config.latency_precision);
+            info!(
+                "Running on {} agents: {}",
+                workers.len(),
+                workers.join(", ")
+            );
+
+            let reporter =
+                Reporter::from_report(distributed::coordinate(&config, &workers, &token).await?);
+            ui.display_summary(reporter.summary());
+
+            for (format, path) in reporter::configured_formats(&config.output) {
+                format.write(reporter.report(), path)?;
+                ui.display_success(&format!("{} report saved to: {}", format.name(), path));
+            }
+
+            Ok(())
+        }
+        Command::Agent { listen, token } => distributed::serve_agent(listen, token).await,
+        Command::Merge {
+            reports,
+            output,
+            html,
+        } => {
+            let reports = load_reports(&reports)?;
+
+            let report_count = reports.len();
+            let reporter = Reporter::from_report(Report::merge(reports)?);
+            let ui = TerminalUI::new(0, false, 0);
+
+            reporter.generate_json(&output, false)?;
+            ui.display_success(&format!("Merged {} reports into: {}", report_count, output));
+
+            if let Some(ref html_path) = html {
+                reporter.generate_html(html_path)?;
+                ui.display_success(&format!("HTML report saved to: {}", html_path));
+            }
+
+            Ok(())
+        }
+        Command::Aggregate {
+            reports,
+            output,
+            html,
+        } => {
+            let reports = load_reports(&reports)?;
+
+            let aggregated = AggregatedReport::from_reports(&reports)?;
+            let ui = TerminalUI::new(0, false, 0);
+
+            aggregated.generate_json(&output)?;
+            ui.display_success(&format!(
+                "Aggregated {} reports into: {}",
+                aggregated.run_count, output
+            ));
+
+            if let Some(ref html_path) = html {
+                reporter::generate_aggregate_html(&aggregated, html_path)?;
+                ui.display_success(&format!("HTML report saved to: {}", html_path));
+            }
+
+            Ok(())
+        }
+        Command::Compare {
+            baseline,
+            current,
+            assert_max_regression_pct,
+            output,
+        } => {
+            let baseline_report = load_report(&baseline)?;
+            let current_report = load_report(&current)?;
+
+            let tolerance_pct = assert_max_regression_pct.unwrap_or(f64::INFINITY);
+            let deltas = baseline::compare_all(
+                &baseline_report.summary,
+                &current_report.summary,
+                tolerance_pct,
+            );
+
+            let baseline_name = baseline.display().to_string();
+            let current_name = current.display().to_string();
+            let ui = TerminalUI::new(0, false, 0);
+            ui.display_report_comparison(&deltas, &baseline_name, &current_name);
+
+            if let Some(ref html_path) = output {
+                reporter::generate_comparison_html(
+                    &deltas,
+                    &baseline_name,
+                    &current_name,
+                    html_path,
+                )?;
+                ui.display_success(&format!("Comparison report saved to: {}", html_path));
+            }
+
+            if deltas.iter().any(|d| d.regressed) {
+                ui.display_error(&format!(
+                    "A metric regressed by more than {}%",
+                    tolerance_pct
+                ));
+                std::process::exit(1);
+            }
+
+            Ok(())
+        }
+    }
+}
+
+/// Load a JSON report, naming the file if it fails to load
+fn load_report(path: &Path) -> Result<Report> {
+    Report::from_file(path)
+        .map_err(|e| anyhow::anyhow!("Failed to load report {}: {}", path.display(), e))
+}
+
+/// Load several JSON reports
+fn load_reports(paths: &[PathBuf]) -> Result<Vec<Report>> {
+    paths.iter().map(|path| load_report(path)).collect()
+}
+
+/// Re-read the config file for a running test, rejecting changes it
+/// cannot adopt. The report paths and seed keep the values the run started
+/// with, including the command line overrides and expanded placeholders.
+fn reload_config(path: &Path, current: &Config) -> Result<Config> {
+    let mut reloaded = Config::from_file(&path.to_path_buf())
+        .map_err(|e| anyhow::anyhow!("Failed to load {}: {}", path.display(), e))?;
+    current.check_reload(&reloaded)?;
+    reloaded.output = current.output.clone();
+    reloaded.seed = current.seed;
+    Ok(reloaded)
+}
+
+/// Read a positive worker count from `path`
+fn read_concurrency(path: &Path) -> Result<usize> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+    match text.trim().parse::<usize>() {
+        Ok(size) if size > 0 => Ok(size),
+        _ => anyhow::bail!(
+            "{} must contain a positive worker count, got '{}'",
+            path.display(),
+            text.trim()
+        ),
+    }
+}
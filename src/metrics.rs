@@ -1,7 +1,92 @@
+use crate::append_vec::AppendVec;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
 use chrono::{DateTime, Utc};
+use crossbeam_channel::{Receiver, Sender};
+use hdrhistogram::serialization::{Deserializer, Serializer, V2DeflateSerializer};
 use hdrhistogram::Histogram;
 use serde::{Deserialize, Serialize};
-use std::sync::{Arc, Mutex};
+use std::borrow::Borrow;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Phase of a request that timed out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeoutKind {
+    Connect,
+    Request,
+}
+
+/// Classified request failure
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "detail")]
+pub enum RequestError {
+    ConnectionRefused,
+    Timeout {
+        kind: TimeoutKind,
+    },
+    TlsError(String),
+    DnsError(String),
+    HttpError {
+        status: u16,
+    },
+    AssertionFailed(String),
+    GraphQLError(String),
+    /// Step not sent because its `skip_if` held; not a failure
+    Skipped,
+    /// Step cancelled or never sent because `scenario_chain_timeout_secs`
+    /// expired
+    ChainTimeout,
+    /// 429 response with `respect_rate_limit` set; not a failure
+    RateLimited,
+    Other(String),
+}
+
+impl RequestError {
+    /// Variant name used as the key in the error breakdown
+    pub fn kind_name(&self) -> &'static str {
+        match self {
+            RequestError::ConnectionRefused => "ConnectionRefused",
+            RequestError::Timeout { .. } => "Timeout",
+            RequestError::TlsError(_) => "TlsError",
+            RequestError::DnsError(_) => "DnsError",
+            RequestError::HttpError { .. } => "HttpError",
+            RequestError::AssertionFailed(_) => "AssertionFailed",
+            RequestError::GraphQLError(_) => "GraphQLError",
+            RequestError::Skipped => "Skipped",
+            RequestError::ChainTimeout => "ChainTimeout",
+            RequestError::RateLimited => "RateLimited",
+            RequestError::Other(_) => "Other",
+        }
+    }
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestError::ConnectionRefused => write!(f, "connection refused"),
+            RequestError::Timeout { kind } => match kind {
+                TimeoutKind::Connect => write!(f, "connect timeout"),
+                TimeoutKind::Request => write!(f, "request timeout"),
+            },
+            RequestError::TlsError(msg) => write!(f, "TLS error: {}", msg),
+            RequestError::DnsError(msg) => write!(f, "DNS error: {}", msg),
+            RequestError::HttpError { status } => write!(f, "HTTP status {}", status),
+            RequestError::AssertionFailed(msg) => write!(f, "assertion failed: {}", msg),
+            RequestError::GraphQLError(msg) => write!(f, "GraphQL error: {}", msg),
+            RequestError::Skipped => write!(f, "skipped"),
+            RequestError::ChainTimeout => write!(f, "chain timeout"),
+            RequestError::RateLimited => write!(f, "rate limited (HTTP 429)"),
+            RequestError::Other(msg) => write!(f, "{}", msg),
+        }
+    }
+}
 
 /// Single request result
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -9,17 +94,259 @@ pub struct RequestResult {
     pub scenario_name: Option<String>,
     pub latency_ms: u64,
     pub status_code: u16,
-    pub error: Option<String>,
+    pub error: Option<RequestError>,
     pub request_start_timestamp: DateTime<Utc>,
     pub request_end_timestamp: DateTime<Utc>,
+    /// Time to the last event of an SSE step (`latency_ms` is time to first)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sse_total_ms: Option<u64>,
+    /// Measured round trip when `latency_ms` was read from the step's
+    /// `latency_from_header`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wall_latency_ms: Option<u64>,
+    /// Per-phase timings, when the protocol reports them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timing: Option<TimingBreakdown>,
+    /// Value of the `trace_header` sent with the request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    /// `Content-Encoding` of a response left compressed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_encoding: Option<String>,
+    /// Sent by a `before_each` or `after_each` hook; kept out of the load
+    /// test statistics
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_hook: bool,
+}
+
+impl RequestResult {
+    /// Whether the step was skipped rather than sent
+    pub fn is_skipped(&self) -> bool {
+        self.error == Some(RequestError::Skipped)
+    }
+
+    /// Whether the result counts as failed: skipped and rate-limited
+    /// results carry an error but are not failures
+    pub fn is_failure(&self) -> bool {
+        !matches!(
+            self.error,
+            None | Some(RequestError::Skipped) | Some(RequestError::RateLimited)
+        )
+    }
+}
+
+/// Time spent in each phase of a request
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TimingBreakdown {
+    /// DNS lookup of a new connection (`detailed_timing`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dns_ms: Option<u64>,
+    /// Connection establishment (including the WebSocket handshake)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect_ms: Option<u64>,
+    /// TLS handshake of a new connection (`detailed_timing`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_ms: Option<u64>,
+    /// From sending the request to the response headers (`detailed_timing`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttfb_ms: Option<u64>,
+    /// From sending the message to receiving the reply
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_ms: Option<u64>,
+    /// Whether the request opened a connection rather than reusing a
+    /// pooled one (`detailed_timing`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new_connection: Option<bool>,
 }
 
+impl TimingBreakdown {
+    /// Phases in request order, as named in `timing_percentiles`
+    pub const PHASES: [&'static str; 5] = ["dns", "connect", "tls", "ttfb", "reply"];
+
+    /// Time of each of `PHASES`, if measured
+    pub fn phases(&self) -> [Option<u64>; 5] {
+        [
+            self.dns_ms,
+            self.connect_ms,
+            self.tls_ms,
+            self.ttfb_ms,
+            self.reply_ms,
+        ]
+    }
+}
+
+/// Latency percentiles of one timing phase across a run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PhasePercentiles {
+    pub phase: String,
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+    pub p99_ms: u64,
+}
+
+/// Maximum number of results the drain task stores per lock acquisition
+const DRAIN_BATCH_SIZE: usize = 1024;
+
+/// Longest a reader waits for the drain thread to store pending results
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
 /// Metrics collector for aggregating results
+///
+/// Workers post results to an unbounded channel; a background thread drains
+/// it into the results vector and histogram so recording never contends on
+/// the storage locks.
 #[derive(Debug)]
 pub struct MetricsCollector {
-    results: Arc<Mutex<Vec<RequestResult>>>,
-    histogram: Arc<Mutex<Histogram<u64>>>,
+    sender: Sender<RequestResult>,
+    total_requests: Arc<AtomicU64>,
+    error_count: Arc<AtomicU64>,
+    total_latency_ms: Arc<AtomicU64>,
+    aborted_chains: Arc<AtomicU64>,
+    chain_timeouts: AtomicU64,
+    throttled_requests: AtomicU64,
+    hook_requests: AtomicU64,
+    failed_hook_requests: AtomicU64,
+    storage: Arc<Storage>,
+    drain_thread: JoinHandle<()>,
+    rolling_histogram: Mutex<Histogram<u64>>,
+    apdex_threshold_ms: Option<u64>,
+    sla_latency_ms: Option<u64>,
     start_time: DateTime<Utc>,
+    /// Live counters at the start of the current phase
+    phase_start: Mutex<PhaseStart>,
+    /// Live counters of each worker, by worker id; the write lock is only
+    /// taken to grow it
+    workers: RwLock<Vec<WorkerCounters>>,
+}
+
+/// Atomic live counters of one worker, updated without locking
+#[derive(Debug, Default)]
+struct WorkerCounters {
+    last_latency_ms: AtomicU64,
+    total_requests: AtomicU64,
+    error_count: AtomicU64,
+}
+
+/// Live counters of one worker, shown as a row below the progress bar
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct WorkerStats {
+    pub worker_id: usize,
+    /// Latency of the worker's most recent request
+    pub last_latency_ms: u64,
+    pub total_requests: u64,
+    pub error_count: u64,
+}
+
+impl WorkerStats {
+    /// Share of the worker's requests that failed, in percent
+    pub fn error_rate(&self) -> f64 {
+        if self.total_requests == 0 {
+            return 0.0;
+        }
+        self.error_count as f64 / self.total_requests as f64 * 100.0
+    }
+}
+
+/// Start time and live counter values of the current phase, subtracted
+/// from the counters so live metrics cover the phase alone
+#[derive(Debug, Clone, Copy)]
+struct PhaseStart {
+    time: DateTime<Utc>,
+    total_requests: u64,
+    error_count: u64,
+    total_latency_ms: u64,
+}
+
+/// Share of requests (percent) allowed over the SLA latency before it is
+/// highlighted as violated
+pub const SLA_ACCEPTABLE_PERCENT: f64 = 1.0;
+
+/// Number of most recent latencies kept for the rolling P99
+const ROLLING_WINDOW_SIZE: usize = 1000;
+
+/// Most distinct error messages kept in `sample_errors`
+const SAMPLE_ERROR_LIMIT: usize = 10;
+
+/// Storage written by the drain thread
+#[derive(Debug)]
+struct Storage {
+    /// Results of every phase, read without blocking the drain thread
+    results: AppendVec<RequestResult>,
+    histogram: Mutex<Histogram<u64>>,
+    /// Latencies of completed phases
+    phase_histogram: Mutex<Histogram<u64>>,
+    bounds: HistogramBounds,
+    /// Latencies capped at the `bounds` maximum before being recorded
+    clamped_values: AtomicUsize,
+    recent_latencies: Mutex<VecDeque<u64>>,
+    stored: AtomicUsize,
+}
+
+/// Range and precision of the latency histograms
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HistogramBounds {
+    pub min_ms: u64,
+    pub max_ms: u64,
+    pub significant_figures: u8,
+}
+
+impl Default for HistogramBounds {
+    fn default() -> Self {
+        Self {
+            min_ms: 1,
+            max_ms: 60_000,
+            significant_figures: 3,
+        }
+    }
+}
+
+impl HistogramBounds {
+    /// Empty histogram with these bounds
+    fn histogram(&self) -> Histogram<u64> {
+        Histogram::new_with_bounds(self.min_ms, self.max_ms, self.significant_figures)
+            .expect("Histogram bounds are validated with the config")
+    }
+
+    /// `latency_ms` capped at `max_ms`, and whether it had to be. Values
+    /// under `min_ms`, such as 0 ms responses, are recorded as is in the
+    /// lowest bucket.
+    fn clamp(&self, latency_ms: u64) -> (u64, bool) {
+        let clamped = latency_ms.min(self.max_ms);
+        (clamped, clamped != latency_ms)
+    }
+}
+
+impl Storage {
+    /// Store a batch of drained results
+    fn store_batch(&self, batch: Vec<RequestResult>) {
+        let count = batch.len();
+
+        if let Ok(mut hist) = self.histogram.lock() {
+            for result in batch.iter().filter(|r| !r.is_skipped()) {
+                let (latency_ms, clamped) = self.bounds.clamp(result.latency_ms);
+                if clamped {
+                    self.clamped_values.fetch_add(1, Ordering::Relaxed);
+                }
+                let _ = hist.record(latency_ms);
+            }
+        }
+
+        if let Ok(mut recent) = self.recent_latencies.lock() {
+            let sent: Vec<&RequestResult> = batch.iter().filter(|r| !r.is_skipped()).collect();
+            for result in sent.iter().rev().take(ROLLING_WINDOW_SIZE).rev() {
+                if recent.len() == ROLLING_WINDOW_SIZE {
+                    recent.pop_front();
+                }
+                recent.push_back(result.latency_ms);
+            }
+        }
+
+        for result in batch {
+            self.results.push(result);
+        }
+
+        self.stored.fetch_add(count, Ordering::AcqRel);
+    }
 }
 
 /// Summary statistics
@@ -33,6 +360,11 @@ pub struct MetricsSummary {
     pub min_latency_ms: u64,
     pub max_latency_ms: u64,
     pub mean_latency_ms: f64,
+    /// Exact median of the raw latencies (histogram estimate when merged)
+    #[serde(default)]
+    pub median_latency_ms: f64,
+    #[serde(default)]
+    pub stddev_latency_ms: f64,
     pub p50_latency_ms: u64,
     pub p90_latency_ms: u64,
     pub p95_latency_ms: u64,
@@ -40,13 +372,96 @@ pub struct MetricsSummary {
     pub error_rate: f64,
     pub start_time: DateTime<Utc>,
     pub end_time: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sampled_requests: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample_rate: Option<f64>,
+    #[serde(default)]
+    pub error_breakdown: HashMap<String, usize>,
+    /// Requests that failed with a connect or request timeout
+    #[serde(default)]
+    pub timeout_count: usize,
+    /// Steps skipped by `skip_if`; counted in `total_requests` but neither
+    /// as successes nor failures
+    #[serde(default)]
+    pub skipped_requests: usize,
+    /// Recorded 429 responses while `respect_rate_limit` is set; counted
+    /// apart from failures. A subset of `throttled_requests`.
+    #[serde(default)]
+    pub rate_limited_count: usize,
+    /// Scenario chain iterations stopped early by `on_scenario_failure` or
+    /// a `critical` step
+    #[serde(default)]
+    pub aborted_chains: usize,
+    /// Scenario chain iterations cut short by `scenario_chain_timeout_secs`
+    #[serde(default)]
+    pub chain_timeouts: usize,
+    /// Every 429 response, each counted once: those recorded as requests
+    /// and those a step waited out and resent (`respect_retry_after`),
+    /// which are not counted as requests. Hook requests are left out.
+    #[serde(default)]
+    pub throttled_requests: usize,
+    /// Connection lookups answered from the DNS cache
+    /// (`dns_cache_ttl_secs`)
+    #[serde(default)]
+    pub dns_cache_hits: usize,
+    /// Latencies above the histogram's upper bound, recorded at it; raise
+    /// `histogram_max_ms` if non-zero
+    #[serde(default)]
+    pub clamped_histogram_values: usize,
+    /// Requests sent by `before_each` and `after_each` hooks, which are
+    /// not counted anywhere else in the summary
+    #[serde(default)]
+    pub hook_requests: usize,
+    #[serde(default)]
+    pub failed_hook_requests: usize,
+    /// Whether any response arrived compressed (visible only when it was
+    /// not decoded, see `accept_encoding`)
+    #[serde(default)]
+    pub gzip_responses: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub apdex: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub over_sla_count: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub over_sla_percent: Option<f64>,
+    /// Base64-encoded HDR histogram (V2 deflate format) of all latencies
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_histogram: Option<String>,
+    /// Peak in-flight iterations (open workload model only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_outstanding: Option<usize>,
+    /// Launches skipped because `max_outstanding` was reached
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dropped_iterations: Option<usize>,
+    /// Most requests in flight at once during the run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peak_concurrency: Option<usize>,
+    /// Percentiles of each measured timing phase; not kept when summaries
+    /// are merged
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub timing_percentiles: Vec<PhasePercentiles>,
+    /// Share of requests (percent) sent on a pooled connection,
+    /// `(requests - new connections) / requests`, with `detailed_timing`;
+    /// not kept when summaries are merged
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connection_reuse_rate: Option<f64>,
+    /// When the first and last failed requests completed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_error_time: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error_time: Option<DateTime<Utc>>,
+    /// Up to ten distinct failure messages, in order of first occurrence
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sample_errors: Vec<String>,
 }
 
-/// Live metrics for terminal display
-#[derive(Debug, Clone)]
+/// Live metrics for terminal display and the web dashboard
+#[derive(Debug, Clone, Serialize)]
 pub struct LiveMetrics {
     pub current_rps: f64,
     pub avg_latency_ms: f64,
+    pub p95_latency_ms: u64,
     pub error_count: usize,
     pub total_requests: usize,
 }
@@ -54,52 +469,250 @@ pub struct LiveMetrics {
 impl MetricsCollector {
     /// Create a new metrics collector
     pub fn new() -> Self {
+        Self::with_histogram_bounds(HistogramBounds::default())
+    }
+
+    /// Create a metrics collector whose latency histograms have `bounds`
+    pub fn with_histogram_bounds(bounds: HistogramBounds) -> Self {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let start_time = Utc::now();
+        let storage = Arc::new(Storage {
+            results: AppendVec::new(),
+            histogram: Mutex::new(bounds.histogram()),
+            phase_histogram: Mutex::new(bounds.histogram()),
+            bounds,
+            clamped_values: AtomicUsize::new(0),
+            recent_latencies: Mutex::new(VecDeque::with_capacity(ROLLING_WINDOW_SIZE)),
+            stored: AtomicUsize::new(0),
+        });
         Self {
-            results: Arc::new(Mutex::new(Vec::new())),
-            histogram: Arc::new(Mutex::new(
-                Histogram::<u64>::new_with_bounds(1, 60_000, 3).unwrap(),
-            )),
-            start_time: Utc::now(),
+            sender,
+            total_requests: Arc::new(AtomicU64::new(0)),
+            error_count: Arc::new(AtomicU64::new(0)),
+            total_latency_ms: Arc::new(AtomicU64::new(0)),
+            aborted_chains: Arc::new(AtomicU64::new(0)),
+            chain_timeouts: AtomicU64::new(0),
+            throttled_requests: AtomicU64::new(0),
+            hook_requests: AtomicU64::new(0),
+            failed_hook_requests: AtomicU64::new(0),
+            drain_thread: Self::spawn_drain_task(Arc::clone(&storage), receiver),
+            storage,
+            rolling_histogram: Mutex::new(bounds.histogram()),
+            apdex_threshold_ms: None,
+            sla_latency_ms: None,
+            start_time,
+            phase_start: Mutex::new(PhaseStart {
+                time: start_time,
+                total_requests: 0,
+                error_count: 0,
+                total_latency_ms: 0,
+            }),
+            workers: RwLock::new(Vec::new()),
         }
     }
 
-    /// Record a request result
+    /// Bounds of this collector's latency histograms
+    pub fn histogram_bounds(&self) -> HistogramBounds {
+        self.storage.bounds
+    }
+
+    /// Compute an Apdex score with threshold `threshold_ms` in the summary
+    pub fn with_apdex_threshold(mut self, threshold_ms: Option<u64>) -> Self {
+        self.apdex_threshold_ms = threshold_ms;
+        self
+    }
+
+    /// Count requests slower than `sla_latency_ms` in the summary
+    pub fn with_sla_latency(mut self, sla_latency_ms: Option<u64>) -> Self {
+        self.sla_latency_ms = sla_latency_ms;
+        self
+    }
+
+    /// Spawn the background thread that drains the channel into storage.
+    /// It exits once the collector (and with it the last sender) is dropped.
+    fn spawn_drain_task(
+        storage: Arc<Storage>,
+        receiver: Receiver<RequestResult>,
+    ) -> JoinHandle<()> {
+        thread::Builder::new()
+            .name("flux-metrics".to_string())
+            .spawn(move || {
+                while let Ok(first) = receiver.recv() {
+                    let mut batch = Vec::with_capacity(DRAIN_BATCH_SIZE);
+                    batch.push(first);
+                    batch.extend(receiver.try_iter().take(DRAIN_BATCH_SIZE - 1));
+
+                    storage.store_batch(batch);
+                }
+            })
+            .expect("Failed to spawn metrics drain thread")
+    }
+
+    /// Record a request result. Hook results are only counted.
     pub fn record(&self, result: RequestResult) {
-        let latency = result.latency_ms;
+        if result.is_hook {
+            self.hook_requests.fetch_add(1, Ordering::Relaxed);
+            if result.is_failure() {
+                self.failed_hook_requests.fetch_add(1, Ordering::Relaxed);
+            }
+            return;
+        }
+        if result.status_code == 429 {
+            self.throttled_requests.fetch_add(1, Ordering::Relaxed);
+        }
 
-        // Store result
-        if let Ok(mut results) = self.results.lock() {
-            results.push(result);
+        // Live counters are updated before the result is queued so the drain
+        // target below always covers it
+        self.total_latency_ms
+            .fetch_add(result.latency_ms, Ordering::Relaxed);
+        if result.is_failure() {
+            self.error_count.fetch_add(1, Ordering::Relaxed);
         }
+        self.total_requests.fetch_add(1, Ordering::AcqRel);
 
-        // Update histogram
-        if let Ok(mut hist) = self.histogram.lock() {
-            let _ = hist.record(latency);
+        let _ = self.sender.send(result);
+    }
+
+    /// Make room for the live stats of `count` workers, so recording them
+    /// never has to grow the table
+    pub fn reserve_workers(&self, count: usize) {
+        let mut workers = self.workers.write().unwrap();
+        if workers.len() < count {
+            workers.resize_with(count, WorkerCounters::default);
         }
     }
 
-    /// Get current live metrics
+    /// Count a result towards the live stats of the worker that sent it
+    pub fn record_worker(&self, worker_id: usize, result: &RequestResult) {
+        if result.is_hook {
+            return;
+        }
+
+        if self.workers.read().unwrap().len() <= worker_id {
+            self.reserve_workers(worker_id + 1);
+        }
+        let workers = self.workers.read().unwrap();
+        let counters = &workers[worker_id];
+        counters
+            .last_latency_ms
+            .store(result.latency_ms, Ordering::Relaxed);
+        counters.total_requests.fetch_add(1, Ordering::Relaxed);
+        if result.is_failure() {
+            counters.error_count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Live stats of every worker that has sent a request
+    pub fn worker_stats(&self) -> Vec<WorkerStats> {
+        self.workers
+            .read()
+            .unwrap()
+            .iter()
+            .enumerate()
+            .map(|(worker_id, counters)| WorkerStats {
+                worker_id,
+                last_latency_ms: counters.last_latency_ms.load(Ordering::Relaxed),
+                total_requests: counters.total_requests.load(Ordering::Relaxed),
+                error_count: counters.error_count.load(Ordering::Relaxed),
+            })
+            .filter(|stats| stats.total_requests > 0)
+            .collect()
+    }
+
+    /// Count a scenario chain iteration stopped after a failed step
+    pub fn record_aborted_chain(&self) {
+        self.aborted_chains.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a scenario chain iteration cut short by its timeout
+    pub fn record_chain_timeout(&self) {
+        self.chain_timeouts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a 429 response waited out and resent instead of recorded;
+    /// recorded ones are counted by `record`
+    pub fn record_throttled(&self) {
+        self.throttled_requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Move every result recorded so far into storage before reading it.
+    /// Gives up with a warning if the drain thread has stopped or does not
+    /// catch up within `DRAIN_TIMEOUT`, leaving the missing results out.
+    fn drain(&self) {
+        let target = self.total_requests.load(Ordering::Acquire) as usize;
+        let deadline = Instant::now() + DRAIN_TIMEOUT;
+
+        // Only the drain thread stores results, so batches land in the order
+        // they were recorded and the rolling window stays ordered
+        loop {
+            let stored = self.storage.stored.load(Ordering::Acquire);
+            if stored >= target {
+                return;
+            }
+            if self.drain_thread.is_finished() || Instant::now() >= deadline {
+                warn!(
+                    "Metrics drain thread did not store {} of {} results; reporting without them",
+                    target - stored,
+                    target
+                );
+                return;
+            }
+            thread::yield_now();
+        }
+    }
+
+    /// Start a new phase: checkpoint and clear the histogram and restart
+    /// the live metrics from now. The final summary and results still
+    /// cover every phase.
+    pub fn reset_histogram(&self) -> anyhow::Result<()> {
+        let total_requests = self.total_requests.load(Ordering::Acquire);
+        self.drain();
+
+        let mut histogram = self.storage.histogram.lock().unwrap();
+        self.storage
+            .phase_histogram
+            .lock()
+            .unwrap()
+            .add(&*histogram)
+            .map_err(|e| anyhow::anyhow!("Failed to checkpoint histogram: {:?}", e))?;
+        histogram.reset();
+
+        *self.phase_start.lock().unwrap() = PhaseStart {
+            time: Utc::now(),
+            total_requests,
+            error_count: self.error_count.load(Ordering::Relaxed),
+            total_latency_ms: self.total_latency_ms.load(Ordering::Relaxed),
+        };
+        Ok(())
+    }
+
+    /// Get current live metrics for the current phase from the atomic
+    /// counters in O(1), plus the running P95 of the phase's stored results
     pub fn get_live_metrics(&self) -> LiveMetrics {
-        let results = self.results.lock().unwrap();
-        let total = results.len();
+        let phase_start = *self.phase_start.lock().unwrap();
+        let total =
+            (self.total_requests.load(Ordering::Relaxed) - phase_start.total_requests) as usize;
 
         if total == 0 {
             return LiveMetrics {
                 current_rps: 0.0,
                 avg_latency_ms: 0.0,
+                p95_latency_ms: 0,
                 error_count: 0,
                 total_requests: 0,
             };
         }
 
         let elapsed = Utc::now()
-            .signed_duration_since(self.start_time)
+            .signed_duration_since(phase_start.time)
             .num_milliseconds() as f64
             / 1000.0;
 
-        let error_count = results.iter().filter(|r| r.error.is_some()).count();
+        let error_count =
+            (self.error_count.load(Ordering::Relaxed) - phase_start.error_count) as usize;
 
-        let sum_latency: u64 = results.iter().map(|r| r.latency_ms).sum();
+        let sum_latency =
+            self.total_latency_ms.load(Ordering::Relaxed) - phase_start.total_latency_ms;
         let avg_latency = sum_latency as f64 / total as f64;
 
         let current_rps = if elapsed > 0.0 {
@@ -108,41 +721,265 @@ impl MetricsCollector {
             0.0
         };
 
+        // The histogram lags the counters by whatever the drain thread has
+        // not stored yet, which is fine for a progress display
+        let p95_latency = self
+            .storage
+            .histogram
+            .lock()
+            .map(|h| h.value_at_quantile(0.95))
+            .unwrap_or(0);
+
         LiveMetrics {
             current_rps,
             avg_latency_ms: avg_latency,
+            p95_latency_ms: p95_latency,
             error_count,
             total_requests: total,
         }
     }
 
+    /// P99 latency over the most recent requests, or `None` before any
+    /// request has completed
+    pub fn rolling_p99(&self) -> Option<u64> {
+        let recent = self.storage.recent_latencies.lock().unwrap();
+        if recent.is_empty() {
+            return None;
+        }
+
+        let mut histogram = self.rolling_histogram.lock().unwrap();
+        histogram.reset();
+        for &latency in recent.iter() {
+            let _ = histogram.record(self.storage.bounds.clamp(latency).0);
+        }
+
+        Some(histogram.value_at_quantile(0.99))
+    }
+
     /// Generate final summary
     pub fn generate_summary(&self) -> MetricsSummary {
-        let results = self.results.lock().unwrap();
-        let histogram = self.histogram.lock().unwrap();
+        self.drain();
+
+        let results: Vec<&RequestResult> = self.storage.results.iter().collect();
+        let mut histogram = self.storage.phase_histogram.lock().unwrap().clone();
+        let _ = histogram.add(&*self.storage.histogram.lock().unwrap());
+
+        let mut summary =
+            MetricsSummary::compute(&results, &histogram, self.start_time, Utc::now());
+        summary.apdex = self.apdex_threshold_ms.map(|t| apdex(&results, t));
+        summary.aborted_chains = self.aborted_chains.load(Ordering::Relaxed) as usize;
+        summary.chain_timeouts = self.chain_timeouts.load(Ordering::Relaxed) as usize;
+        summary.throttled_requests = self.throttled_requests.load(Ordering::Relaxed) as usize;
+        summary.hook_requests = self.hook_requests.load(Ordering::Relaxed) as usize;
+        summary.failed_hook_requests = self.failed_hook_requests.load(Ordering::Relaxed) as usize;
+        summary.clamped_histogram_values = self.storage.clamped_values.load(Ordering::Relaxed);
+
+        if let Some(sla_ms) = self.sla_latency_ms {
+            let sent: Vec<u64> = results
+                .iter()
+                .filter(|r| !r.is_skipped())
+                .map(|r| r.latency_ms)
+                .collect();
+            let over = sent
+                .iter()
+                .filter(|&&latency_ms| latency_ms > sla_ms)
+                .count();
+            summary.over_sla_count = Some(over);
+            summary.over_sla_percent = Some(if sent.is_empty() {
+                0.0
+            } else {
+                over as f64 / sent.len() as f64 * 100.0
+            });
+        }
+        summary
+    }
 
+    /// Get all results for reporting
+    pub fn get_results(&self) -> Vec<RequestResult> {
+        self.drain();
+
+        self.storage.results.iter().cloned().collect()
+    }
+}
+
+impl MetricsSummary {
+    /// Compute a summary from raw results, building the latency histogram
+    /// with the default bounds
+    pub fn from_results(
+        results: &[RequestResult],
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Self {
+        Self::from_results_with_bounds(results, HistogramBounds::default(), start_time, end_time)
+    }
+
+    /// Compute a summary from raw results, building the latency histogram
+    /// with `bounds`
+    pub fn from_results_with_bounds(
+        results: &[RequestResult],
+        bounds: HistogramBounds,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Self {
+        let mut histogram = bounds.histogram();
+        let mut clamped_values = 0;
+        for result in results.iter().filter(|r| !r.is_skipped()) {
+            let (latency_ms, clamped) = bounds.clamp(result.latency_ms);
+            clamped_values += clamped as usize;
+            let _ = histogram.record(latency_ms);
+        }
+
+        let mut summary = Self::compute(results, &histogram, start_time, end_time);
+        summary.clamped_histogram_values = clamped_values;
+        summary
+    }
+
+    /// Merge summaries from several runners by adding their serialized
+    /// histograms, giving exact aggregate percentiles without raw results
+    pub fn merge(summaries: &[MetricsSummary]) -> anyhow::Result<Self> {
+        let start_time = summaries
+            .iter()
+            .map(|s| s.start_time)
+            .min()
+            .ok_or_else(|| anyhow::anyhow!("No summaries to merge"))?;
+        let end_time = summaries
+            .iter()
+            .map(|s| s.end_time)
+            .max()
+            .unwrap_or(start_time);
+
+        // Runners may have used different bounds; grow to fit them all
+        let mut histogram = HistogramBounds::default().histogram();
+        histogram.auto(true);
+        let mut total = 0;
+        let mut successful = 0;
+        let mut error_breakdown: HashMap<String, usize> = HashMap::new();
+
+        for summary in summaries {
+            let encoded = summary.latency_histogram.as_deref().ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Summary starting at {} has no histogram",
+                    summary.start_time
+                )
+            })?;
+            histogram
+                .add(decode_histogram(encoded)?)
+                .map_err(|e| anyhow::anyhow!("Failed to merge histograms: {:?}", e))?;
+
+            total += summary.total_requests;
+            successful += summary.successful_requests;
+            for (kind, count) in &summary.error_breakdown {
+                *error_breakdown.entry(kind.clone()).or_insert(0) += count;
+            }
+        }
+
+        let mut merged = Self::build(
+            total,
+            successful,
+            error_breakdown,
+            &histogram,
+            start_time,
+            end_time,
+        );
+        merged.gzip_responses = summaries.iter().any(|s| s.gzip_responses);
+        merged.aborted_chains = summaries.iter().map(|s| s.aborted_chains).sum();
+        merged.chain_timeouts = summaries.iter().map(|s| s.chain_timeouts).sum();
+        merged.throttled_requests = summaries.iter().map(|s| s.throttled_requests).sum();
+        merged.dns_cache_hits = summaries.iter().map(|s| s.dns_cache_hits).sum();
+        merged.clamped_histogram_values =
+            summaries.iter().map(|s| s.clamped_histogram_values).sum();
+        merged.first_error_time = summaries.iter().filter_map(|s| s.first_error_time).min();
+        merged.last_error_time = summaries.iter().filter_map(|s| s.last_error_time).max();
+        merged.sample_errors = sample_errors(
+            summaries
+                .iter()
+                .flat_map(|s| s.sample_errors.iter().cloned()),
+        );
+        merged.hook_requests = summaries.iter().map(|s| s.hook_requests).sum();
+        merged.failed_hook_requests = summaries.iter().map(|s| s.failed_hook_requests).sum();
+        Ok(merged)
+    }
+
+    /// Compute a summary from raw results and their latency histogram
+    fn compute(
+        results: &[impl Borrow<RequestResult>],
+        histogram: &Histogram<u64>,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Self {
         let total = results.len();
-        let successful = results.iter().filter(|r| r.error.is_none()).count();
-        let failed = total - successful;
+        let successful = borrowed(results).filter(|r| r.error.is_none()).count();
+
+        let mut error_breakdown: HashMap<String, usize> = HashMap::new();
+        for error in borrowed(results).filter_map(|r| r.error.as_ref()) {
+            *error_breakdown
+                .entry(error.kind_name().to_string())
+                .or_insert(0) += 1;
+        }
+
+        let mut summary = Self::build(
+            total,
+            successful,
+            error_breakdown,
+            histogram,
+            start_time,
+            end_time,
+        );
+        summary.median_latency_ms = median(results);
+        summary.gzip_responses = borrowed(results).any(|r| r.content_encoding.is_some());
+        summary.timing_percentiles = timing_percentiles(results);
+        summary.connection_reuse_rate = connection_reuse_rate(results);
+
+        let failures = || borrowed(results).filter(|r| r.is_failure());
+        summary.first_error_time = failures().map(|r| r.request_end_timestamp).min();
+        summary.last_error_time = failures().map(|r| r.request_end_timestamp).max();
+        summary.sample_errors = sample_errors(failures().map(|r| {
+            let error = r
+                .error
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default();
+            match r.scenario_name {
+                Some(ref name) => format!("{}: {}", name, error),
+                None => error,
+            }
+        }));
+        summary
+    }
+
+    /// Build a summary from request counts and a latency histogram
+    fn build(
+        total: usize,
+        successful: usize,
+        error_breakdown: HashMap<String, usize>,
+        histogram: &Histogram<u64>,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Self {
+        let skipped = error_breakdown.get("Skipped").copied().unwrap_or(0);
+        let rate_limited = error_breakdown.get("RateLimited").copied().unwrap_or(0);
+        let sent = total - skipped;
+        let failed = sent - successful - rate_limited;
 
-        let end_time = Utc::now();
         let duration = end_time
-            .signed_duration_since(self.start_time)
+            .signed_duration_since(start_time)
             .num_milliseconds() as f64
             / 1000.0;
 
         let throughput = if duration > 0.0 {
-            total as f64 / duration
+            sent as f64 / duration
         } else {
             0.0
         };
 
-        let error_rate = if total > 0 {
-            (failed as f64 / total as f64) * 100.0
+        let error_rate = if sent > 0 {
+            (failed as f64 / sent as f64) * 100.0
         } else {
             0.0
         };
 
+        let timeout_count = error_breakdown.get("Timeout").copied().unwrap_or(0);
+
         let min = histogram.min();
         let max = histogram.max();
         let mean = histogram.mean();
@@ -151,7 +988,7 @@ impl MetricsCollector {
         let p95 = histogram.value_at_quantile(0.95);
         let p99 = histogram.value_at_quantile(0.99);
 
-        MetricsSummary {
+        Self {
             total_requests: total,
             successful_requests: successful,
             failed_requests: failed,
@@ -160,20 +997,161 @@ impl MetricsCollector {
             min_latency_ms: min,
             max_latency_ms: max,
             mean_latency_ms: mean,
+            median_latency_ms: p50 as f64,
+            stddev_latency_ms: histogram.stdev(),
             p50_latency_ms: p50,
             p90_latency_ms: p90,
             p95_latency_ms: p95,
             p99_latency_ms: p99,
             error_rate,
-            start_time: self.start_time,
+            start_time,
             end_time,
+            sampled_requests: None,
+            sample_rate: None,
+            error_breakdown,
+            timeout_count,
+            skipped_requests: skipped,
+            rate_limited_count: rate_limited,
+            aborted_chains: 0,
+            chain_timeouts: 0,
+            throttled_requests: 0,
+            dns_cache_hits: 0,
+            clamped_histogram_values: 0,
+            hook_requests: 0,
+            failed_hook_requests: 0,
+            gzip_responses: false,
+            apdex: None,
+            over_sla_count: None,
+            over_sla_percent: None,
+            latency_histogram: encode_histogram(histogram).ok(),
+            max_outstanding: None,
+            dropped_iterations: None,
+            peak_concurrency: None,
+            timing_percentiles: Vec::new(),
+            connection_reuse_rate: None,
+            first_error_time: None,
+            last_error_time: None,
+            sample_errors: Vec::new(),
         }
     }
+}
 
-    /// Get all results for reporting
-    pub fn get_results(&self) -> Vec<RequestResult> {
-        self.results.lock().unwrap().clone()
+/// Apdex score `(satisfied + tolerating / 2) / total` for threshold T.
+/// Satisfied is <= T, tolerating <= 4T; failed requests count as frustrated
+/// and skipped steps are left out.
+pub fn apdex(results: &[impl Borrow<RequestResult>], threshold_ms: u64) -> f64 {
+    let sent = borrowed(results).filter(|r| !r.is_skipped()).count();
+    if sent == 0 {
+        return 0.0;
     }
+
+    let (satisfied, tolerating) =
+        borrowed(results)
+            .filter(|r| r.error.is_none())
+            .fold((0usize, 0usize), |(s, t), r| {
+                if r.latency_ms <= threshold_ms {
+                    (s + 1, t)
+                } else if r.latency_ms <= threshold_ms * 4 {
+                    (s, t + 1)
+                } else {
+                    (s, t)
+                }
+            });
+
+    (satisfied as f64 + tolerating as f64 / 2.0) / sent as f64
+}
+
+/// Percentiles of every timing phase measured in at least one result
+fn timing_percentiles(results: &[impl Borrow<RequestResult>]) -> Vec<PhasePercentiles> {
+    let mut histograms: Vec<Option<Histogram<u64>>> = vec![None; TimingBreakdown::PHASES.len()];
+    for timing in borrowed(results).filter_map(|r| r.timing.as_ref()) {
+        for (histogram, value) in histograms.iter_mut().zip(timing.phases()) {
+            if let Some(value) = value {
+                let _ = histogram
+                    .get_or_insert_with(|| HistogramBounds::default().histogram())
+                    .record(value);
+            }
+        }
+    }
+
+    TimingBreakdown::PHASES
+        .iter()
+        .zip(histograms)
+        .filter_map(|(phase, histogram)| {
+            let histogram = histogram?;
+            Some(PhasePercentiles {
+                phase: phase.to_string(),
+                p50_ms: histogram.value_at_quantile(0.50),
+                p95_ms: histogram.value_at_quantile(0.95),
+                p99_ms: histogram.value_at_quantile(0.99),
+            })
+        })
+        .collect()
+}
+
+/// Percent of the requests with a known connection that reused one
+fn connection_reuse_rate(results: &[impl Borrow<RequestResult>]) -> Option<f64> {
+    let connections: Vec<bool> = borrowed(results)
+        .filter_map(|r| r.timing.as_ref()?.new_connection)
+        .collect();
+    if connections.is_empty() {
+        return None;
+    }
+
+    let new = connections.iter().filter(|&&new| new).count();
+    Some((connections.len() - new) as f64 / connections.len() as f64 * 100.0)
+}
+
+/// The first `SAMPLE_ERROR_LIMIT` distinct messages
+fn sample_errors(messages: impl Iterator<Item = String>) -> Vec<String> {
+    let mut samples: Vec<String> = Vec::new();
+    for message in messages {
+        if samples.len() == SAMPLE_ERROR_LIMIT {
+            break;
+        }
+        if !samples.contains(&message) {
+            samples.push(message);
+        }
+    }
+    samples
+}
+
+/// Iterate results that may be owned or borrowed
+fn borrowed<R: Borrow<RequestResult>>(results: &[R]) -> impl Iterator<Item = &RequestResult> {
+    results.iter().map(<R as Borrow<RequestResult>>::borrow)
+}
+
+/// True median of the recorded latencies, averaging the middle pair
+fn median(results: &[impl Borrow<RequestResult>]) -> f64 {
+    let mut latencies: Vec<u64> = borrowed(results)
+        .filter(|r| !r.is_skipped())
+        .map(|r| r.latency_ms)
+        .collect();
+    latencies.sort_unstable();
+
+    let mid = latencies.len() / 2;
+    match latencies.len() {
+        0 => 0.0,
+        n if n.is_multiple_of(2) => (latencies[mid - 1] + latencies[mid]) as f64 / 2.0,
+        _ => latencies[mid] as f64,
+    }
+}
+
+/// Serialize a histogram to base64-encoded V2 deflate format
+pub fn encode_histogram(histogram: &Histogram<u64>) -> anyhow::Result<String> {
+    let mut bytes = Vec::new();
+    V2DeflateSerializer::new()
+        .serialize(histogram, &mut bytes)
+        .map_err(|e| anyhow::anyhow!("Failed to serialize histogram: {:?}", e))?;
+    Ok(BASE64.encode(bytes))
+}
+
+/// Deserialize a histogram produced by `encode_histogram`
+pub fn decode_histogram(encoded: &str) -> anyhow::Result<Histogram<u64>> {
+    let bytes = BASE64.decode(encoded)?;
+    Deserializer::new()
+        .deserialize(&mut bytes.as_slice())
+        .map_err(|e| anyhow::anyhow!("Failed to deserialize histogram: {:?}", e))
 }
 
 impl Default for MetricsCollector {
@@ -197,17 +1175,440 @@ mod tests {
             error: None,
             request_start_timestamp: Utc::now(),
             request_end_timestamp: Utc::now(),
+            sse_total_ms: None,
+            wall_latency_ms: None,
+            timing: None,
+            request_id: None,
+            content_encoding: None,
+            is_hook: false,
         };
 
+        collector.record_worker(2, &result);
         collector.record(result.clone());
 
         let live = collector.get_live_metrics();
         assert_eq!(live.total_requests, 1);
         assert_eq!(live.error_count, 0);
 
+        let workers = collector.worker_stats();
+        assert_eq!(workers.len(), 1);
+        assert_eq!(workers[0].worker_id, 2);
+        assert_eq!(workers[0].last_latency_ms, 100);
+        assert_eq!(workers[0].error_rate(), 0.0);
+
         let summary = collector.generate_summary();
         assert_eq!(summary.total_requests, 1);
         assert_eq!(summary.successful_requests, 1);
         assert_eq!(summary.failed_requests, 0);
     }
+
+    #[test]
+    fn test_reset_histogram_between_phases() {
+        let collector = MetricsCollector::new();
+        let result = |latency_ms: u64| RequestResult {
+            scenario_name: None,
+            latency_ms,
+            status_code: 200,
+            error: None,
+            request_start_timestamp: Utc::now(),
+            request_end_timestamp: Utc::now(),
+            sse_total_ms: None,
+            wall_latency_ms: None,
+            timing: None,
+            request_id: None,
+            content_encoding: None,
+            is_hook: false,
+        };
+
+        collector.record(result(1_000));
+        collector.record(result(1_000));
+        collector.reset_histogram().unwrap();
+        collector.record(result(10));
+        collector.drain();
+
+        let live = collector.get_live_metrics();
+        assert_eq!(live.total_requests, 1);
+        assert_eq!(live.avg_latency_ms, 10.0);
+        assert_eq!(live.p95_latency_ms, 10);
+
+        let summary = collector.generate_summary();
+        assert_eq!(summary.total_requests, 3);
+        assert_eq!(summary.max_latency_ms, 1_000);
+        assert_eq!(collector.get_results().len(), 3);
+    }
+
+    #[test]
+    fn test_error_breakdown() {
+        let collector = MetricsCollector::new();
+        let result = |error: Option<RequestError>| RequestResult {
+            scenario_name: None,
+            latency_ms: 10,
+            status_code: 0,
+            error,
+            request_start_timestamp: Utc::now(),
+            request_end_timestamp: Utc::now(),
+            sse_total_ms: None,
+            wall_latency_ms: None,
+            timing: None,
+            request_id: None,
+            content_encoding: None,
+            is_hook: false,
+        };
+
+        collector.record(result(None));
+        collector.record(result(Some(RequestError::ConnectionRefused)));
+        collector.record(result(Some(RequestError::Timeout {
+            kind: TimeoutKind::Request,
+        })));
+        collector.record(result(Some(RequestError::Timeout {
+            kind: TimeoutKind::Connect,
+        })));
+
+        let summary = collector.generate_summary();
+        assert_eq!(summary.failed_requests, 3);
+        assert_eq!(summary.error_breakdown.get("Timeout"), Some(&2));
+        assert_eq!(summary.timeout_count, 2);
+        assert_eq!(summary.error_breakdown.get("ConnectionRefused"), Some(&1));
+    }
+
+    #[test]
+    fn test_median_and_stddev() {
+        let result = |latency_ms: u64| RequestResult {
+            scenario_name: None,
+            latency_ms,
+            status_code: 200,
+            error: None,
+            request_start_timestamp: Utc::now(),
+            request_end_timestamp: Utc::now(),
+            sse_total_ms: None,
+            wall_latency_ms: None,
+            timing: None,
+            request_id: None,
+            content_encoding: None,
+            is_hook: false,
+        };
+
+        let results: Vec<_> = [10, 20, 30, 40].into_iter().map(result).collect();
+        let summary = MetricsSummary::from_results(&results, Utc::now(), Utc::now());
+        assert_eq!(summary.median_latency_ms, 25.0);
+        assert!((summary.stddev_latency_ms - 11.18).abs() < 0.01);
+
+        let summary = MetricsSummary::from_results(&results[..3], Utc::now(), Utc::now());
+        assert_eq!(summary.median_latency_ms, 20.0);
+    }
+
+    #[test]
+    fn test_apdex() {
+        let collector = MetricsCollector::new().with_apdex_threshold(Some(100));
+        let result = |latency_ms: u64, error: Option<RequestError>| RequestResult {
+            scenario_name: None,
+            latency_ms,
+            status_code: 200,
+            error,
+            request_start_timestamp: Utc::now(),
+            request_end_timestamp: Utc::now(),
+            sse_total_ms: None,
+            wall_latency_ms: None,
+            timing: None,
+            request_id: None,
+            content_encoding: None,
+            is_hook: false,
+        };
+
+        collector.record(result(50, None)); // satisfied
+        collector.record(result(100, None)); // satisfied
+        collector.record(result(300, None)); // tolerating
+        collector.record(result(500, None)); // frustrated
+        collector.record(result(50, Some(RequestError::ConnectionRefused))); // frustrated
+
+        let summary = collector.generate_summary();
+        assert_eq!(summary.apdex, Some(0.5));
+        assert!(MetricsCollector::new().generate_summary().apdex.is_none());
+    }
+
+    #[test]
+    fn test_over_sla() {
+        let collector = MetricsCollector::new().with_sla_latency(Some(300));
+        // The skipped step is left out of the SLA violation rate
+        let results = [100, 200, 300, 301, 900]
+            .map(|latency_ms| (latency_ms, None))
+            .into_iter()
+            .chain([(0, Some(RequestError::Skipped))]);
+        for (latency_ms, error) in results {
+            collector.record(RequestResult {
+                scenario_name: None,
+                latency_ms,
+                status_code: 200,
+                error,
+                request_start_timestamp: Utc::now(),
+                request_end_timestamp: Utc::now(),
+                sse_total_ms: None,
+                wall_latency_ms: None,
+                timing: None,
+                request_id: None,
+                content_encoding: None,
+                is_hook: false,
+            });
+        }
+
+        let summary = collector.generate_summary();
+        assert_eq!(summary.over_sla_count, Some(2));
+        assert_eq!(summary.over_sla_percent, Some(40.0));
+    }
+
+    #[test]
+    fn test_histogram_bounds_clamp_latencies() {
+        let collector = MetricsCollector::with_histogram_bounds(HistogramBounds {
+            min_ms: 1,
+            max_ms: 100,
+            significant_figures: 2,
+        });
+        // A 0 ms response fits in the lowest bucket; only 5000 ms is clamped
+        for latency_ms in [0, 20, 50, 5000] {
+            collector.record(RequestResult {
+                scenario_name: None,
+                latency_ms,
+                status_code: 200,
+                error: None,
+                request_start_timestamp: Utc::now(),
+                request_end_timestamp: Utc::now(),
+                sse_total_ms: None,
+                wall_latency_ms: None,
+                timing: None,
+                request_id: None,
+                content_encoding: None,
+                is_hook: false,
+            });
+        }
+
+        let summary = collector.generate_summary();
+        assert_eq!(summary.clamped_histogram_values, 1);
+        assert_eq!(summary.p99_latency_ms, 100);
+        assert_eq!(summary.max_latency_ms, 100);
+
+        let merged = MetricsSummary::merge(&[summary.clone(), summary]).unwrap();
+        assert_eq!(merged.clamped_histogram_values, 2);
+
+        let results = collector.get_results();
+        let now = Utc::now();
+        let summary = MetricsSummary::from_results_with_bounds(
+            &results,
+            collector.histogram_bounds(),
+            now,
+            now,
+        );
+        assert_eq!(summary.clamped_histogram_values, 1);
+        assert_eq!(summary.max_latency_ms, 100);
+        assert_eq!(
+            MetricsSummary::from_results(&results, now, now).clamped_histogram_values,
+            0
+        );
+    }
+
+    #[test]
+    fn test_error_times_and_samples() {
+        let start = Utc::now();
+        let result = |offset_ms: i64, status: u16| RequestResult {
+            scenario_name: Some("login".to_string()),
+            latency_ms: 10,
+            status_code: status,
+            error: (status >= 400).then_some(RequestError::HttpError { status }),
+            request_start_timestamp: start,
+            request_end_timestamp: start + chrono::Duration::milliseconds(offset_ms),
+            sse_total_ms: None,
+            wall_latency_ms: None,
+            timing: None,
+            request_id: None,
+            content_encoding: None,
+            is_hook: false,
+        };
+        let mut results = vec![result(0, 200), result(10, 500), result(20, 503)];
+        results.extend((30..60).map(|offset_ms| result(offset_ms, 400 + offset_ms as u16)));
+        results.push(result(70, 500));
+        results.push(result(90, 200));
+
+        let summary = MetricsSummary::from_results(&results, start, start);
+        assert_eq!(
+            summary.first_error_time,
+            Some(start + chrono::Duration::milliseconds(10))
+        );
+        assert_eq!(
+            summary.last_error_time,
+            Some(start + chrono::Duration::milliseconds(70))
+        );
+        assert_eq!(summary.sample_errors.len(), 10);
+        assert_eq!(summary.sample_errors[0], "login: HTTP status 500");
+        assert_eq!(summary.sample_errors[2], "login: HTTP status 430");
+
+        let merged = MetricsSummary::merge(&[summary.clone(), summary]).unwrap();
+        assert_eq!(merged.sample_errors.len(), 10);
+        assert_eq!(
+            merged.first_error_time,
+            Some(start + chrono::Duration::milliseconds(10))
+        );
+    }
+
+    #[test]
+    fn test_timing_percentiles() {
+        let result = |dns_ms: Option<u64>, ttfb_ms: u64| RequestResult {
+            scenario_name: None,
+            latency_ms: ttfb_ms,
+            status_code: 200,
+            error: None,
+            request_start_timestamp: Utc::now(),
+            request_end_timestamp: Utc::now(),
+            sse_total_ms: None,
+            wall_latency_ms: None,
+            timing: Some(TimingBreakdown {
+                dns_ms,
+                ttfb_ms: Some(ttfb_ms),
+                ..TimingBreakdown::default()
+            }),
+            request_id: None,
+            content_encoding: None,
+            is_hook: false,
+        };
+        let mut results: Vec<RequestResult> = (1..=100).map(|i| result(None, i)).collect();
+        results.push(result(Some(40), 1));
+
+        let phases = timing_percentiles(&results);
+        assert_eq!(connection_reuse_rate(&results), None);
+        assert_eq!(phases.len(), 2);
+        assert_eq!(phases[0].phase, "dns");
+        assert_eq!(phases[0].p99_ms, 40);
+        assert_eq!(phases[1].phase, "ttfb");
+        assert_eq!(phases[1].p50_ms, 50);
+
+        // One new connection carrying four requests
+        for (i, result) in results.iter_mut().take(4).enumerate() {
+            result.timing.as_mut().unwrap().new_connection = Some(i == 0);
+        }
+        assert_eq!(connection_reuse_rate(&results), Some(75.0));
+    }
+
+    #[test]
+    fn test_request_error_serialization() {
+        let error = RequestError::HttpError { status: 503 };
+        let json = serde_json::to_string(&error).unwrap();
+
+        assert_eq!(json, r#"{"type":"HttpError","detail":{"status":503}}"#);
+        assert_eq!(serde_json::from_str::<RequestError>(&json).unwrap(), error);
+    }
+
+    #[test]
+    fn test_histogram_round_trip_and_merge() {
+        let result = |latency_ms: u64| RequestResult {
+            scenario_name: None,
+            latency_ms,
+            status_code: 200,
+            error: None,
+            request_start_timestamp: Utc::now(),
+            request_end_timestamp: Utc::now(),
+            sse_total_ms: None,
+            wall_latency_ms: None,
+            timing: None,
+            request_id: None,
+            content_encoding: None,
+            is_hook: false,
+        };
+
+        let start = Utc::now();
+        let end = start + chrono::Duration::seconds(1);
+        let fast: Vec<_> = (1..=90).map(result).collect();
+        let slow: Vec<_> = (1..=10).map(|i| result(1000 + i)).collect();
+
+        let first = MetricsSummary::from_results(&fast, start, end);
+        let second = MetricsSummary::from_results(&slow, start, end);
+
+        let decoded = decode_histogram(first.latency_histogram.as_ref().unwrap()).unwrap();
+        assert_eq!(decoded.len(), 90);
+
+        let merged = MetricsSummary::merge(&[first, second]).unwrap();
+        let pooled: Vec<_> = fast.into_iter().chain(slow).collect();
+        let expected = MetricsSummary::from_results(&pooled, start, end);
+
+        assert_eq!(merged.total_requests, 100);
+        assert_eq!(merged.p50_latency_ms, expected.p50_latency_ms);
+        assert_eq!(merged.p95_latency_ms, expected.p95_latency_ms);
+        assert_eq!(merged.p99_latency_ms, expected.p99_latency_ms);
+        assert_eq!(merged.max_latency_ms, expected.max_latency_ms);
+    }
+
+    #[test]
+    fn test_live_metrics_after_concurrent_records() {
+        let collector = Arc::new(MetricsCollector::new());
+
+        let handles: Vec<_> = (0..10)
+            .map(|t| {
+                let collector = Arc::clone(&collector);
+                thread::spawn(move || {
+                    for i in 0..1_000 {
+                        collector.record(RequestResult {
+                            scenario_name: None,
+                            latency_ms: 10,
+                            status_code: 200,
+                            error: if (t * 1_000 + i) % 10 == 0 {
+                                Some(RequestError::ConnectionRefused)
+                            } else {
+                                None
+                            },
+                            request_start_timestamp: Utc::now(),
+                            request_end_timestamp: Utc::now(),
+                            sse_total_ms: None,
+                            wall_latency_ms: None,
+                            timing: None,
+                            request_id: None,
+                            content_encoding: None,
+                            is_hook: false,
+                        });
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let live = collector.get_live_metrics();
+        assert_eq!(live.total_requests, 10_000);
+        assert_eq!(live.error_count, 1_000);
+        assert_eq!(live.avg_latency_ms, 10.0);
+        assert_eq!(collector.get_results().len(), 10_000);
+
+        // Once drained, the running P95 reflects every stored latency
+        assert_eq!(collector.get_live_metrics().p95_latency_ms, 10);
+    }
+
+    #[test]
+    fn test_rolling_p99_uses_recent_window() {
+        let collector = MetricsCollector::new();
+        assert_eq!(collector.rolling_p99(), None);
+
+        let result = |latency_ms: u64| RequestResult {
+            scenario_name: None,
+            latency_ms,
+            status_code: 200,
+            error: None,
+            request_start_timestamp: Utc::now(),
+            request_end_timestamp: Utc::now(),
+            sse_total_ms: None,
+            wall_latency_ms: None,
+            timing: None,
+            request_id: None,
+            content_encoding: None,
+            is_hook: false,
+        };
+
+        for _ in 0..ROLLING_WINDOW_SIZE {
+            collector.record(result(5000));
+        }
+        for _ in 0..ROLLING_WINDOW_SIZE {
+            collector.record(result(10));
+        }
+        collector.drain();
+
+        assert_eq!(collector.rolling_p99(), Some(10));
+        assert!(collector.generate_summary().max_latency_ms >= 5000);
+    }
 }
@@ -0,0 +1,84 @@
+use crate::config::NotifyConfig;
+use crate::metrics::MetricsSummary;
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+/// POST the key metrics of a finished run to the configured webhook
+pub async fn send_notification(
+    notify: &NotifyConfig,
+    summary: &MetricsSummary,
+    passed: bool,
+) -> Result<()> {
+    let payload = build_payload(notify, summary, passed);
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()?;
+
+    client
+        .post(&notify.webhook_url)
+        .json(&payload)
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
+
+/// Build the webhook payload in the configured format
+fn build_payload(notify: &NotifyConfig, summary: &MetricsSummary, passed: bool) -> Value {
+    match notify.format.as_deref() {
+        Some("slack") => {
+            let status = if passed { "✅ passed" } else { "❌ failed" };
+            json!({
+                "text": format!(
+                    "⚡ Flux load test {}\n• Total requests: {}\n• Error rate: {:.2}%\n• P95 latency: {}ms\n• Throughput: {:.2} req/s",
+                    status,
+                    summary.total_requests,
+                    summary.error_rate,
+                    summary.p95_latency_ms,
+                    summary.throughput_rps
+                )
+            })
+        }
+        _ => json!({
+            "passed": passed,
+            "total_requests": summary.total_requests,
+            "failed_requests": summary.failed_requests,
+            "error_rate": summary.error_rate,
+            "p95_latency_ms": summary.p95_latency_ms,
+            "throughput_rps": summary.throughput_rps,
+            "start_time": summary.start_time,
+            "end_time": summary.end_time,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn test_payload_formats() {
+        let summary = MetricsSummary::from_results(&[], Utc::now(), Utc::now());
+
+        let json_notify = NotifyConfig {
+            webhook_url: "http://localhost/hook".to_string(),
+            format: None,
+        };
+        let payload = build_payload(&json_notify, &summary, true);
+        assert_eq!(payload["passed"], true);
+        assert_eq!(payload["total_requests"], 0);
+
+        let slack_notify = NotifyConfig {
+            format: Some("slack".to_string()),
+            ..json_notify
+        };
+        let payload = build_payload(&slack_notify, &summary, false);
+        let text = payload["text"].as_str().unwrap();
+        assert!(text.contains("failed"));
+        assert!(text.contains("Total requests: 0"));
+    }
+}
@@ -1,56 +1,667 @@
-use crate::metrics::{MetricsSummary, RequestResult};
+use crate::aggregate::{AggregatedReport, FieldStats};
+use crate::baseline::MetricDelta;
+use crate::config::OutputConfig;
+use crate::grafana;
+use crate::har::{Har, HarEntry};
+use crate::metrics::{MetricsSummary, RequestResult, SLA_ACCEPTABLE_PERCENT};
 use anyhow::Result;
-use serde::Serialize;
-use std::fs;
+use base64::engine::general_purpose::URL_SAFE as BASE64_URL;
+use base64::Engine;
+use reqwest::header::CONTENT_TYPE;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
 use tera::{Context, Tera};
 
 /// Report data structure
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Report {
     pub summary: MetricsSummary,
     pub results: Vec<RequestResult>,
+    /// Summary of each phase of a phased run, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub phase_summaries: Vec<(String, MetricsSummary)>,
+    /// Summary of each request in a weighted request mix
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub request_summaries: Vec<(String, MetricsSummary)>,
+}
+
+impl Report {
+    /// Load a previously generated JSON report
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let report: Report = serde_json::from_str(&content)?;
+        Ok(report)
+    }
+
+    /// Merge reports from several runners into one. Percentiles come from the
+    /// merged histograms when every report carries one, otherwise they are
+    /// recomputed from the pooled results
+    pub fn merge(reports: Vec<Report>) -> Result<Self> {
+        let summaries: Vec<MetricsSummary> = reports.iter().map(|r| r.summary.clone()).collect();
+
+        let mut results: Vec<RequestResult> = reports.into_iter().flat_map(|r| r.results).collect();
+        results.sort_by_key(|r| r.request_start_timestamp);
+
+        let summary = if summaries.iter().all(|s| s.latency_histogram.is_some()) {
+            MetricsSummary::merge(&summaries)?
+        } else {
+            let start_time = summaries
+                .iter()
+                .map(|s| s.start_time)
+                .min()
+                .ok_or_else(|| anyhow::anyhow!("No reports to merge"))?;
+            let end_time = summaries
+                .iter()
+                .map(|s| s.end_time)
+                .max()
+                .unwrap_or(start_time);
+
+            MetricsSummary::from_results(&results, start_time, end_time)
+        };
+
+        Ok(Self {
+            summary,
+            results,
+            phase_summaries: Vec::new(),
+            request_summaries: Vec::new(),
+        })
+    }
+}
+
+/// Single line of the NDJSON request log
+#[derive(Debug, Serialize)]
+struct RequestLogEntry<'a> {
+    #[serde(flatten)]
+    result: &'a RequestResult,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    sampled: bool,
+}
+
+/// NDJSON writer logging individual requests as they complete
+#[derive(Debug)]
+pub struct RequestLog {
+    writer: Mutex<BufWriter<File>>,
+    written: AtomicUsize,
+}
+
+impl RequestLog {
+    /// Create the log file, truncating any previous contents
+    pub fn create(output_path: &str) -> Result<Self> {
+        // Ensure parent directory exists
+        if let Some(parent) = Path::new(output_path).parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let file = File::create(output_path)?;
+        Ok(Self {
+            writer: Mutex::new(BufWriter::new(file)),
+            written: AtomicUsize::new(0),
+        })
+    }
+
+    /// Append a request result as a single JSON line
+    pub fn write(&self, result: &RequestResult, sampled: bool) -> Result<()> {
+        let line = serde_json::to_string(&RequestLogEntry { result, sampled })?;
+
+        let mut writer = self
+            .writer
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Request log lock poisoned"))?;
+        writeln!(writer, "{}", line)?;
+        self.written.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Number of entries written so far
+    pub fn written(&self) -> usize {
+        self.written.load(Ordering::Relaxed)
+    }
+
+    /// Flush buffered entries to disk
+    pub fn flush(&self) -> Result<()> {
+        let mut writer = self
+            .writer
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Request log lock poisoned"))?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Single line of the streaming JSON output
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StreamLine {
+    Result(Box<RequestResult>),
+    Summary(Box<MetricsSummary>),
+}
+
+/// NDJSON writer streaming every result as it completes, followed by the
+/// final summary. Lines are sent over a channel to a writer thread that
+/// flushes (and for files syncs) each batch, so the output stays readable
+/// if the process is killed mid-run and recording never waits on I/O.
+#[derive(Debug)]
+pub struct StreamingJsonWriter {
+    sender: Mutex<Option<crossbeam_channel::Sender<StreamLine>>>,
+    handle: Mutex<Option<JoinHandle<Result<()>>>>,
+}
+
+impl StreamingJsonWriter {
+    /// Create the stream file, truncating any previous contents
+    pub fn create(output_path: &str) -> Result<Self> {
+        // Ensure parent directory exists
+        if let Some(parent) = Path::new(output_path).parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        Self::spawn(File::create(output_path)?, File::sync_data)
+    }
+
+    /// Stream to stdout, e.g. for another process to consume live
+    pub fn stdout() -> Result<Self> {
+        Self::spawn(std::io::stdout(), |_| Ok(()))
+    }
+
+    /// Start the writer thread, calling `sync` after each flushed batch
+    fn spawn<W: Write + Send + 'static>(
+        output: W,
+        sync: fn(&W) -> std::io::Result<()>,
+    ) -> Result<Self> {
+        let (sender, receiver) = crossbeam_channel::unbounded::<StreamLine>();
+
+        let handle = thread::Builder::new()
+            .name("flux-json-stream".to_string())
+            .spawn(move || -> Result<()> {
+                let mut writer = BufWriter::new(output);
+
+                while let Ok(first) = receiver.recv() {
+                    for line in std::iter::once(first).chain(receiver.try_iter()) {
+                        serde_json::to_writer(&mut writer, &line)?;
+                        writer.write_all(b"\n")?;
+                    }
+
+                    writer.flush()?;
+                    sync(writer.get_ref())?;
+                }
+
+                Ok(())
+            })?;
+
+        Ok(Self {
+            sender: Mutex::new(Some(sender)),
+            handle: Mutex::new(Some(handle)),
+        })
+    }
+
+    /// Queue a request result to be appended as a JSON line
+    pub fn write(&self, result: &RequestResult) {
+        if let Ok(sender) = self.sender.lock() {
+            if let Some(ref sender) = *sender {
+                let _ = sender.send(StreamLine::Result(Box::new(result.clone())));
+            }
+        }
+    }
+
+    /// Append the summary as the last line and wait for everything to be
+    /// written. Results written afterwards are dropped.
+    pub fn finish(&self, summary: &MetricsSummary) -> Result<()> {
+        let sender = self
+            .sender
+            .lock()
+            .map_err(|_| anyhow::anyhow!("JSON stream lock poisoned"))?
+            .take();
+
+        if let Some(sender) = sender {
+            let _ = sender.send(StreamLine::Summary(Box::new(summary.clone())));
+        }
+
+        let handle = self
+            .handle
+            .lock()
+            .map_err(|_| anyhow::anyhow!("JSON stream lock poisoned"))?
+            .take();
+
+        match handle {
+            Some(handle) => handle
+                .join()
+                .map_err(|_| anyhow::anyhow!("JSON stream writer panicked"))?,
+            None => Ok(()),
+        }
+    }
+}
+
+/// Per-scenario request counts used by the JUnit report
+#[derive(Debug, Default)]
+struct ScenarioStats {
+    total: usize,
+    failed: usize,
+    total_latency_ms: u64,
+    first_error: Option<String>,
 }
 
 /// Reporter for generating JSON and HTML reports
 pub struct Reporter {
     report: Report,
+    har_entries: Vec<HarEntry>,
 }
 
 impl Reporter {
     /// Create a new reporter
     pub fn new(summary: MetricsSummary, results: Vec<RequestResult>) -> Self {
+        Self::from_report(Report {
+            summary,
+            results,
+            phase_summaries: Vec::new(),
+            request_summaries: Vec::new(),
+        })
+    }
+
+    /// Create a reporter for an existing report
+    pub fn from_report(report: Report) -> Self {
         Self {
-            report: Report { summary, results },
+            report,
+            har_entries: Vec::new(),
         }
     }
 
-    /// Generate JSON report
-    pub fn generate_json(&self, output_path: &str) -> Result<()> {
-        let json = serde_json::to_string_pretty(&self.report)?;
+    /// Include the summary of each phase in the report
+    pub fn with_phase_summaries(mut self, phase_summaries: Vec<(String, MetricsSummary)>) -> Self {
+        self.report.phase_summaries = phase_summaries;
+        self
+    }
 
-        // Ensure parent directory exists
-        if let Some(parent) = Path::new(output_path).parent() {
-            fs::create_dir_all(parent)?;
-        }
+    /// Include a summary of each request in the mix
+    pub fn with_request_summaries(
+        mut self,
+        request_summaries: Vec<(String, MetricsSummary)>,
+    ) -> Self {
+        self.report.request_summaries = request_summaries;
+        self
+    }
 
-        fs::write(output_path, json)?;
-        Ok(())
+    /// Include captured request/response pairs for the HAR file
+    pub fn with_har_entries(mut self, har_entries: Vec<HarEntry>) -> Self {
+        self.har_entries = har_entries;
+        self
+    }
+
+    /// Report being generated
+    pub fn report(&self) -> &Report {
+        &self.report
+    }
+
+    /// Summary of the report being generated
+    pub fn summary(&self) -> &MetricsSummary {
+        &self.report.summary
+    }
+
+    /// Generate JSON report, indented when `pretty`
+    pub fn generate_json(&self, output_path: &str, pretty: bool) -> Result<()> {
+        JsonFormat { pretty }.write(&self.report, output_path)
     }
 
     /// Generate HTML report
     pub fn generate_html(&self, output_path: &str) -> Result<()> {
-        let html = self.render_html()?;
+        HtmlFormat.write(&self.report, output_path)
+    }
 
-        // Ensure parent directory exists
-        if let Some(parent) = Path::new(output_path).parent() {
-            fs::create_dir_all(parent)?;
-        }
+    /// Generate JUnit XML report with one test case per scenario
+    pub fn generate_junit(&self, output_path: &str) -> Result<()> {
+        JunitFormat.write(&self.report, output_path)
+    }
+
+    /// Generate a Grafana snapshot JSON with the time series inlined
+    pub fn generate_grafana_dashboard(&self, output_path: &str) -> Result<()> {
+        GrafanaFormat.write(&self.report, output_path)
+    }
 
-        fs::write(output_path, html)?;
+    /// POST the summary gauges in Prometheus text format to the gateway,
+    /// grouped by `job` and this host as `instance`
+    pub async fn push_to_prometheus(&self, gateway_url: &str, job: &str) -> Result<()> {
+        let body = prometheus_text(&self.report.summary);
+        let url = push_url(gateway_url, job, &hostname());
+
+        reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()?
+            .post(&url)
+            .header(CONTENT_TYPE, "text/plain; version=0.0.4")
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?;
         Ok(())
     }
 
+    /// Generate a HAR 1.2 file of the captured request/response pairs
+    pub fn generate_har(&self, output_path: &str) -> Result<()> {
+        let har = Har::new(self.har_entries.clone());
+        write_output(output_path, &serde_json::to_string_pretty(&har)?)
+    }
+}
+
+/// Job the Prometheus gauges are pushed under unless configured
+pub const DEFAULT_PROMETHEUS_JOB: &str = "flux";
+
+/// Pushgateway URL grouping a push by `job` and `instance`. Both are
+/// base64url-encoded (`@base64`) so any value, `/` included, fits in the
+/// path; an empty value is written as `=`.
+fn push_url(gateway_url: &str, job: &str, instance: &str) -> String {
+    let encode = |value: &str| match BASE64_URL.encode(value) {
+        encoded if encoded.is_empty() => "=".to_string(),
+        encoded => encoded,
+    };
+    format!(
+        "{}/metrics/job@base64/{}/instance@base64/{}",
+        gateway_url.trim_end_matches('/'),
+        encode(job),
+        encode(instance)
+    )
+}
+
+/// Summary gauges in the Prometheus text exposition format, labelled by
+/// the Pushgateway from the grouping key
+fn prometheus_text(summary: &MetricsSummary) -> String {
+    let gauges = [
+        (
+            "flux_total_requests",
+            "Requests sent",
+            summary.total_requests as f64,
+        ),
+        (
+            "flux_error_rate",
+            "Failed requests in percent",
+            summary.error_rate,
+        ),
+        (
+            "flux_p50_latency_ms",
+            "P50 latency in milliseconds",
+            summary.p50_latency_ms as f64,
+        ),
+        (
+            "flux_p95_latency_ms",
+            "P95 latency in milliseconds",
+            summary.p95_latency_ms as f64,
+        ),
+        (
+            "flux_p99_latency_ms",
+            "P99 latency in milliseconds",
+            summary.p99_latency_ms as f64,
+        ),
+        (
+            "flux_throughput_rps",
+            "Requests per second",
+            summary.throughput_rps,
+        ),
+    ];
+
+    let mut text = String::new();
+    for (name, help, value) in gauges {
+        text.push_str(&format!(
+            "# HELP {name} {help}\n# TYPE {name} gauge\n{name} {value}\n"
+        ));
+    }
+    text
+}
+
+/// Name of this machine, or "unknown" when it can't be read
+fn hostname() -> String {
+    std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| fs::read_to_string("/proc/sys/kernel/hostname").ok())
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// An output format a finished report can be written in
+pub trait ReportFormat {
+    /// Human-readable name used in log messages
+    fn name(&self) -> &'static str;
+
+    /// Write the report to `path`
+    fn write(&self, report: &Report, path: &str) -> Result<()>;
+}
+
+/// Full report with raw results as JSON
+pub struct JsonFormat {
+    /// Indent the output
+    pub pretty: bool,
+}
+
+impl ReportFormat for JsonFormat {
+    fn name(&self) -> &'static str {
+        "JSON"
+    }
+
+    fn write(&self, report: &Report, path: &str) -> Result<()> {
+        write_json(path, report, self.pretty)
+    }
+}
+
+/// Self-contained HTML report with charts
+pub struct HtmlFormat;
+
+impl ReportFormat for HtmlFormat {
+    fn name(&self) -> &'static str {
+        "HTML"
+    }
+
+    fn write(&self, report: &Report, path: &str) -> Result<()> {
+        write_output(path, &report.render_html()?)
+    }
+}
+
+/// JUnit XML with one test case per scenario
+pub struct JunitFormat;
+
+impl ReportFormat for JunitFormat {
+    fn name(&self) -> &'static str {
+        "JUnit"
+    }
+
+    fn write(&self, report: &Report, path: &str) -> Result<()> {
+        write_output(path, &report.render_junit())
+    }
+}
+
+/// Grafana dashboard snapshot with the time series inlined
+pub struct GrafanaFormat;
+
+impl ReportFormat for GrafanaFormat {
+    fn name(&self) -> &'static str {
+        "Grafana snapshot"
+    }
+
+    fn write(&self, report: &Report, path: &str) -> Result<()> {
+        write_output(
+            path,
+            &serde_json::to_string_pretty(&grafana::snapshot(report))?,
+        )
+    }
+}
+
+/// Every report format enabled in the output config with its path
+pub fn configured_formats(output: &OutputConfig) -> Vec<(Box<dyn ReportFormat>, &str)> {
+    let mut formats: Vec<(Box<dyn ReportFormat>, &str)> = vec![
+        (
+            Box::new(JsonFormat {
+                pretty: output.pretty_json,
+            }),
+            output.json.as_str(),
+        ),
+        (Box::new(HtmlFormat), output.html.as_str()),
+    ];
+
+    if let Some(ref path) = output.junit {
+        formats.push((Box::new(JunitFormat), path.as_str()));
+    }
+    if let Some(ref path) = output.grafana_dashboard {
+        formats.push((Box::new(GrafanaFormat), path.as_str()));
+    }
+
+    formats
+}
+
+/// Write a side-by-side HTML comparison of two reports
+pub fn generate_comparison_html(
+    deltas: &[MetricDelta],
+    baseline_name: &str,
+    current_name: &str,
+    output_path: &str,
+) -> Result<()> {
+    write_output(
+        output_path,
+        &render_comparison_html(deltas, baseline_name, current_name)?,
+    )
+}
+
+/// Render the comparison HTML report
+fn render_comparison_html(
+    deltas: &[MetricDelta],
+    baseline_name: &str,
+    current_name: &str,
+) -> Result<String> {
+    let mut tera = Tera::default();
+    tera.add_raw_template("comparison.html", include_str!("templates/comparison.html"))?;
+
+    let mut context = Context::new();
+    context.insert("deltas", deltas);
+    context.insert("baseline_name", baseline_name);
+    context.insert("current_name", current_name);
+
+    Ok(tera.render("comparison.html", &context)?)
+}
+
+/// Write the aggregated multi-run report as HTML
+pub fn generate_aggregate_html(report: &AggregatedReport, output_path: &str) -> Result<()> {
+    write_output(output_path, &render_aggregate_html(report)?)
+}
+
+/// Render the aggregated report HTML, charting latency percentiles with
+/// standard deviation error bars
+fn render_aggregate_html(report: &AggregatedReport) -> Result<String> {
+    let mut tera = Tera::default();
+    tera.add_raw_template("aggregate.html", include_str!("templates/aggregate.html"))?;
+
+    let percentiles: Vec<(&str, &FieldStats)> = [
+        ("Mean", "mean_latency_ms"),
+        ("P50", "p50_latency_ms"),
+        ("P90", "p90_latency_ms"),
+        ("P95", "p95_latency_ms"),
+        ("P99", "p99_latency_ms"),
+    ]
+    .into_iter()
+    .filter_map(|(label, field)| report.metrics.get(field).map(|stats| (label, stats)))
+    .collect();
+
+    let mut context = Context::new();
+    context.insert("report", report);
+    context.insert("percentiles", &percentiles);
+    context.insert("charts_js", include_str!("templates/charts.js"));
+
+    Ok(tera.render("aggregate.html", &context)?)
+}
+
+/// Write a rendered report, creating the parent directory if needed
+fn write_output(path: &str, contents: &str) -> Result<()> {
+    if let Some(parent) = Path::new(path).parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    fs::write(path, contents)?;
+    Ok(())
+}
+
+/// Serialize `value` straight into a buffered file, so large result sets
+/// are never held in memory as one string
+fn write_json<T: Serialize>(path: &str, value: &T, pretty: bool) -> Result<()> {
+    if let Some(parent) = Path::new(path).parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    if pretty {
+        serde_json::to_writer_pretty(&mut writer, value)?;
+    } else {
+        serde_json::to_writer(&mut writer, value)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+impl Report {
+    /// Render JUnit XML report
+    fn render_junit(&self) -> String {
+        let stats = self.scenario_stats();
+        let failures = stats.values().filter(|s| s.failed > 0).count();
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!(
+            "<testsuite name=\"flux\" tests=\"{}\" failures=\"{}\" errors=\"0\" time=\"{:.3}\" timestamp=\"{}\">\n",
+            stats.len(),
+            failures,
+            self.summary.total_duration_secs,
+            self.summary.start_time.to_rfc3339()
+        ));
+
+        for (name, stat) in &stats {
+            xml.push_str(&format!(
+                "  <testcase name=\"{}\" classname=\"flux\" time=\"{:.3}\">\n",
+                escape_xml(name),
+                stat.total_latency_ms as f64 / 1000.0
+            ));
+
+            if stat.failed > 0 {
+                let error_rate = stat.failed as f64 / stat.total as f64 * 100.0;
+                let message = format!(
+                    "{} of {} requests failed ({:.2}% error rate)",
+                    stat.failed, stat.total, error_rate
+                );
+                xml.push_str(&format!(
+                    "    <failure message=\"{}\" type=\"error_rate\">{}</failure>\n",
+                    escape_xml(&message),
+                    escape_xml(stat.first_error.as_deref().unwrap_or(&message))
+                ));
+            }
+
+            xml.push_str("  </testcase>\n");
+        }
+
+        xml.push_str("</testsuite>\n");
+        xml
+    }
+
+    /// Group results by scenario name
+    fn scenario_stats(&self) -> BTreeMap<String, ScenarioStats> {
+        let mut stats: BTreeMap<String, ScenarioStats> = BTreeMap::new();
+
+        for result in &self.results {
+            let name = result.scenario_name.as_deref().unwrap_or("request");
+            let entry = stats.entry(name.to_string()).or_default();
+
+            entry.total += 1;
+            entry.total_latency_ms += result.latency_ms;
+
+            if let Some(error) = result.error.as_ref().filter(|_| result.is_failure()) {
+                entry.failed += 1;
+                if entry.first_error.is_none() {
+                    entry.first_error = Some(error.to_string());
+                }
+            }
+        }
+
+        stats
+    }
+
     /// Render HTML report
     fn render_html(&self) -> Result<String> {
         let template = include_str!("templates/report.html");
@@ -59,13 +670,13 @@ impl Reporter {
         tera.add_raw_template("report.html", template)?;
 
         let mut context = Context::new();
-        context.insert("summary", &self.report.summary);
-        context.insert("results", &self.report.results);
+        context.insert("summary", &self.summary);
+        context.insert("results", &self.results);
 
         // Prepare data for charts
-        let latency_data: Vec<u64> = self.report.results.iter().map(|r| r.latency_ms).collect();
+        let latency_data: Vec<u64> = self.results.iter().map(|r| r.latency_ms).collect();
 
-        let status_codes: Vec<u16> = self.report.results.iter().map(|r| r.status_code).collect();
+        let status_codes: Vec<u16> = self.results.iter().map(|r| r.status_code).collect();
 
         // Calculate latency distribution
         let latency_distribution = self.calculate_latency_distribution();
@@ -74,12 +685,30 @@ impl Reporter {
         context.insert("status_codes", &status_codes);
         context.insert("latency_distribution", &latency_distribution);
 
+        // Mark where each phase starts on the latency-over-time chart
+        let phase_markers: Vec<(usize, &str)> = self
+            .phase_summaries
+            .iter()
+            .filter_map(|(name, summary)| {
+                self.results
+                    .iter()
+                    .position(|r| r.request_start_timestamp >= summary.start_time)
+                    .map(|index| (index, name.as_str()))
+            })
+            .collect();
+        context.insert("phase_markers", &phase_markers);
+
+        context.insert("sla_acceptable_percent", &SLA_ACCEPTABLE_PERCENT);
+
+        // Inline the chart script so the report renders offline
+        context.insert("charts_js", include_str!("templates/charts.js"));
+
         let html = tera.render("report.html", &context)?;
         Ok(html)
     }
 
     /// Calculate latency distribution for histogram
-    fn calculate_latency_distribution(&self) -> Vec<(String, usize)> {
+    pub fn calculate_latency_distribution(&self) -> Vec<(String, usize)> {
         let mut buckets: Vec<(String, usize)> = vec![
             ("0-50ms".to_string(), 0),
             ("50-100ms".to_string(), 0),
@@ -89,7 +718,7 @@ impl Reporter {
             ("1000ms+".to_string(), 0),
         ];
 
-        for result in &self.report.results {
+        for result in &self.results {
             let latency = result.latency_ms;
 
             if latency < 50 {
@@ -111,10 +740,22 @@ impl Reporter {
     }
 }
 
+/// Escape a string for use in XML text and attribute values
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::metrics::RequestError;
     use chrono::Utc;
+    use std::collections::HashMap;
 
     #[test]
     fn test_latency_distribution() {
@@ -126,6 +767,12 @@ mod tests {
                 error: None,
                 request_start_timestamp: Utc::now(),
                 request_end_timestamp: Utc::now(),
+                sse_total_ms: None,
+                wall_latency_ms: None,
+                timing: None,
+                request_id: None,
+                content_encoding: None,
+                is_hook: false,
             },
             RequestResult {
                 scenario_name: None,
@@ -134,6 +781,12 @@ mod tests {
                 error: None,
                 request_start_timestamp: Utc::now(),
                 request_end_timestamp: Utc::now(),
+                sse_total_ms: None,
+                wall_latency_ms: None,
+                timing: None,
+                request_id: None,
+                content_encoding: None,
+                is_hook: false,
             },
             RequestResult {
                 scenario_name: None,
@@ -142,6 +795,12 @@ mod tests {
                 error: None,
                 request_start_timestamp: Utc::now(),
                 request_end_timestamp: Utc::now(),
+                sse_total_ms: None,
+                wall_latency_ms: None,
+                timing: None,
+                request_id: None,
+                content_encoding: None,
+                is_hook: false,
             },
         ];
 
@@ -154,6 +813,8 @@ mod tests {
             min_latency_ms: 30,
             max_latency_ms: 150,
             mean_latency_ms: 85.0,
+            median_latency_ms: 85.0,
+            stddev_latency_ms: 0.0,
             p50_latency_ms: 75,
             p90_latency_ms: 150,
             p95_latency_ms: 150,
@@ -161,13 +822,435 @@ mod tests {
             error_rate: 0.0,
             start_time: Utc::now(),
             end_time: Utc::now(),
+            sampled_requests: None,
+            sample_rate: None,
+            error_breakdown: HashMap::new(),
+            timeout_count: 0,
+            skipped_requests: 0,
+            rate_limited_count: 0,
+            aborted_chains: 0,
+            chain_timeouts: 0,
+            throttled_requests: 0,
+            dns_cache_hits: 0,
+            clamped_histogram_values: 0,
+            hook_requests: 0,
+            failed_hook_requests: 0,
+            timing_percentiles: Vec::new(),
+            connection_reuse_rate: None,
+            first_error_time: None,
+            last_error_time: None,
+            sample_errors: Vec::new(),
+            gzip_responses: false,
+            latency_histogram: None,
+            max_outstanding: None,
+            dropped_iterations: None,
+            peak_concurrency: None,
+            apdex: None,
+            over_sla_count: None,
+            over_sla_percent: None,
         };
 
         let reporter = Reporter::new(summary, results);
-        let distribution = reporter.calculate_latency_distribution();
+        let distribution = reporter.report.calculate_latency_distribution();
 
         assert_eq!(distribution[0].1, 1); // 0-50ms
         assert_eq!(distribution[1].1, 1); // 50-100ms
         assert_eq!(distribution[2].1, 1); // 100-200ms
     }
+
+    #[test]
+    fn test_junit_report() {
+        let result = |name: &str, error: Option<&str>| RequestResult {
+            scenario_name: Some(name.to_string()),
+            latency_ms: 100,
+            status_code: if error.is_some() { 0 } else { 200 },
+            error: error.map(|e| RequestError::Other(e.to_string())),
+            request_start_timestamp: Utc::now(),
+            request_end_timestamp: Utc::now(),
+            sse_total_ms: None,
+            wall_latency_ms: None,
+            timing: None,
+            request_id: None,
+            content_encoding: None,
+            is_hook: false,
+        };
+
+        let results = vec![
+            result("login", None),
+            result("login", None),
+            result("get-users", Some("connection refused <eof>")),
+            result("get-users", None),
+        ];
+
+        let summary = MetricsSummary {
+            total_requests: 4,
+            successful_requests: 3,
+            failed_requests: 1,
+            total_duration_secs: 1.0,
+            throughput_rps: 4.0,
+            min_latency_ms: 100,
+            max_latency_ms: 100,
+            mean_latency_ms: 100.0,
+            median_latency_ms: 100.0,
+            stddev_latency_ms: 0.0,
+            p50_latency_ms: 100,
+            p90_latency_ms: 100,
+            p95_latency_ms: 100,
+            p99_latency_ms: 100,
+            error_rate: 25.0,
+            start_time: Utc::now(),
+            end_time: Utc::now(),
+            sampled_requests: None,
+            sample_rate: None,
+            error_breakdown: HashMap::new(),
+            timeout_count: 0,
+            skipped_requests: 0,
+            rate_limited_count: 0,
+            aborted_chains: 0,
+            chain_timeouts: 0,
+            throttled_requests: 0,
+            dns_cache_hits: 0,
+            clamped_histogram_values: 0,
+            hook_requests: 0,
+            failed_hook_requests: 0,
+            timing_percentiles: Vec::new(),
+            connection_reuse_rate: None,
+            first_error_time: None,
+            last_error_time: None,
+            sample_errors: Vec::new(),
+            gzip_responses: false,
+            latency_histogram: None,
+            max_outstanding: None,
+            dropped_iterations: None,
+            peak_concurrency: None,
+            apdex: None,
+            over_sla_count: None,
+            over_sla_percent: None,
+        };
+
+        let reporter = Reporter::new(summary, results);
+        let xml = reporter.report.render_junit();
+
+        assert!(xml.contains("tests=\"2\" failures=\"1\""));
+        assert!(xml.contains("<testcase name=\"login\""));
+        assert!(xml.contains("1 of 2 requests failed (50.00% error rate)"));
+        assert!(xml.contains("connection refused &lt;eof&gt;"));
+    }
+
+    #[test]
+    fn test_html_report_is_self_contained() {
+        let now = Utc::now();
+        let summary = MetricsSummary::from_results(&[], now, now);
+        let html = Reporter::new(summary, Vec::new())
+            .report
+            .render_html()
+            .unwrap();
+
+        assert!(!html.contains("<script src"));
+        assert!(!html.contains("cdn."));
+        assert!(html.contains("FluxCharts = {"));
+    }
+
+    #[test]
+    fn test_html_report_optional_cards() {
+        let now = Utc::now();
+        let mut summary = MetricsSummary::from_results(&[], now, now);
+        let html = Reporter::new(summary.clone(), Vec::new())
+            .report
+            .render_html()
+            .unwrap();
+        assert!(!html.contains("Over SLA"));
+
+        summary.over_sla_count = Some(3);
+        summary.over_sla_percent = Some(2.5);
+        summary.apdex = Some(0.0);
+        let html = Reporter::new(summary, Vec::new())
+            .report
+            .render_html()
+            .unwrap();
+        assert!(html.contains("Over SLA"));
+        assert!(html.contains("Apdex"));
+    }
+
+    #[test]
+    fn test_phase_summaries_in_report() {
+        let now = Utc::now();
+        let results = vec![RequestResult {
+            scenario_name: None,
+            latency_ms: 10,
+            status_code: 200,
+            error: None,
+            request_start_timestamp: now,
+            request_end_timestamp: now,
+            sse_total_ms: None,
+            wall_latency_ms: None,
+            timing: None,
+            request_id: None,
+            content_encoding: None,
+            is_hook: false,
+        }];
+        let summary = MetricsSummary::from_results(&results, now, now);
+        let reporter = Reporter::new(summary.clone(), results)
+            .with_phase_summaries(vec![("smoke".to_string(), summary)]);
+
+        let json = serde_json::to_value(reporter.report()).unwrap();
+        assert_eq!(json["phase_summaries"][0][0], "smoke");
+        assert_eq!(json["phase_summaries"][0][1]["total_requests"], 1);
+
+        let html = reporter.report.render_html().unwrap();
+        assert!(html.contains(r#"markers: [{ index: 0, label: "smoke" }]"#));
+    }
+
+    #[test]
+    fn test_configured_formats() {
+        let mut output = OutputConfig {
+            json: "out.json".to_string(),
+            html: "out.html".to_string(),
+            junit: None,
+            request_log: None,
+            json_stream: None,
+            har: None,
+            grafana_dashboard: None,
+            timestamp_outputs: false,
+            pretty_json: false,
+            prometheus_push_gateway: None,
+            prometheus_job: None,
+        };
+
+        let names = |output: &OutputConfig| -> Vec<&'static str> {
+            configured_formats(output)
+                .iter()
+                .map(|(format, _)| format.name())
+                .collect()
+        };
+        assert_eq!(names(&output), vec!["JSON", "HTML"]);
+
+        output.junit = Some("junit.xml".to_string());
+        assert_eq!(names(&output), vec!["JSON", "HTML", "JUnit"]);
+        assert_eq!(configured_formats(&output)[2].1, "junit.xml");
+    }
+
+    #[test]
+    fn test_json_report_pretty_flag() {
+        let path = std::env::temp_dir().join("flux_test_report.json");
+        let path = path.to_str().unwrap();
+        let now = Utc::now();
+        let reporter = Reporter::new(MetricsSummary::from_results(&[], now, now), Vec::new());
+
+        for pretty in [false, true] {
+            reporter.generate_json(path, pretty).unwrap();
+            let content = fs::read_to_string(path).unwrap();
+            assert_eq!(content.contains('\n'), pretty);
+
+            let report: Report = serde_json::from_str(&content).unwrap();
+            assert_eq!(report.summary.total_requests, 0);
+        }
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_comparison_html_marks_regressions() {
+        let now = Utc::now();
+        let result = |latency_ms: u64| RequestResult {
+            scenario_name: None,
+            latency_ms,
+            status_code: 200,
+            error: None,
+            request_start_timestamp: now,
+            request_end_timestamp: now,
+            sse_total_ms: None,
+            wall_latency_ms: None,
+            timing: None,
+            request_id: None,
+            content_encoding: None,
+            is_hook: false,
+        };
+        let before = MetricsSummary::from_results(&[result(100)], now, now);
+        let after = MetricsSummary::from_results(&[result(150)], now, now);
+
+        let deltas = crate::baseline::compare_all(&before, &after, 10.0);
+        let html = render_comparison_html(&deltas, "before.json", "after.json").unwrap();
+
+        assert!(html.contains("before.json → after.json"));
+        assert!(html.contains("<td class=\"error\">50%</td>"));
+    }
+
+    #[tokio::test]
+    async fn test_push_to_prometheus() {
+        let (pushed_tx, mut pushed) = tokio::sync::mpsc::unbounded_channel();
+        let router = axum::Router::new().route(
+            "/metrics/job@base64/:job/instance@base64/:instance",
+            axum::routing::post(
+                move |axum::extract::Path(key): axum::extract::Path<(String, String)>,
+                      body: String| {
+                    let decode =
+                        |value: &str| String::from_utf8(BASE64_URL.decode(value).unwrap()).unwrap();
+                    let _ = pushed_tx.send((decode(&key.0), decode(&key.1), body));
+                    async {}
+                },
+            ),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let gateway = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let mut summary = MetricsSummary::from_results(&[], Utc::now(), Utc::now());
+        summary.total_requests = 120;
+        summary.p95_latency_ms = 87;
+        summary.error_rate = 2.5;
+        Reporter::new(summary, Vec::new())
+            .push_to_prometheus(&gateway, "checkout/eu west")
+            .await
+            .unwrap();
+
+        let (job, instance, body) = pushed.recv().await.unwrap();
+        assert_eq!(job, "checkout/eu west");
+        assert_eq!(instance, hostname());
+        assert!(body.contains("# TYPE flux_total_requests gauge\n"));
+        assert!(body.contains("flux_total_requests 120\n"));
+        assert!(body.contains("flux_p95_latency_ms 87\n"));
+        assert!(body.contains("flux_error_rate 2.5\n"));
+        assert!(body.contains("flux_throughput_rps"));
+    }
+
+    #[test]
+    fn test_request_log_marks_sampled_entries() {
+        let path = std::env::temp_dir().join("flux_test_request_log.ndjson");
+        let path = path.to_str().unwrap();
+
+        let result = RequestResult {
+            scenario_name: None,
+            latency_ms: 42,
+            status_code: 200,
+            error: None,
+            request_start_timestamp: Utc::now(),
+            request_end_timestamp: Utc::now(),
+            sse_total_ms: None,
+            wall_latency_ms: None,
+            timing: None,
+            request_id: None,
+            content_encoding: None,
+            is_hook: false,
+        };
+
+        let log = RequestLog::create(path).unwrap();
+        log.write(&result, false).unwrap();
+        log.write(&result, true).unwrap();
+        log.flush().unwrap();
+
+        let content = fs::read_to_string(path).unwrap();
+        let lines: Vec<serde_json::Value> = content
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+
+        assert_eq!(log.written(), 2);
+        assert_eq!(lines[0]["latency_ms"], 42);
+        assert!(lines[0].get("sampled").is_none());
+        assert_eq!(lines[1]["sampled"], true);
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_streaming_json_writer() {
+        let path = std::env::temp_dir().join("flux_test_stream.ndjson");
+        let path = path.to_str().unwrap();
+
+        let result = RequestResult {
+            scenario_name: Some("login".to_string()),
+            latency_ms: 42,
+            status_code: 200,
+            error: None,
+            request_start_timestamp: Utc::now(),
+            request_end_timestamp: Utc::now(),
+            sse_total_ms: None,
+            wall_latency_ms: None,
+            timing: None,
+            request_id: None,
+            content_encoding: None,
+            is_hook: false,
+        };
+
+        let stream = StreamingJsonWriter::create(path).unwrap();
+        stream.write(&result);
+        stream.write(&result);
+        let summary =
+            MetricsSummary::from_results(&[result.clone(), result], Utc::now(), Utc::now());
+        stream.finish(&summary).unwrap();
+
+        let content = fs::read_to_string(path).unwrap();
+        let lines: Vec<serde_json::Value> = content
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["type"], "result");
+        assert_eq!(lines[0]["scenario_name"], "login");
+        assert_eq!(lines[2]["type"], "summary");
+        assert_eq!(lines[2]["total_requests"], 2);
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_merge_reports() {
+        let start = Utc::now();
+        let result = |name: &str, latency_ms: u64, error: Option<&str>| RequestResult {
+            scenario_name: Some(name.to_string()),
+            latency_ms,
+            status_code: if error.is_some() { 0 } else { 200 },
+            error: error.map(|e| RequestError::Other(e.to_string())),
+            request_start_timestamp: start,
+            request_end_timestamp: start,
+            sse_total_ms: None,
+            wall_latency_ms: None,
+            timing: None,
+            request_id: None,
+            content_encoding: None,
+            is_hook: false,
+        };
+
+        let first_results = vec![result("login", 10, None), result("login", 20, None)];
+        let second_results = vec![result("upload", 100, Some("timeout"))];
+
+        let first = Report {
+            summary: MetricsSummary::from_results(
+                &first_results,
+                start,
+                start + chrono::Duration::seconds(1),
+            ),
+            results: first_results,
+            phase_summaries: Vec::new(),
+            request_summaries: Vec::new(),
+        };
+        let second = Report {
+            summary: MetricsSummary::from_results(
+                &second_results,
+                start - chrono::Duration::seconds(1),
+                start + chrono::Duration::seconds(2),
+            ),
+            results: second_results,
+            phase_summaries: Vec::new(),
+            request_summaries: Vec::new(),
+        };
+
+        let merged = Report::merge(vec![first, second]).unwrap();
+
+        assert_eq!(merged.results.len(), 3);
+        assert_eq!(merged.summary.total_requests, 3);
+        assert_eq!(merged.summary.failed_requests, 1);
+        assert_eq!(merged.summary.max_latency_ms, 100);
+        assert_eq!(
+            merged.summary.start_time,
+            start - chrono::Duration::seconds(1)
+        );
+        assert_eq!(
+            merged.summary.end_time,
+            start + chrono::Duration::seconds(2)
+        );
+        assert_eq!(merged.summary.total_duration_secs, 3.0);
+    }
 }
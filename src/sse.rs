@@ -0,0 +1,166 @@
+use crate::metrics::{RequestError, TimeoutKind};
+use reqwest::Response;
+use std::time::Instant;
+use tokio::time::timeout_at;
+
+/// Events read from a Server-Sent Events stream
+#[derive(Debug)]
+pub struct SseEvents {
+    /// `data` of each event, multi-line data joined with newlines
+    pub data: Vec<String>,
+    /// When the first and last events were received
+    pub first_event: Instant,
+    pub last_event: Instant,
+}
+
+/// Incremental parser for the `text/event-stream` format. Bytes are
+/// buffered until a line is complete, so a UTF-8 character split across
+/// chunks is decoded whole.
+#[derive(Debug, Default)]
+struct SseParser {
+    buffer: Vec<u8>,
+    data: Vec<String>,
+}
+
+impl SseParser {
+    /// Feed a chunk, returning the data of every event it completes
+    fn feed(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\n', '\r']);
+
+            if line.is_empty() {
+                // A blank line dispatches the event; comment-only events have no data
+                if !self.data.is_empty() {
+                    events.push(self.data.join("\n"));
+                    self.data.clear();
+                }
+            } else if let Some(value) = line.strip_prefix("data:") {
+                self.data
+                    .push(value.strip_prefix(' ').unwrap_or(value).to_string());
+            }
+            // Comments (": ping") and other fields are ignored
+        }
+
+        events
+    }
+}
+
+/// Read `count` events from `response`, giving up at `deadline`
+pub async fn read_events(
+    mut response: Response,
+    count: usize,
+    deadline: tokio::time::Instant,
+) -> Result<SseEvents, RequestError> {
+    let mut parser = SseParser::default();
+    let mut data = Vec::with_capacity(count);
+    let mut first_event = None;
+    let mut last_event = Instant::now();
+
+    while data.len() < count {
+        let chunk = timeout_at(deadline, response.chunk())
+            .await
+            .map_err(|_| RequestError::Timeout {
+                kind: TimeoutKind::Request,
+            })?
+            .map_err(|e| RequestError::Other(e.to_string()))?;
+
+        let Some(chunk) = chunk else {
+            return Err(RequestError::Other(format!(
+                "SSE stream ended after {} of {} events",
+                data.len(),
+                count
+            )));
+        };
+
+        for event in parser.feed(&chunk) {
+            last_event = Instant::now();
+            first_event.get_or_insert(last_event);
+            if data.len() < count {
+                data.push(event);
+            }
+        }
+    }
+
+    Ok(SseEvents {
+        data,
+        first_event: first_event.unwrap_or(last_event),
+        last_event,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parser_handles_split_chunks() {
+        let mut parser = SseParser::default();
+
+        assert!(parser.feed(b": ping\n\n").is_empty());
+        assert!(parser.feed(b"event: price\ndata: {\"a\"").is_empty());
+        assert_eq!(parser.feed(b": 1}\r\n\r\n"), vec![r#"{"a": 1}"#]);
+        assert_eq!(
+            parser.feed(b"data: line one\ndata:line two\n\ndata: x\n\n"),
+            vec!["line one\nline two", "x"]
+        );
+
+        // "é" split between two chunks
+        let bytes = "data: café\n\n".as_bytes();
+        let split = bytes.len() - 3;
+        assert!(parser.feed(&bytes[..split]).is_empty());
+        assert_eq!(parser.feed(&bytes[split..]), vec!["café"]);
+    }
+
+    #[tokio::test]
+    async fn test_read_events() {
+        use axum::response::sse::{Event, KeepAlive, Sse};
+        use tokio::time::Duration;
+
+        let router = axum::Router::new()
+            .route(
+                "/events",
+                axum::routing::get(|| async {
+                    let events = futures::stream::iter(
+                        ["1", "2", "3"]
+                            .map(|d| Ok::<_, std::convert::Infallible>(Event::default().data(d))),
+                    );
+                    Sse::new(futures::StreamExt::chain(
+                        events,
+                        futures::stream::pending(),
+                    ))
+                }),
+            )
+            .route(
+                "/empty",
+                axum::routing::get(|| async {
+                    Sse::new(futures::stream::pending::<
+                        Result<Event, std::convert::Infallible>,
+                    >())
+                    .keep_alive(KeepAlive::new().interval(Duration::from_millis(50)))
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let target = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let deadline = || tokio::time::Instant::now() + Duration::from_millis(500);
+
+        let response = reqwest::get(format!("{}/events", target)).await.unwrap();
+        let events = read_events(response, 2, deadline()).await.unwrap();
+        assert_eq!(events.data, vec!["1", "2"]);
+
+        let response = reqwest::get(format!("{}/empty", target)).await.unwrap();
+        let err = read_events(response, 1, deadline()).await.unwrap_err();
+        assert_eq!(
+            err,
+            RequestError::Timeout {
+                kind: TimeoutKind::Request
+            }
+        );
+    }
+}
@@ -0,0 +1,231 @@
+/*
+ * Minimal canvas charts for the Flux HTML report.
+ *
+ * Inlined into the generated report so it renders offline without loading
+ * anything from a CDN. Supports the chart types the reports use: bar, line,
+ * doughnut and bars with error whiskers.
+ */
+(function (global) {
+    'use strict';
+
+    var FONT = '12px -apple-system, BlinkMacSystemFont, "Segoe UI", Roboto, sans-serif';
+    var AXIS_COLOR = '#999';
+    var GRID_COLOR = '#e5e7eb';
+    var TEXT_COLOR = '#666';
+    var PADDING = { top: 10, right: 10, bottom: 30, left: 50 };
+
+    /* Size the canvas to its container, accounting for high-DPI displays */
+    function setup(canvas) {
+        var parent = canvas.parentElement;
+        var width = parent.clientWidth - 40;
+        var height = parent.clientHeight - 40;
+        var ratio = global.devicePixelRatio || 1;
+
+        canvas.width = width * ratio;
+        canvas.height = height * ratio;
+        canvas.style.width = width + 'px';
+        canvas.style.height = height + 'px';
+
+        var ctx = canvas.getContext('2d');
+        ctx.scale(ratio, ratio);
+        ctx.font = FONT;
+        return { ctx: ctx, width: width, height: height };
+    }
+
+    /* Round the axis maximum up to a readable number */
+    function niceMax(value) {
+        if (value <= 0) {
+            return 1;
+        }
+        var magnitude = Math.pow(10, Math.floor(Math.log10(value)));
+        var steps = [1, 2, 2.5, 5, 10];
+        for (var i = 0; i < steps.length; i++) {
+            if (steps[i] * magnitude >= value) {
+                return steps[i] * magnitude;
+            }
+        }
+        return 10 * magnitude;
+    }
+
+    /* Draw the y axis grid and labels, returning the plot area */
+    function drawAxes(c, max) {
+        var plot = {
+            x: PADDING.left,
+            y: PADDING.top,
+            width: c.width - PADDING.left - PADDING.right,
+            height: c.height - PADDING.top - PADDING.bottom
+        };
+        var ticks = 5;
+
+        c.ctx.textAlign = 'right';
+        c.ctx.textBaseline = 'middle';
+        for (var i = 0; i <= ticks; i++) {
+            var value = max * i / ticks;
+            var y = plot.y + plot.height - plot.height * i / ticks;
+
+            c.ctx.strokeStyle = GRID_COLOR;
+            c.ctx.beginPath();
+            c.ctx.moveTo(plot.x, y);
+            c.ctx.lineTo(plot.x + plot.width, y);
+            c.ctx.stroke();
+
+            c.ctx.fillStyle = TEXT_COLOR;
+            c.ctx.fillText(Number(value.toFixed(2)).toString(), plot.x - 8, y);
+        }
+
+        c.ctx.strokeStyle = AXIS_COLOR;
+        c.ctx.beginPath();
+        c.ctx.moveTo(plot.x, plot.y);
+        c.ctx.lineTo(plot.x, plot.y + plot.height);
+        c.ctx.lineTo(plot.x + plot.width, plot.y + plot.height);
+        c.ctx.stroke();
+
+        return plot;
+    }
+
+    function bar(canvas, options) {
+        var c = setup(canvas);
+        var values = options.values;
+        var max = niceMax(Math.max.apply(null, values.concat([0])));
+        var plot = drawAxes(c, max);
+        var slot = plot.width / Math.max(values.length, 1);
+
+        c.ctx.textAlign = 'center';
+        c.ctx.textBaseline = 'top';
+        values.forEach(function (value, i) {
+            var barHeight = plot.height * value / max;
+            var x = plot.x + slot * i + slot * 0.15;
+
+            c.ctx.fillStyle = options.color;
+            c.ctx.fillRect(x, plot.y + plot.height - barHeight, slot * 0.7, barHeight);
+
+            c.ctx.fillStyle = TEXT_COLOR;
+            c.ctx.fillText(options.labels[i], plot.x + slot * (i + 0.5), plot.y + plot.height + 8);
+        });
+    }
+
+    /* Bars of `values` with whiskers spanning value +/- `errors` */
+    function errorBars(canvas, options) {
+        var c = setup(canvas);
+        var values = options.values;
+        var tops = values.map(function (v, i) { return v + options.errors[i]; });
+        var max = niceMax(Math.max.apply(null, tops.concat([0])));
+        var plot = drawAxes(c, max);
+        var slot = plot.width / Math.max(values.length, 1);
+        var toY = function (v) { return plot.y + plot.height - plot.height * v / max; };
+
+        c.ctx.textAlign = 'center';
+        c.ctx.textBaseline = 'top';
+        values.forEach(function (value, i) {
+            var center = plot.x + slot * (i + 0.5);
+            var low = Math.max(value - options.errors[i], 0);
+            var high = value + options.errors[i];
+
+            c.ctx.fillStyle = options.color;
+            c.ctx.fillRect(plot.x + slot * i + slot * 0.2, toY(value), slot * 0.6, plot.y + plot.height - toY(value));
+
+            c.ctx.strokeStyle = options.errorColor;
+            c.ctx.lineWidth = 2;
+            c.ctx.beginPath();
+            c.ctx.moveTo(center, toY(low));
+            c.ctx.lineTo(center, toY(high));
+            c.ctx.moveTo(center - slot * 0.1, toY(low));
+            c.ctx.lineTo(center + slot * 0.1, toY(low));
+            c.ctx.moveTo(center - slot * 0.1, toY(high));
+            c.ctx.lineTo(center + slot * 0.1, toY(high));
+            c.ctx.stroke();
+
+            c.ctx.fillStyle = TEXT_COLOR;
+            c.ctx.fillText(options.labels[i], center, plot.y + plot.height + 8);
+        });
+    }
+
+    function line(canvas, options) {
+        var c = setup(canvas);
+        var values = options.values;
+        var max = niceMax(Math.max.apply(null, values.concat([0])));
+        var plot = drawAxes(c, max);
+        var step = plot.width / Math.max(values.length - 1, 1);
+
+        if (values.length === 0) {
+            return;
+        }
+
+        c.ctx.beginPath();
+        values.forEach(function (value, i) {
+            var x = plot.x + step * i;
+            var y = plot.y + plot.height - plot.height * value / max;
+            if (i === 0) {
+                c.ctx.moveTo(x, y);
+            } else {
+                c.ctx.lineTo(x, y);
+            }
+        });
+        c.ctx.strokeStyle = options.color;
+        c.ctx.lineWidth = 2;
+        c.ctx.stroke();
+
+        c.ctx.lineTo(plot.x + step * (values.length - 1), plot.y + plot.height);
+        c.ctx.lineTo(plot.x, plot.y + plot.height);
+        c.ctx.closePath();
+        c.ctx.fillStyle = options.fill;
+        c.ctx.fill();
+
+        // Labelled dashed lines, e.g. where each test phase starts
+        c.ctx.setLineDash([4, 4]);
+        c.ctx.strokeStyle = '#555';
+        c.ctx.lineWidth = 1;
+        c.ctx.fillStyle = '#555';
+        c.ctx.textAlign = 'left';
+        c.ctx.textBaseline = 'top';
+        (options.markers || []).forEach(function (marker) {
+            var x = plot.x + step * marker.index;
+            c.ctx.beginPath();
+            c.ctx.moveTo(x, plot.y);
+            c.ctx.lineTo(x, plot.y + plot.height);
+            c.ctx.stroke();
+            c.ctx.fillText(marker.label, x + 4, plot.y + 2);
+        });
+        c.ctx.setLineDash([]);
+    }
+
+    function doughnut(canvas, options) {
+        var c = setup(canvas);
+        var values = options.values;
+        var total = values.reduce(function (sum, v) { return sum + v; }, 0);
+        var legendHeight = 30;
+        var radius = Math.min(c.width, c.height - legendHeight) / 2 - 10;
+        var cx = c.width / 2;
+        var cy = (c.height - legendHeight) / 2;
+        var angle = -Math.PI / 2;
+
+        values.forEach(function (value, i) {
+            var sweep = total > 0 ? Math.PI * 2 * value / total : 0;
+            c.ctx.beginPath();
+            c.ctx.arc(cx, cy, radius, angle, angle + sweep);
+            c.ctx.arc(cx, cy, radius * 0.55, angle + sweep, angle, true);
+            c.ctx.closePath();
+            c.ctx.fillStyle = options.colors[i % options.colors.length];
+            c.ctx.fill();
+            c.ctx.strokeStyle = '#fff';
+            c.ctx.lineWidth = 2;
+            c.ctx.stroke();
+            angle += sweep;
+        });
+
+        // Legend centered below the chart
+        var itemWidth = 90;
+        var x = cx - itemWidth * values.length / 2;
+        c.ctx.textAlign = 'left';
+        c.ctx.textBaseline = 'middle';
+        options.labels.forEach(function (label, i) {
+            var left = x + itemWidth * i;
+            c.ctx.fillStyle = options.colors[i % options.colors.length];
+            c.ctx.fillRect(left, c.height - legendHeight / 2 - 6, 12, 12);
+            c.ctx.fillStyle = TEXT_COLOR;
+            c.ctx.fillText(label + ' (' + values[i] + ')', left + 18, c.height - legendHeight / 2);
+        });
+    }
+
+    global.FluxCharts = { bar: bar, errorBars: errorBars, line: line, doughnut: doughnut };
+})(window);
@@ -0,0 +1,265 @@
+use crate::metrics::TimingBreakdown;
+use anyhow::{bail, Result};
+use hyper::client::connect::{Connected, Connection};
+use hyper::service::Service;
+use hyper::{Body, Client, Uri};
+use reqwest::{Request, Response};
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpStream;
+use tokio_native_tls::{native_tls, TlsConnector, TlsStream};
+
+/// HTTP/1 client timing the DNS lookup, TCP connect and TLS handshake of
+/// each new connection and the time to first byte of each request
+pub struct TimedClient {
+    client: Client<TimingConnector>,
+}
+
+impl TimedClient {
+    /// Client resolving the hosts in `resolve` to the given IPs instead of
+    /// looking them up
+    pub fn new(resolve: HashMap<String, IpAddr>) -> Result<Self> {
+        let tls = TlsConnector::from(native_tls::TlsConnector::new()?);
+        Ok(Self {
+            client: Client::builder().build(TimingConnector {
+                resolve: Arc::new(resolve),
+                tls,
+            }),
+        })
+    }
+
+    /// Send a request, returning the response with its timing. Requests on
+    /// a reused connection report zero DNS, connect and TLS time. Streamed
+    /// (multipart) bodies are not supported and responses are not
+    /// decompressed.
+    pub async fn send(
+        &self,
+        request: Request,
+        default_timeout: Duration,
+    ) -> Result<(Response, TimingBreakdown)> {
+        let uri: Uri = request.url().as_str().parse()?;
+        let timeout = request.timeout().copied().unwrap_or(default_timeout);
+
+        let body = match request.body() {
+            Some(body) => match body.as_bytes() {
+                Some(bytes) => Body::from(bytes.to_vec()),
+                None => bail!("Streamed request bodies are not supported with detailed_timing"),
+            },
+            None => Body::empty(),
+        };
+        let mut http_request = hyper::Request::new(body);
+        *http_request.method_mut() = request.method().clone();
+        *http_request.uri_mut() = uri;
+        *http_request.headers_mut() = request.headers().clone();
+
+        let start = Instant::now();
+        let response = tokio::time::timeout(timeout, self.client.request(http_request)).await??;
+        let elapsed = start.elapsed();
+
+        // Only the first response on a connection claims its setup time
+        let fresh = response
+            .extensions()
+            .get::<ConnectionTiming>()
+            .filter(|timing| timing.fresh.swap(false, Ordering::Relaxed))
+            .cloned();
+        let new_connection = fresh.is_some();
+        let connection = fresh.unwrap_or_default();
+        let setup = connection.dns + connection.connect + connection.tls;
+
+        let timing = TimingBreakdown {
+            dns_ms: Some(millis(connection.dns)),
+            connect_ms: Some(millis(connection.connect)),
+            tls_ms: Some(millis(connection.tls)),
+            ttfb_ms: Some(millis(elapsed.saturating_sub(setup))),
+            new_connection: Some(new_connection),
+            ..TimingBreakdown::default()
+        };
+        Ok((Response::from(response), timing))
+    }
+}
+
+fn millis(duration: Duration) -> u64 {
+    duration.as_millis() as u64
+}
+
+/// Setup time of a connection, attached to every response it carries
+#[derive(Debug, Clone, Default)]
+struct ConnectionTiming {
+    dns: Duration,
+    connect: Duration,
+    tls: Duration,
+    /// Set until the first response on the connection claims the timing
+    fresh: Arc<AtomicBool>,
+}
+
+/// Connects over TCP, and TLS for `https`, timing each step
+#[derive(Clone)]
+struct TimingConnector {
+    resolve: Arc<HashMap<String, IpAddr>>,
+    tls: TlsConnector,
+}
+
+impl Service<Uri> for TimingConnector {
+    type Response = TimedConnection;
+    type Error = anyhow::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<TimedConnection>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let connector = self.clone();
+        Box::pin(async move { connector.connect(uri).await })
+    }
+}
+
+impl TimingConnector {
+    async fn connect(self, uri: Uri) -> Result<TimedConnection> {
+        let Some(host) = uri.host() else {
+            bail!("URL '{}' has no host", uri);
+        };
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        let https = uri.scheme_str() == Some("https");
+        let port = uri.port_u16().unwrap_or(if https { 443 } else { 80 });
+
+        let start = Instant::now();
+        let addr = match self.resolve.get(host) {
+            Some(ip) => SocketAddr::new(*ip, port),
+            None => match tokio::net::lookup_host((host, port)).await?.next() {
+                Some(addr) => addr,
+                None => bail!("No addresses found for '{}'", host),
+            },
+        };
+        let dns = start.elapsed();
+
+        let start = Instant::now();
+        let tcp = TcpStream::connect(addr).await?;
+        tcp.set_nodelay(true)?;
+        let connect = start.elapsed();
+
+        let start = Instant::now();
+        let stream = if https {
+            Stream::Tls(Box::new(self.tls.connect(host, tcp).await?))
+        } else {
+            Stream::Plain(tcp)
+        };
+        let tls = start.elapsed();
+
+        Ok(TimedConnection {
+            stream,
+            timing: ConnectionTiming {
+                dns,
+                connect,
+                tls,
+                fresh: Arc::new(AtomicBool::new(true)),
+            },
+        })
+    }
+}
+
+enum Stream {
+    Plain(TcpStream),
+    Tls(Box<TlsStream<TcpStream>>),
+}
+
+/// Plain or TLS stream usable as a hyper client connection
+struct TimedConnection {
+    stream: Stream,
+    timing: ConnectionTiming,
+}
+
+impl Connection for TimedConnection {
+    fn connected(&self) -> Connected {
+        Connected::new().extra(self.timing.clone())
+    }
+}
+
+impl AsyncRead for TimedConnection {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        match self.stream {
+            Stream::Plain(ref mut tcp) => Pin::new(tcp).poll_read(cx, buf),
+            Stream::Tls(ref mut tls) => Pin::new(tls).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for TimedConnection {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        match self.stream {
+            Stream::Plain(ref mut tcp) => Pin::new(tcp).poll_write(cx, buf),
+            Stream::Tls(ref mut tls) => Pin::new(tls).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        match self.stream {
+            Stream::Plain(ref mut tcp) => Pin::new(tcp).poll_flush(cx),
+            Stream::Tls(ref mut tls) => Pin::new(tls).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        match self.stream {
+            Stream::Plain(ref mut tcp) => Pin::new(tcp).poll_shutdown(cx),
+            Stream::Tls(ref mut tls) => Pin::new(tls).poll_shutdown(cx),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_timing_of_new_and_reused_connections() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let router = axum::Router::new().route(
+            "/",
+            axum::routing::get(|| async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                "ok"
+            }),
+        );
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let client =
+            TimedClient::new(HashMap::from([("flux.invalid".to_string(), addr.ip())])).unwrap();
+        let url = format!("http://flux.invalid:{}/", addr.port());
+        let request = || reqwest::Client::new().get(&url).build().unwrap();
+
+        let (response, first) = client
+            .send(request(), Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(response.text().await.unwrap(), "ok");
+        assert!(first.ttfb_ms.unwrap() >= 50);
+        assert_eq!(first.tls_ms, Some(0));
+        assert_eq!(first.new_connection, Some(true));
+
+        let (_, second) = client
+            .send(request(), Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(second.dns_ms, Some(0));
+        assert_eq!(second.connect_ms, Some(0));
+        assert_eq!(second.new_connection, Some(false));
+        assert!(second.ttfb_ms.unwrap() >= 50);
+    }
+}
@@ -1,16 +1,53 @@
+use crate::baseline::MetricDelta;
 use crate::config::Config;
-use crate::metrics::{LiveMetrics, MetricsSummary};
+use crate::executor::DryRunStep;
+use crate::metrics::{LiveMetrics, MetricsSummary, WorkerStats, SLA_ACCEPTABLE_PERCENT};
+use chrono::{DateTime, Utc};
 use colored::*;
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+/// Most per-worker rows shown; above this only the slowest and fastest
+/// workers are listed
+const MAX_WORKER_ROWS: usize = 20;
+
+/// Error rate (percent) above which a worker's row is shown in red
+const WORKER_ERROR_ALERT_PERCENT: f64 = 10.0;
 
 /// Terminal UI for displaying load test progress
 pub struct TerminalUI {
     progress_bar: ProgressBar,
+    /// One row per worker, up to `MAX_WORKER_ROWS`
+    worker_bars: Vec<ProgressBar>,
+    alert_bar: ProgressBar,
+    duration_secs: u64,
+    /// Plain line output without progress bars, for CI and non-TTY runs
+    is_quiet: bool,
+    /// Print nothing to stdout, which carries the `--events` stream
+    is_hidden: bool,
+    last_progress_pct: AtomicU64,
+    alert_shown: AtomicBool,
+    /// Decimal places shown for latencies
+    latency_precision: usize,
 }
 
 impl TerminalUI {
-    /// Create a new terminal UI
-    pub fn new(duration_secs: u64) -> Self {
+    /// Create a new terminal UI. Quiet mode is used when `quiet` is set,
+    /// stdout is not a TTY or `TERM=dumb`; `FORCE_COLOR=1` keeps colors on.
+    /// Below the progress bar a row per worker is shown for up to
+    /// `concurrency` workers.
+    pub fn new(duration_secs: u64, quiet: bool, concurrency: usize) -> Self {
+        let is_quiet = quiet
+            || !std::io::stdout().is_terminal()
+            || std::env::var("TERM").is_ok_and(|term| term == "dumb");
+
+        if std::env::var("FORCE_COLOR").is_ok_and(|v| v == "1") {
+            colored::control::set_override(true);
+        } else if is_quiet {
+            colored::control::set_override(false);
+        }
+
         let progress_bar = ProgressBar::new(duration_secs);
 
         progress_bar.set_style(
@@ -20,11 +57,72 @@ impl TerminalUI {
                 .progress_chars("█▓▒░ "),
         );
 
-        Self { progress_bar }
+        // Worker rows and a status line for live alerts below the progress bar
+        let multi_progress = MultiProgress::new();
+        if is_quiet {
+            multi_progress.set_draw_target(ProgressDrawTarget::hidden());
+        }
+        let progress_bar = multi_progress.add(progress_bar);
+        let worker_rows = if is_quiet {
+            0
+        } else {
+            concurrency.min(MAX_WORKER_ROWS)
+        };
+        let worker_bars = (0..worker_rows)
+            .map(|_| {
+                let bar = multi_progress.add(ProgressBar::new_spinner());
+                bar.set_style(
+                    ProgressStyle::default_spinner()
+                        .template("{msg}")
+                        .expect("Failed to set worker bar template"),
+                );
+                bar
+            })
+            .collect();
+        let alert_bar = multi_progress.add(ProgressBar::new_spinner());
+        alert_bar.set_style(
+            ProgressStyle::default_spinner()
+                .template("{msg}")
+                .expect("Failed to set alert bar template"),
+        );
+
+        Self {
+            progress_bar,
+            worker_bars,
+            alert_bar,
+            duration_secs,
+            is_quiet,
+            is_hidden: false,
+            last_progress_pct: AtomicU64::new(0),
+            alert_shown: AtomicBool::new(false),
+            latency_precision: 0,
+        }
+    }
+
+    /// Show latencies with `latency_precision` decimal places
+    pub fn with_latency_precision(mut self, latency_precision: usize) -> Self {
+        self.latency_precision = latency_precision;
+        self
+    }
+
+    /// Keep stdout free for the event stream: the banner, summaries and
+    /// messages are not shown, errors still go to stderr
+    pub fn hidden(mut self, hidden: bool) -> Self {
+        self.is_hidden = hidden;
+        self
+    }
+
+    /// Latency in milliseconds at the configured precision
+    fn latency(&self, ms: f64) -> String {
+        format!("{:.*}ms", self.latency_precision, ms)
     }
 
     /// Display initial banner
     pub fn display_banner(&self, config: &Config, duration_secs: u64) {
+        if self.is_hidden {
+            return;
+        }
+
         println!("\n{}", "═".repeat(70).bright_cyan());
         println!("{}", "⚡ Flux Load Test Started".bright_white().bold());
         println!("{}", "═".repeat(70).bright_cyan());
@@ -33,11 +131,55 @@ impl TerminalUI {
             println!("{:<20} : {}", "Target".bright_yellow(), target);
         }
 
-        println!(
-            "{:<20} : {} workers",
-            "Concurrency".bright_yellow(),
-            config.concurrency
-        );
+        if !config.requests.is_empty() {
+            let requests: Vec<String> = config
+                .requests
+                .iter()
+                .map(|r| format!("{} ({})", r.name, r.weight))
+                .collect();
+            println!(
+                "{:<20} : {}",
+                "Requests".bright_yellow(),
+                requests.join(", ")
+            );
+        } else if config.is_simple_mode() && config.grpc.is_none() {
+            println!(
+                "{:<20} : {}",
+                "Method".bright_yellow(),
+                config.resolved_method()
+            );
+        }
+
+        if let Some(grpc) = &config.grpc {
+            println!(
+                "{:<20} : {} {}/{}",
+                "gRPC".bright_yellow(),
+                grpc.endpoint,
+                grpc.service,
+                grpc.method
+            );
+        }
+
+        if let Some(ref phases) = config.phases {
+            let phases: Vec<String> = phases
+                .iter()
+                .map(|p| format!("{} ({} × {})", p.name, p.concurrency, p.duration))
+                .collect();
+            println!("{:<20} : {}", "Phases".bright_yellow(), phases.join(" → "));
+        } else if config.is_open_model() {
+            println!(
+                "{:<20} : {}/s (max {} outstanding)",
+                "Arrival Rate".bright_yellow(),
+                config.arrival_rate.unwrap_or_default(),
+                config.resolved_max_outstanding()
+            );
+        } else {
+            println!(
+                "{:<20} : {} workers",
+                "Concurrency".bright_yellow(),
+                config.concurrency
+            );
+        }
         println!("{:<20} : {}s", "Duration".bright_yellow(), duration_secs);
         println!(
             "{:<20} : {}",
@@ -55,6 +197,18 @@ impl TerminalUI {
             );
         }
 
+        if let Some(seed) = config.seed {
+            println!("{:<20} : {}", "Seed".bright_yellow(), seed);
+        }
+
+        if let Some(port) = config.dashboard_port {
+            println!(
+                "{:<20} : http://localhost:{}",
+                "Dashboard".bright_yellow(),
+                port
+            );
+        }
+
         println!("{}", "═".repeat(70).bright_cyan());
         println!();
     }
@@ -64,9 +218,10 @@ impl TerminalUI {
         self.progress_bar.set_position(elapsed_secs);
 
         let message = format!(
-            "RPS: {:.0} | Avg Latency: {:.0}ms | Errors: {} ({:.1}%)",
+            "RPS: {:.0} | Avg Latency: {} | P95: {} | Errors: {} ({:.1}%)",
             live_metrics.current_rps,
-            live_metrics.avg_latency_ms,
+            self.latency(live_metrics.avg_latency_ms),
+            self.latency(live_metrics.p95_latency_ms as f64),
             live_metrics.error_count,
             if live_metrics.total_requests > 0 {
                 (live_metrics.error_count as f64 / live_metrics.total_requests as f64) * 100.0
@@ -75,16 +230,111 @@ impl TerminalUI {
             }
         );
 
+        if self.is_quiet {
+            if let Some(pct) = self.progress_mark(elapsed_secs) {
+                println!(
+                    "[{:>3}%] {}/{}s | {}",
+                    pct, elapsed_secs, self.duration_secs, message
+                );
+            }
+            return;
+        }
+
         self.progress_bar.set_message(message);
     }
 
+    /// Show each worker's last latency, requests and errors, yellow above
+    /// `p95_latency_ms` and red above 10% errors
+    pub fn update_workers(&self, workers: &[WorkerStats], p95_latency_ms: u64) {
+        let rows = worker_rows(workers, self.worker_bars.len());
+        for (i, bar) in self.worker_bars.iter().enumerate() {
+            let Some(stats) = rows.get(i) else {
+                bar.set_message("");
+                continue;
+            };
+
+            let row = format!(
+                "  Worker {:>4} | Last: {:>8} | Requests: {:>7} | Errors: {} ({:.1}%)",
+                stats.worker_id,
+                self.latency(stats.last_latency_ms as f64),
+                stats.total_requests,
+                stats.error_count,
+                stats.error_rate()
+            );
+            let row = if stats.error_rate() > WORKER_ERROR_ALERT_PERCENT {
+                row.bright_red().to_string()
+            } else if stats.last_latency_ms > p95_latency_ms {
+                row.bright_yellow().to_string()
+            } else {
+                row
+            };
+            bar.set_message(row);
+        }
+    }
+
+    /// Next 10% completion mark reached since the last call, if any
+    fn progress_mark(&self, elapsed_secs: u64) -> Option<u64> {
+        if self.duration_secs == 0 {
+            return None;
+        }
+
+        let pct = (elapsed_secs * 100 / self.duration_secs).min(100) / 10 * 10;
+        let last = self.last_progress_pct.fetch_max(pct, Ordering::Relaxed);
+        (pct > last).then_some(pct)
+    }
+
+    /// Show a warning below the progress bar while rolling P99 exceeds the threshold
+    pub fn show_p99_alert(&self, p99_ms: u64, threshold_ms: u64) {
+        let message = format!(
+            "⚠ Rolling P99 latency {}ms exceeds alert threshold {}ms",
+            p99_ms, threshold_ms
+        );
+
+        if self.is_quiet {
+            // Only log when the alert starts, not on every check
+            if !self.alert_shown.swap(true, Ordering::Relaxed) {
+                eprintln!("{}", message);
+            }
+            return;
+        }
+
+        self.alert_bar
+            .set_message(message.bright_red().bold().blink().to_string());
+    }
+
+    /// Clear the P99 warning once latency recovers
+    pub fn clear_p99_alert(&self) {
+        self.alert_shown.store(false, Ordering::Relaxed);
+        self.alert_bar.set_message("");
+    }
+
     /// Finish progress bar
     pub fn finish_progress(&self) {
+        if self.is_quiet {
+            println!("Test completed");
+        }
         self.progress_bar.finish_with_message("Test completed");
+        for bar in &self.worker_bars {
+            bar.finish_and_clear();
+        }
+        self.alert_bar.finish_and_clear();
+    }
+
+    /// Remove the progress bars without drawing them
+    pub fn hide_progress(&self) {
+        self.progress_bar.finish_and_clear();
+        for bar in &self.worker_bars {
+            bar.finish_and_clear();
+        }
+        self.alert_bar.finish_and_clear();
     }
 
     /// Display final summary
     pub fn display_summary(&self, summary: &MetricsSummary) {
+        if self.is_hidden {
+            return;
+        }
+
         println!("\n{}", "═".repeat(70).bright_cyan());
         println!("{}", "📊 Final Summary".bright_white().bold());
         println!("{}", "═".repeat(70).bright_cyan());
@@ -127,54 +377,322 @@ impl TerminalUI {
                 format!("{:.2}", summary.error_rate).bright_green()
             }
         );
+        if summary.timeout_count > 0 {
+            println!(
+                "  {:<25} : {}",
+                "Timeouts".bright_white(),
+                summary.timeout_count.to_string().bright_red()
+            );
+        }
+        if summary.skipped_requests > 0 {
+            println!(
+                "  {:<25} : {}",
+                "Skipped Steps".bright_white(),
+                summary.skipped_requests
+            );
+        }
+        if summary.rate_limited_count > 0 {
+            println!(
+                "  {:<25} : {}",
+                "Rate Limited (429)".bright_white(),
+                summary.rate_limited_count.to_string().bright_yellow()
+            );
+        }
+        if summary.aborted_chains > 0 {
+            println!(
+                "  {:<25} : {}",
+                "Aborted Chains".bright_white(),
+                summary.aborted_chains.to_string().bright_red()
+            );
+        }
+        if summary.throttled_requests > 0 {
+            println!(
+                "  {:<25} : {}",
+                "Throttled (All 429s)".bright_white(),
+                summary.throttled_requests.to_string().bright_yellow()
+            );
+        }
+        if summary.chain_timeouts > 0 {
+            println!(
+                "  {:<25} : {}",
+                "Chain Timeouts".bright_white(),
+                summary.chain_timeouts.to_string().bright_red()
+            );
+        }
+        if summary.hook_requests > 0 {
+            let hooks = format!(
+                "{} ({} failed)",
+                summary.hook_requests, summary.failed_hook_requests
+            );
+            println!("  {:<25} : {}", "Hook Requests".bright_white(), hooks);
+        }
+        if summary.gzip_responses {
+            println!("  {:<25} : yes", "Compressed Responses".bright_white());
+        }
+        if let (Some(count), Some(percent)) = (summary.over_sla_count, summary.over_sla_percent) {
+            let over = format!("{} ({:.2}%)", count, percent);
+            println!(
+                "  {:<25} : {}",
+                "Over SLA".bright_white(),
+                if percent > SLA_ACCEPTABLE_PERCENT {
+                    over.bright_red()
+                } else {
+                    over.bright_green()
+                }
+            );
+        }
+        if let Some(apdex) = summary.apdex {
+            let score = format!("{:.2}", apdex);
+            println!(
+                "  {:<25} : {}",
+                "Apdex".bright_white(),
+                if apdex >= 0.94 {
+                    score.bright_green()
+                } else if apdex >= 0.7 {
+                    score.bright_yellow()
+                } else {
+                    score.bright_red()
+                }
+            );
+        }
+        if let Some(peak) = summary.peak_concurrency {
+            println!("  {:<25} : {}", "Peak Concurrency".bright_white(), peak);
+        }
+        if summary.clamped_histogram_values > 0 {
+            println!(
+                "  {:<25} : {}",
+                "Clamped Latencies".bright_white(),
+                summary.clamped_histogram_values.to_string().bright_yellow()
+            );
+        }
+        if summary.dns_cache_hits > 0 {
+            println!(
+                "  {:<25} : {}",
+                "DNS Cache Hits".bright_white(),
+                summary.dns_cache_hits
+            );
+        }
+        if let Some(max_outstanding) = summary.max_outstanding {
+            println!(
+                "  {:<25} : {}",
+                "Max Outstanding".bright_white(),
+                max_outstanding
+            );
+        }
+        if let Some(dropped) = summary.dropped_iterations.filter(|&d| d > 0) {
+            println!(
+                "  {:<25} : {}",
+                "Dropped Iterations".bright_white(),
+                dropped.to_string().bright_red()
+            );
+        }
         println!(
             "  {:<25} : {:.2}s",
             "Total Duration".bright_white(),
             summary.total_duration_secs
         );
 
-        // Latency percentiles
+        // Error breakdown
+        if !summary.error_breakdown.is_empty() {
+            println!("\n{}", "Error Breakdown:".bright_green().bold());
+
+            let mut breakdown: Vec<_> = summary.error_breakdown.iter().collect();
+            breakdown.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+
+            for (kind, count) in breakdown {
+                println!(
+                    "  {:<25} : {}",
+                    kind.bright_white(),
+                    count.to_string().bright_red()
+                );
+            }
+        }
+
+        if let (Some(first), Some(last)) = (summary.first_error_time, summary.last_error_time) {
+            let format = |time: DateTime<Utc>| time.format("%H:%M:%S%.3f").to_string();
+            println!(
+                "  {:<25} : {} → {}",
+                "First / Last Error".bright_white(),
+                format(first),
+                format(last)
+            );
+        }
+        if !summary.sample_errors.is_empty() {
+            println!("\n{}", "Sample Errors:".bright_green().bold());
+            for message in &summary.sample_errors {
+                println!("  {}", message.bright_red());
+            }
+        }
+
+        // Latency percentiles; mean, median and std dev keep at least two
+        // decimals since they are rarely whole milliseconds
         println!("\n{}", "Latency Percentiles:".bright_green().bold());
+        let precise = |ms: f64| format!("{:.*}ms", self.latency_precision.max(2), ms);
+        for (label, value) in [
+            ("Min", self.latency(summary.min_latency_ms as f64)),
+            ("P50", self.latency(summary.p50_latency_ms as f64)),
+            ("P90", self.latency(summary.p90_latency_ms as f64)),
+            ("P95", self.latency(summary.p95_latency_ms as f64)),
+            ("P99", self.latency(summary.p99_latency_ms as f64)),
+            ("Max", self.latency(summary.max_latency_ms as f64)),
+            ("Mean", precise(summary.mean_latency_ms)),
+            ("Median", precise(summary.median_latency_ms)),
+            ("Std Dev", precise(summary.stddev_latency_ms)),
+        ] {
+            println!("  {:<25} : {}", label.bright_white(), value);
+        }
+
+        if !summary.timing_percentiles.is_empty() {
+            println!(
+                "\n{}",
+                "Timing Breakdown (P50 / P95 / P99):".bright_green().bold()
+            );
+            for phase in &summary.timing_percentiles {
+                println!(
+                    "  {:<25} : {} / {} / {}",
+                    phase.phase.to_uppercase().bright_white(),
+                    self.latency(phase.p50_ms as f64),
+                    self.latency(phase.p95_ms as f64),
+                    self.latency(phase.p99_ms as f64)
+                );
+            }
+        }
+
+        println!("\n{}", "═".repeat(70).bright_cyan());
+        println!();
+    }
+
+    /// Display one row per phase of a phased run
+    pub fn display_phase_summaries(&self, phases: &[(String, MetricsSummary)]) {
+        if self.is_hidden {
+            return;
+        }
+        display_breakdown("Phases:", "Phase", phases);
+    }
+
+    /// Display one row per request of a weighted request mix
+    pub fn display_request_summaries(&self, requests: &[(String, MetricsSummary)]) {
+        if self.is_hidden {
+            return;
+        }
+        display_breakdown("Requests:", "Request", requests);
+    }
+
+    /// Display comparison against a baseline report
+    pub fn display_baseline_comparison(&self, deltas: &[MetricDelta], tolerance_pct: f64) {
+        if self.is_hidden {
+            return;
+        }
+
+        println!("\n{}", "═".repeat(70).bright_cyan());
         println!(
-            "  {:<25} : {}ms",
-            "Min".bright_white(),
-            summary.min_latency_ms
-        );
-        println!(
-            "  {:<25} : {}ms",
-            "P50 (Median)".bright_white(),
-            summary.p50_latency_ms
-        );
-        println!(
-            "  {:<25} : {}ms",
-            "P90".bright_white(),
-            summary.p90_latency_ms
-        );
-        println!(
-            "  {:<25} : {}ms",
-            "P95".bright_white(),
-            summary.p95_latency_ms
-        );
-        println!(
-            "  {:<25} : {}ms",
-            "P99".bright_white(),
-            summary.p99_latency_ms
+            "{}",
+            format!("📉 Baseline Comparison (tolerance {:.1}%)", tolerance_pct)
+                .bright_white()
+                .bold()
         );
+        println!("{}", "═".repeat(70).bright_cyan());
+
         println!(
-            "  {:<25} : {}ms",
-            "Max".bright_white(),
-            summary.max_latency_ms
+            "  {:<25} {:>12} {:>12} {:>12}",
+            "Metric".bright_white(),
+            "Baseline",
+            "Current",
+            "Change"
         );
+
+        for delta in deltas {
+            let change = format!("{:+.2}%", delta.change_pct);
+            println!(
+                "  {:<25} {:>12.2} {:>12.2} {:>12}",
+                delta.name.bright_white(),
+                delta.baseline,
+                delta.current,
+                if delta.regressed {
+                    change.bright_red()
+                } else {
+                    change.bright_green()
+                }
+            );
+        }
+
+        println!("\n{}", "═".repeat(70).bright_cyan());
+        println!();
+    }
+
+    /// Display a metric-by-metric comparison of two reports
+    pub fn display_report_comparison(
+        &self,
+        deltas: &[MetricDelta],
+        baseline_name: &str,
+        current_name: &str,
+    ) {
+        println!("\n{}", "═".repeat(82).bright_cyan());
+        println!("{}", "🔍 Report Comparison".bright_white().bold());
+        println!("{}", "═".repeat(82).bright_cyan());
+        println!("  {:<10} : {}", "Baseline".bright_yellow(), baseline_name);
+        println!("  {:<10} : {}\n", "Current".bright_yellow(), current_name);
+
         println!(
-            "  {:<25} : {:.2}ms",
-            "Mean".bright_white(),
-            summary.mean_latency_ms
+            "  {:<25} {:>12} {:>12} {:>12} {:>12}",
+            "Metric".bright_white(),
+            "Baseline",
+            "Current",
+            "Delta",
+            "Change"
         );
 
-        println!("\n{}", "═".repeat(70).bright_cyan());
+        for delta in deltas {
+            let change = format!(
+                "{:>12} {:>12}",
+                format!("{:+.2}", delta.current - delta.baseline),
+                format!("{:+.2}%", delta.change_pct)
+            );
+            println!(
+                "  {:<25} {:>12.2} {:>12.2} {}",
+                delta.name.bright_white(),
+                delta.baseline,
+                delta.current,
+                if delta.worsened {
+                    change.bright_red()
+                } else if delta.improved {
+                    change.bright_green()
+                } else {
+                    change.normal()
+                }
+            );
+        }
+
+        println!("\n{}", "═".repeat(82).bright_cyan());
         println!();
     }
 
+    /// Display each request of a dry run with its outcome and the
+    /// variables it extracted
+    pub fn display_dry_run(&self, steps: &[DryRunStep]) {
+        println!("\n{}", "Dry Run:".bright_green().bold());
+
+        for step in steps {
+            let outcome = match step.result.error {
+                Some(ref error) if step.result.is_failure() => format!("✗ {}", error).bright_red(),
+                Some(ref error) => format!("- {}", error).bright_yellow(),
+                None => "✓".bright_green(),
+            };
+            println!(
+                "  {} {} {} {} → {} ({})",
+                outcome,
+                step.name.bright_white(),
+                step.method,
+                step.url,
+                step.result.status_code,
+                self.latency(step.result.latency_ms as f64)
+            );
+            for (name, value) in &step.extracted {
+                println!("      {} = {}", name.bright_cyan(), value);
+            }
+        }
+    }
+
     /// Display error message
     pub fn display_error(&self, message: &str) {
         eprintln!("\n{} {}", "❌ Error:".bright_red().bold(), message);
@@ -182,24 +700,115 @@ impl TerminalUI {
 
     /// Display success message
     pub fn display_success(&self, message: &str) {
+        if self.is_hidden {
+            return;
+        }
         println!("\n{} {}", "✅".bright_green(), message);
     }
 }
 
+/// Table of one row per named group: phases or requests in a mix
+/// Workers to show in at most `limit` rows, slowest last latency first.
+/// When there are more workers, half the rows go to the slowest and the
+/// rest to the fastest.
+fn worker_rows(workers: &[WorkerStats], limit: usize) -> Vec<WorkerStats> {
+    let mut rows = workers.to_vec();
+    rows.sort_by(|a, b| {
+        b.last_latency_ms
+            .cmp(&a.last_latency_ms)
+            .then(a.worker_id.cmp(&b.worker_id))
+    });
+    if rows.len() > limit {
+        let slowest = limit.div_ceil(2);
+        rows.drain(slowest..rows.len() - (limit - slowest));
+    }
+    rows
+}
+
+fn display_breakdown(title: &str, label: &str, groups: &[(String, MetricsSummary)]) {
+    if groups.is_empty() {
+        return;
+    }
+
+    println!("{}", title.bright_green().bold());
+    println!(
+        "  {:<25} {:>10} {:>10} {:>10} {:>10}",
+        label.bright_white(),
+        "Requests",
+        "RPS",
+        "P95 (ms)",
+        "Errors %"
+    );
+    for (name, summary) in groups {
+        println!(
+            "  {:<25} {:>10} {:>10.2} {:>10} {:>10.2}",
+            name.bright_white(),
+            summary.total_requests,
+            summary.throughput_rps,
+            summary.p95_latency_ms,
+            summary.error_rate
+        );
+    }
+    println!();
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use chrono::Utc;
+    use std::collections::HashMap;
 
     #[test]
     fn test_terminal_ui_creation() {
-        let _ui = TerminalUI::new(30);
+        let _ui = TerminalUI::new(30, false, 4);
         // Test passes if no panic occurs
     }
 
+    #[test]
+    fn test_worker_rows() {
+        let workers: Vec<WorkerStats> = (0..30)
+            .map(|worker_id| WorkerStats {
+                worker_id,
+                last_latency_ms: worker_id as u64 * 10,
+                total_requests: 10,
+                error_count: 0,
+            })
+            .collect();
+
+        let rows = worker_rows(&workers[..3], MAX_WORKER_ROWS);
+        let ids: Vec<usize> = rows.iter().map(|w| w.worker_id).collect();
+        assert_eq!(ids, vec![2, 1, 0]);
+
+        let rows = worker_rows(&workers, MAX_WORKER_ROWS);
+        assert_eq!(rows.len(), MAX_WORKER_ROWS);
+        assert_eq!(rows[0].worker_id, 29);
+        assert_eq!(rows[9].worker_id, 20);
+        assert_eq!(rows[10].worker_id, 9);
+        assert_eq!(rows[19].worker_id, 0);
+    }
+
+    #[test]
+    fn test_quiet_progress_marks() {
+        let ui = TerminalUI::new(30, true, 0);
+        assert!(ui.is_quiet);
+
+        let marks: Vec<u64> = (1..=30).filter_map(|s| ui.progress_mark(s)).collect();
+        assert_eq!(marks, vec![10, 20, 30, 40, 50, 60, 70, 80, 90, 100]);
+    }
+
+    #[test]
+    fn test_latency_precision() {
+        let ui = TerminalUI::new(30, true, 0);
+        assert_eq!(ui.latency(1.25), "1ms");
+
+        let ui = ui.with_latency_precision(2);
+        assert_eq!(ui.latency(1.25), "1.25ms");
+        assert_eq!(ui.latency(3.0), "3.00ms");
+    }
+
     #[test]
     fn test_display_summary() {
-        let _ui = TerminalUI::new(30);
+        let _ui = TerminalUI::new(30, false, 0);
         let summary = MetricsSummary {
             total_requests: 1000,
             successful_requests: 950,
@@ -209,6 +818,8 @@ mod tests {
             min_latency_ms: 10,
             max_latency_ms: 500,
             mean_latency_ms: 85.5,
+            median_latency_ms: 85.5,
+            stddev_latency_ms: 0.0,
             p50_latency_ms: 75,
             p90_latency_ms: 150,
             p95_latency_ms: 200,
@@ -216,6 +827,32 @@ mod tests {
             error_rate: 5.0,
             start_time: Utc::now(),
             end_time: Utc::now(),
+            sampled_requests: None,
+            sample_rate: None,
+            error_breakdown: HashMap::new(),
+            timeout_count: 0,
+            skipped_requests: 0,
+            rate_limited_count: 0,
+            aborted_chains: 0,
+            chain_timeouts: 0,
+            throttled_requests: 0,
+            dns_cache_hits: 0,
+            clamped_histogram_values: 0,
+            hook_requests: 0,
+            failed_hook_requests: 0,
+            timing_percentiles: Vec::new(),
+            connection_reuse_rate: None,
+            first_error_time: None,
+            last_error_time: None,
+            sample_errors: Vec::new(),
+            gzip_responses: false,
+            latency_histogram: None,
+            max_outstanding: None,
+            dropped_iterations: None,
+            peak_concurrency: None,
+            apdex: None,
+            over_sla_count: None,
+            over_sla_percent: None,
         };
 
         // This will print to stdout, but we're just testing it doesn't panic
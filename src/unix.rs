@@ -0,0 +1,232 @@
+use anyhow::{bail, Result};
+use hyper::client::connect::{Connected, Connection};
+use hyper::service::Service;
+use hyper::{Body, Client, Uri};
+use reqwest::{Request, Response};
+use std::fs;
+use std::future::Future;
+use std::os::unix::fs::FileTypeExt;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::UnixStream;
+
+/// Placeholder host of requests bound for the socket; `.invalid` never
+/// resolves, so they cannot leak onto the network
+pub const HOST: &str = "unix.invalid";
+
+/// Socket path of a `unix:/path/to/socket` target. The target may carry a
+/// request path after the socket, e.g. `unix:/run/app.sock/health`; the
+/// socket is the shortest prefix that exists as one on disk.
+pub fn socket_path(target: &str) -> Option<String> {
+    let path = target.strip_prefix("unix:")?;
+    let path = Path::new(path.split('?').next().unwrap_or(path));
+
+    let mut prefixes: Vec<&Path> = path.ancestors().collect();
+    prefixes.reverse();
+    let socket = prefixes
+        .into_iter()
+        .find(|prefix| {
+            fs::metadata(prefix)
+                .map(|m| m.file_type().is_socket())
+                .unwrap_or(false)
+        })
+        .unwrap_or(path);
+    Some(socket.to_string_lossy().trim_end_matches('/').to_string())
+}
+
+/// HTTP/1 client for a single Unix domain socket, pooling its connections
+pub struct UnixClient {
+    socket: String,
+    client: Client<UnixConnector>,
+}
+
+impl UnixClient {
+    /// Client connecting to the socket at `socket`
+    pub fn new(socket: &str) -> Self {
+        Self {
+            socket: socket.to_string(),
+            client: Client::builder().build(UnixConnector {
+                path: PathBuf::from(socket),
+            }),
+        }
+    }
+
+    /// Map a URL of the socket path followed by the request path, e.g.
+    /// `unix:/run/app.sock/users?page=2`, to an HTTP URL on `HOST`
+    pub fn http_url(&self, url: &str) -> Result<String> {
+        let rest = url
+            .strip_prefix("unix:")
+            .and_then(|path| path.strip_prefix(&self.socket))
+            .filter(|rest| rest.is_empty() || rest.starts_with(['/', '?']));
+        let Some(rest) = rest else {
+            bail!(
+                "URL '{}' is not on the target socket '{}'",
+                url,
+                self.socket
+            );
+        };
+        Ok(format!("http://{}/{}", HOST, rest.trim_start_matches('/')))
+    }
+
+    /// Send a request built for `http_url`. Streamed (multipart) bodies are
+    /// not supported and responses are not decompressed.
+    pub async fn send(&self, request: Request, default_timeout: Duration) -> Result<Response> {
+        let url = request.url();
+        let uri: Uri = match url.query() {
+            Some(query) => format!("http://localhost{}?{}", url.path(), query),
+            None => format!("http://localhost{}", url.path()),
+        }
+        .parse()?;
+        let timeout = request.timeout().copied().unwrap_or(default_timeout);
+
+        let body = match request.body() {
+            Some(body) => match body.as_bytes() {
+                Some(bytes) => Body::from(bytes.to_vec()),
+                None => bail!("Streamed request bodies are not supported over a Unix socket"),
+            },
+            None => Body::empty(),
+        };
+        let mut http_request = hyper::Request::new(body);
+        *http_request.method_mut() = request.method().clone();
+        *http_request.uri_mut() = uri;
+        *http_request.headers_mut() = request.headers().clone();
+
+        let response = tokio::time::timeout(timeout, self.client.request(http_request)).await??;
+        Ok(Response::from(response))
+    }
+}
+
+/// Connects every request to the same socket, whatever its URI
+#[derive(Clone)]
+struct UnixConnector {
+    path: PathBuf,
+}
+
+impl Service<Uri> for UnixConnector {
+    type Response = UnixConnection;
+    type Error = std::io::Error;
+    type Future = Pin<Box<dyn Future<Output = std::io::Result<UnixConnection>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _uri: Uri) -> Self::Future {
+        let path = self.path.clone();
+        Box::pin(async move { Ok(UnixConnection(UnixStream::connect(path).await?)) })
+    }
+}
+
+/// Unix stream usable as a hyper client connection
+struct UnixConnection(UnixStream);
+
+impl Connection for UnixConnection {
+    fn connected(&self) -> Connected {
+        Connected::new()
+    }
+}
+
+impl AsyncRead for UnixConnection {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.0).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for UnixConnection {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.0).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.0).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.0).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::HttpClient;
+    use crate::config::Config;
+    use std::collections::HashMap;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[test]
+    fn test_socket_path() {
+        assert_eq!(
+            socket_path("unix:/flux.invalid/app.sock/").as_deref(),
+            Some("/flux.invalid/app.sock")
+        );
+        assert_eq!(socket_path("http://localhost"), None);
+
+        let client = UnixClient::new("/run/app.sock");
+        assert_eq!(
+            client.http_url("unix:/run/app.sock?a=1").unwrap(),
+            "http://unix.invalid/?a=1"
+        );
+        assert!(client.http_url("unix:/run/app.sockets/a").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_request_over_socket() {
+        let dir = std::env::temp_dir().join(format!("flux-unix-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let socket = dir.join("app.sock");
+        let _ = std::fs::remove_file(&socket);
+        let listener = tokio::net::UnixListener::bind(&socket).unwrap();
+        let target = format!("unix:{}/users?page=2", socket.display());
+        assert_eq!(
+            socket_path(&target).as_deref(),
+            Some(socket.to_str().unwrap())
+        );
+
+        // Echo the request head back as the body
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let head = String::from_utf8_lossy(&request).into_owned();
+            let response = format!(
+                "HTTP/1.1 201 Created\r\ncontent-length: {}\r\n\r\n{}",
+                head.len(),
+                head
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        let config: Config = format!("target: {}\noutput: {{ json: a, html: b }}\n", target)
+            .parse()
+            .unwrap();
+        let client = HttpClient::from_config(&config).unwrap();
+        let headers = HashMap::from([("X-Test".to_string(), "1".to_string())]);
+        let response = client
+            .execute_simple(&target, "GET", &headers, None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(response.status().as_u16(), 201);
+        let head = response.text().await.unwrap();
+        assert!(head.starts_with("GET /users?page=2 HTTP/1.1\r\n"));
+        assert!(head.to_lowercase().contains("x-test: 1"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
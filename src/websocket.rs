@@ -0,0 +1,162 @@
+use crate::metrics::{RequestError, TimeoutKind};
+use futures::{SinkExt, StreamExt};
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::time::Instant;
+use tokio::time::{timeout_at, Duration};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::{HeaderName, HeaderValue};
+use tokio_tungstenite::tungstenite::{Error, Message};
+
+/// Outcome of a WebSocket message exchange
+#[derive(Debug)]
+pub struct WebSocketReply {
+    /// Handshake status code (101 on success)
+    pub status: u16,
+    /// Text of the first data message received
+    pub text: String,
+    pub connect_ms: u64,
+    pub reply_ms: u64,
+}
+
+/// Resolve a step URL against the base target, switching http(s) to ws(s)
+pub fn websocket_url(base_url: Option<&str>, url: &str) -> String {
+    if url.starts_with("ws://") || url.starts_with("wss://") {
+        url.to_string()
+    } else if let Some(base) = base_url {
+        let base = base.trim_end_matches('/');
+        let base = match base.strip_prefix("http") {
+            Some(rest) => format!("ws{}", rest),
+            None => base.to_string(),
+        };
+        format!("{}{}", base, url)
+    } else {
+        url.to_string()
+    }
+}
+
+/// Connect, send `message` (if any), wait for the first data message and
+/// close. `timeout` bounds the whole exchange.
+pub async fn exchange(
+    url: &str,
+    headers: &HashMap<String, String>,
+    message: Option<String>,
+    timeout: Duration,
+) -> Result<WebSocketReply, RequestError> {
+    let deadline = tokio::time::Instant::now() + timeout;
+
+    let mut request = url
+        .into_client_request()
+        .map_err(|e| RequestError::Other(e.to_string()))?;
+    for (key, value) in headers {
+        let name = HeaderName::from_bytes(key.as_bytes())
+            .map_err(|e| RequestError::Other(e.to_string()))?;
+        let value = HeaderValue::from_str(value).map_err(|e| RequestError::Other(e.to_string()))?;
+        request.headers_mut().insert(name, value);
+    }
+
+    let connect_start = Instant::now();
+    let (mut stream, response) = timeout_at(deadline, tokio_tungstenite::connect_async(request))
+        .await
+        .map_err(|_| RequestError::Timeout {
+            kind: TimeoutKind::Connect,
+        })?
+        .map_err(classify_error)?;
+    let connect_ms = connect_start.elapsed().as_millis() as u64;
+
+    let reply_start = Instant::now();
+    let text = timeout_at(deadline, async {
+        if let Some(message) = message {
+            stream.send(Message::Text(message)).await?;
+        }
+
+        while let Some(message) = stream.next().await {
+            match message? {
+                Message::Text(text) => return Ok(text),
+                Message::Binary(data) => return Ok(String::from_utf8_lossy(&data).into_owned()),
+                Message::Close(_) => break,
+                _ => {}
+            }
+        }
+
+        Err(Error::ConnectionClosed)
+    })
+    .await
+    .map_err(|_| RequestError::Timeout {
+        kind: TimeoutKind::Request,
+    })?
+    .map_err(classify_error)?;
+    let reply_ms = reply_start.elapsed().as_millis() as u64;
+
+    // The reply is already measured; a failed close handshake doesn't matter
+    let _ = stream.close(None).await;
+
+    Ok(WebSocketReply {
+        status: response.status().as_u16(),
+        text,
+        connect_ms,
+        reply_ms,
+    })
+}
+
+/// Map a WebSocket error to the shared error categories
+fn classify_error(error: Error) -> RequestError {
+    match error {
+        Error::Http(response) => RequestError::HttpError {
+            status: response.status().as_u16(),
+        },
+        Error::Io(e) if e.kind() == ErrorKind::ConnectionRefused => RequestError::ConnectionRefused,
+        Error::Tls(e) => RequestError::TlsError(e.to_string()),
+        e => RequestError::Other(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_websocket_url() {
+        assert_eq!(
+            websocket_url(Some("https://api.example.com/"), "/ws"),
+            "wss://api.example.com/ws"
+        );
+        assert_eq!(
+            websocket_url(Some("http://localhost:8080"), "/ws"),
+            "ws://localhost:8080/ws"
+        );
+        assert_eq!(
+            websocket_url(Some("http://localhost:8080"), "ws://other/ws"),
+            "ws://other/ws"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_exchange_echo() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/", listener.local_addr().unwrap());
+
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut stream = tokio_tungstenite::accept_async(socket).await.unwrap();
+            if let Some(Ok(Message::Text(text))) = stream.next().await {
+                stream
+                    .send(Message::Text(format!(r#"{{"echo": "{}"}}"#, text)))
+                    .await
+                    .unwrap();
+            }
+        });
+
+        let reply = exchange(
+            &url,
+            &HashMap::new(),
+            Some("hello".to_string()),
+            Duration::from_secs(5),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(reply.status, 101);
+        assert_eq!(reply.text, r#"{"echo": "hello"}"#);
+    }
+}